@@ -0,0 +1,3 @@
+-- Add down migration script here
+-- pgcrypto 可能也被其他資料庫物件使用，這裡只移除本專案建立的函式
+DROP FUNCTION IF EXISTS gen_random_uuid_v7();
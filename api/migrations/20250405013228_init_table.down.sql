@@ -0,0 +1,11 @@
+-- Add down migration script here
+DROP TABLE attendance_students;
+DROP TABLE attendance_records;
+DROP TABLE announcements;
+DROP TABLE student_exams;
+DROP TABLE student_infos;
+DROP TABLE member_family_relations;
+DROP TABLE teacher_assignments;
+DROP TABLE students;
+DROP TABLE teachers;
+DROP TABLE members;
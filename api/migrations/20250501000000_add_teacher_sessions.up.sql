@@ -0,0 +1,14 @@
+-- Add migration script here
+CREATE TABLE teacher_sessions
+(
+    id         UUID PRIMARY KEY   DEFAULT gen_random_uuid_v7(),
+    teacher_id UUID      NOT NULL,
+    ip_address text,
+    user_agent text,
+    created_at TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    expires_at TIMESTAMP NOT NULL,
+    revoked_at TIMESTAMP,
+    CONSTRAINT fk_teacher_id FOREIGN KEY (teacher_id) REFERENCES teachers (member_id) ON DELETE CASCADE
+);
+CREATE INDEX idx_teacher_sessions_teacher_id
+    ON teacher_sessions (teacher_id);
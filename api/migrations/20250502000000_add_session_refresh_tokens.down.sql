@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP TABLE session_refresh_tokens;
@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE session_refresh_tokens
+(
+    id         UUID PRIMARY KEY   DEFAULT gen_random_uuid_v7(),
+    session_id UUID      NOT NULL,
+    token_hash text      NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    expires_at TIMESTAMP NOT NULL,
+    used_at    TIMESTAMP,
+    CONSTRAINT uq_session_refresh_tokens_hash UNIQUE (token_hash),
+    CONSTRAINT fk_session_id FOREIGN KEY (session_id) REFERENCES teacher_sessions (id) ON DELETE CASCADE
+);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE teachers
+    DROP COLUMN failed_login_attempts,
+    DROP COLUMN locked_until;
@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TABLE teachers
+    ADD COLUMN failed_login_attempts int2 NOT NULL DEFAULT 0,
+    ADD COLUMN locked_until          TIMESTAMP;
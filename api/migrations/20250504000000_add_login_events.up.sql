@@ -0,0 +1,14 @@
+-- Add migration script here
+CREATE TABLE login_events
+(
+    id         UUID PRIMARY KEY   DEFAULT gen_random_uuid_v7(),
+    teacher_id UUID,
+    username   text,
+    event_type int2      NOT NULL,
+    ip_address text,
+    user_agent text,
+    created_at TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    CONSTRAINT fk_teacher_id FOREIGN KEY (teacher_id) REFERENCES teachers (member_id) ON DELETE SET NULL
+);
+CREATE INDEX idx_login_events_teacher_id_created_at
+    ON login_events (teacher_id, created_at);
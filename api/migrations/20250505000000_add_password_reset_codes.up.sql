@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE password_reset_codes
+(
+    id         UUID PRIMARY KEY   DEFAULT gen_random_uuid_v7(),
+    teacher_id UUID      NOT NULL,
+    issued_by  UUID      NOT NULL,
+    code_hash  text      NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    expires_at TIMESTAMP NOT NULL,
+    used_at    TIMESTAMP,
+    CONSTRAINT fk_teacher_id FOREIGN KEY (teacher_id) REFERENCES teachers (member_id) ON DELETE CASCADE,
+    CONSTRAINT fk_issued_by FOREIGN KEY (issued_by) REFERENCES teachers (member_id) ON DELETE CASCADE
+);
+CREATE INDEX idx_password_reset_codes_teacher_id
+    ON password_reset_codes (teacher_id);
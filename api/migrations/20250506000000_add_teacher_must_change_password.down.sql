@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE teachers
+    DROP COLUMN must_change_password;
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE teachers
+    ADD COLUMN must_change_password BOOLEAN NOT NULL DEFAULT FALSE;
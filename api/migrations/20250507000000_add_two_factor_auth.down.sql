@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP TABLE system_settings;
+DROP TABLE two_factor_recovery_codes;
+
+ALTER TABLE teachers
+    DROP COLUMN totp_secret,
+    DROP COLUMN totp_enabled_at,
+    DROP COLUMN totp_last_used_step;
@@ -0,0 +1,24 @@
+-- Add migration script here
+ALTER TABLE teachers
+    ADD COLUMN totp_secret         text,
+    ADD COLUMN totp_enabled_at     TIMESTAMP,
+    ADD COLUMN totp_last_used_step int8;
+
+CREATE TABLE two_factor_recovery_codes
+(
+    id         UUID PRIMARY KEY   DEFAULT gen_random_uuid_v7(),
+    teacher_id UUID      NOT NULL,
+    code_hash  text      NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    used_at    TIMESTAMP,
+    CONSTRAINT fk_teacher_id FOREIGN KEY (teacher_id) REFERENCES teachers (member_id) ON DELETE CASCADE
+);
+CREATE INDEX idx_two_factor_recovery_codes_teacher_id
+    ON two_factor_recovery_codes (teacher_id);
+
+CREATE TABLE system_settings
+(
+    key        text PRIMARY KEY,
+    value      text      NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT timezone('utc', now())
+);
@@ -0,0 +1,16 @@
+-- Add down migration script here
+-- 還原成原本的兩種角色，主管理員以外的角色都視為一般管理員
+ALTER TABLE teachers
+    ADD COLUMN role_type int2;
+
+UPDATE teachers
+SET role_type = CASE roles.name WHEN 'super_admin' THEN 0 ELSE 1 END
+FROM roles
+WHERE roles.id = teachers.role_id;
+
+ALTER TABLE teachers
+    ALTER COLUMN role_type SET NOT NULL,
+    DROP COLUMN role_id;
+
+DROP TABLE role_permissions;
+DROP TABLE roles;
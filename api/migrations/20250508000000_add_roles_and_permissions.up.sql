@@ -0,0 +1,62 @@
+-- Add migration script here
+CREATE TABLE roles
+(
+    id          UUID PRIMARY KEY   DEFAULT gen_random_uuid_v7(),
+    name        text      NOT NULL UNIQUE,
+    description text,
+    is_system   BOOLEAN   NOT NULL DEFAULT FALSE,
+    created_at  TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    updated_at  TIMESTAMP NOT NULL DEFAULT timezone('utc', now())
+);
+
+CREATE TABLE role_permissions
+(
+    role_id    UUID NOT NULL,
+    permission text NOT NULL,
+    PRIMARY KEY (role_id, permission),
+    CONSTRAINT fk_role_id FOREIGN KEY (role_id) REFERENCES roles (id) ON DELETE CASCADE
+);
+
+-- 以原本的兩種角色建立系統內建角色，權限與原本的行為相同
+INSERT INTO roles (name, description, is_system)
+VALUES ('super_admin', '主管理員', TRUE),
+       ('admin', '管理員', TRUE);
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT roles.id, permission
+FROM roles,
+     unnest(ARRAY [
+         'teachers.read', 'teachers.manage', 'members.read', 'members.write',
+         'students.read', 'students.read_pii', 'students.write',
+         'attendance.read', 'attendance.write',
+         'announcements.write', 'announcements.manage',
+         'roles.manage', 'settings.manage'
+         ]) AS permission
+WHERE roles.name = 'super_admin';
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT roles.id, permission
+FROM roles,
+     unnest(ARRAY [
+         'teachers.read', 'members.read', 'members.write',
+         'students.read', 'students.read_pii', 'students.write',
+         'attendance.read', 'attendance.write',
+         'announcements.write'
+         ]) AS permission
+WHERE roles.name = 'admin';
+
+ALTER TABLE teachers
+    ADD COLUMN role_id UUID;
+
+UPDATE teachers
+SET role_id = (SELECT roles.id
+               FROM roles
+               WHERE roles.name = CASE teachers.role_type WHEN 0 THEN 'super_admin' ELSE 'admin' END);
+
+ALTER TABLE teachers
+    ALTER COLUMN role_id SET NOT NULL,
+    ADD CONSTRAINT fk_role_id FOREIGN KEY (role_id) REFERENCES roles (id),
+    DROP COLUMN role_type;
+
+CREATE INDEX idx_teachers_role_id
+    ON teachers (role_id);
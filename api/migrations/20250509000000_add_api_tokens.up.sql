@@ -0,0 +1,27 @@
+-- Add migration script here
+CREATE TABLE api_tokens
+(
+    id           UUID PRIMARY KEY   DEFAULT gen_random_uuid_v7(),
+    teacher_id   UUID      NOT NULL,
+    created_by   UUID      NOT NULL,
+    name         text      NOT NULL,
+    token_hash   text      NOT NULL UNIQUE,
+    token_prefix text      NOT NULL,
+    created_at   TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    expires_at   TIMESTAMP NOT NULL,
+    last_used_at TIMESTAMP,
+    revoked_at   TIMESTAMP,
+    CONSTRAINT fk_teacher_id FOREIGN KEY (teacher_id) REFERENCES teachers (member_id) ON DELETE CASCADE,
+    CONSTRAINT fk_created_by FOREIGN KEY (created_by) REFERENCES teachers (member_id) ON DELETE CASCADE
+);
+CREATE INDEX idx_api_tokens_teacher_id
+    ON api_tokens (teacher_id);
+
+-- token 可使用的權限範圍，實際生效的是與擁有者角色權限的交集
+CREATE TABLE api_token_permissions
+(
+    api_token_id UUID NOT NULL,
+    permission   text NOT NULL,
+    PRIMARY KEY (api_token_id, permission),
+    CONSTRAINT fk_api_token_id FOREIGN KEY (api_token_id) REFERENCES api_tokens (id) ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+-- Add down migration script here
+ALTER TABLE announcements
+    DROP COLUMN audience;
+
+DROP INDEX idx_member_family_relations_relative_id;
+DROP TABLE guardian_sessions;
+DROP TABLE guardians;
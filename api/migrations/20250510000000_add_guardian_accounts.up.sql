@@ -0,0 +1,35 @@
+-- Add migration script here
+CREATE TABLE guardians
+(
+    member_id            UUID PRIMARY KEY,
+    username             text      NOT NULL UNIQUE,
+    password             text      NOT NULL,
+    must_change_password BOOLEAN   NOT NULL DEFAULT TRUE,
+    last_login_at        TIMESTAMP,
+    created_at           TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    updated_at           TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    deleted_at           TIMESTAMP,
+    CONSTRAINT fk_member_id FOREIGN KEY (member_id) REFERENCES members (id) ON DELETE CASCADE
+);
+
+CREATE TABLE guardian_sessions
+(
+    id          UUID PRIMARY KEY   DEFAULT gen_random_uuid_v7(),
+    guardian_id UUID      NOT NULL,
+    ip_address  text,
+    user_agent  text,
+    created_at  TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    expires_at  TIMESTAMP NOT NULL,
+    revoked_at  TIMESTAMP,
+    CONSTRAINT fk_guardian_id FOREIGN KEY (guardian_id) REFERENCES guardians (member_id) ON DELETE CASCADE
+);
+CREATE INDEX idx_guardian_sessions_guardian_id
+    ON guardian_sessions (guardian_id);
+
+-- 家長透過 member_family_relations 中 relative_id 為自己的紀錄找到子女
+CREATE INDEX idx_member_family_relations_relative_id
+    ON member_family_relations (relative_id);
+
+-- staff：只給教職員、guardians：只給家長、everyone：兩者皆可看到
+ALTER TABLE announcements
+    ADD COLUMN audience text NOT NULL DEFAULT 'staff';
@@ -0,0 +1,16 @@
+-- Add migration script here
+-- 教職員與外部身分提供者帳號的對應。
+-- 管理者可以先只填電子郵件，第一次以 OpenID Connect 登入時再記錄 issuer 與 subject，
+-- 之後即使對方在身分提供者更換了電子郵件，仍以 subject 對應。
+CREATE TABLE teacher_oidc_identities
+(
+    teacher_id UUID PRIMARY KEY,
+    issuer     text,
+    subject    text,
+    email      text UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    updated_at TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    CONSTRAINT fk_teacher_id FOREIGN KEY (teacher_id) REFERENCES teachers (member_id) ON DELETE CASCADE,
+    CONSTRAINT uq_teacher_oidc_identities_subject UNIQUE (issuer, subject),
+    CONSTRAINT chk_teacher_oidc_identities_target CHECK (subject IS NOT NULL OR email IS NOT NULL)
+);
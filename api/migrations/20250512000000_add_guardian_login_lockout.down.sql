@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE guardian_login_events;
+
+ALTER TABLE guardians
+    DROP COLUMN failed_login_attempts,
+    DROP COLUMN locked_until;
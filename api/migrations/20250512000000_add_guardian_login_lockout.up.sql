@@ -0,0 +1,19 @@
+-- Add migration script here
+ALTER TABLE guardians
+    ADD COLUMN failed_login_attempts int2 NOT NULL DEFAULT 0,
+    ADD COLUMN locked_until          TIMESTAMP;
+
+-- 與 login_events 相同的結構，家長的登入紀錄不與教職員的混在一起
+CREATE TABLE guardian_login_events
+(
+    id          UUID PRIMARY KEY   DEFAULT gen_random_uuid_v7(),
+    guardian_id UUID,
+    username    text,
+    event_type  int2      NOT NULL,
+    ip_address  text,
+    user_agent  text,
+    created_at  TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+    CONSTRAINT fk_guardian_id FOREIGN KEY (guardian_id) REFERENCES guardians (member_id) ON DELETE SET NULL
+);
+CREATE INDEX idx_guardian_login_events_guardian_id_created_at
+    ON guardian_login_events (guardian_id, created_at);
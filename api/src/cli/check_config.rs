@@ -0,0 +1,13 @@
+use crate::config::{ConfigSource, CONFIG};
+use crate::util::check_jwt_keys;
+
+/// 設定在啟動時已載入並驗證，這裡再檢查需要讀取檔案的 JWT 金鑰。
+pub(super) fn run(source: &ConfigSource) -> Result<(), String> {
+    check_jwt_keys(&CONFIG.auth).map_err(|e| format!("Invalid JWT keys: {}", e))?;
+
+    println!("config file: {}", source.config_file()?.display());
+    println!("environment: {}", CONFIG.environment);
+    println!("configuration is valid");
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use crate::models::{
+    AnnouncementView, AppResponse, GuardianView, MemberView, StudentScope, StudentView, TeacherView,
+};
+use crate::services::prelude::{
+    find_student_views, get_announcements, get_guardians, get_members, get_teachers,
+};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use clap::Args;
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use tracing::info;
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// 輸出的檔案路徑，未指定時輸出到標準輸出
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+/// 匯出的內容與 API 回傳的格式相同，學生資料包含個人資料，請妥善保管
+#[derive(Debug, Serialize)]
+struct DataExport {
+    exported_at: DateTime<Utc>,
+    members: Vec<MemberView>,
+    teachers: Vec<TeacherView>,
+    students: Vec<StudentView>,
+    guardians: Vec<GuardianView>,
+    announcements: Vec<AnnouncementView>,
+}
+
+pub(super) async fn run(db: &DatabaseConnection, args: ExportArgs) -> Result<(), String> {
+    let export = DataExport {
+        exported_at: Utc::now(),
+        members: response_data(get_members(State(db.clone())).await)?,
+        teachers: response_data(get_teachers(State(db.clone())).await)?,
+        students: find_student_views(db, &StudentScope::All, true)
+            .await
+            .map_err(|(_, response)| response.0.message)?,
+        guardians: response_data(get_guardians(State(db.clone())).await)?,
+        announcements: response_data(get_announcements(State(db.clone())).await)?,
+    };
+
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    match &args.output {
+        Some(path) => {
+            fs::write(path, json)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            info!(
+                path = %path.display(),
+                members = export.members.len(),
+                students = export.students.len(),
+                "data exported"
+            );
+        }
+        None => writeln!(io::stdout(), "{}", json).map_err(|e| e.to_string())?,
+    }
+
+    Ok(())
+}
+
+/// 取出 handler 回應中的資料，失敗時改用回應的訊息作為錯誤
+fn response_data<T>(
+    result: Result<Json<AppResponse<T>>, (StatusCode, Json<AppResponse>)>,
+) -> Result<T, String>
+where
+    T: Serialize,
+{
+    match result {
+        Ok(Json(response)) => response.data.ok_or(response.message),
+        Err((_, Json(response))) => Err(response.message),
+    }
+}
@@ -0,0 +1,68 @@
+use crate::db;
+use clap::Subcommand;
+use sea_orm::DatabaseConnection;
+use tracing::info;
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommand {
+    /// 列出各 migration 是否已套用
+    Status,
+    /// 套用所有尚未套用的 migration
+    Up,
+    /// 還原 migration，預設只還原最近一個
+    Down {
+        /// 還原最近幾個 migration
+        #[arg(long, default_value_t = 1, conflicts_with = "target")]
+        steps: usize,
+        /// 還原所有版本大於此值的 migration，0 代表全部還原
+        #[arg(long)]
+        target: Option<i64>,
+    },
+}
+
+pub(super) async fn run(conn: &DatabaseConnection, command: MigrateCommand) -> Result<(), String> {
+    match command {
+        MigrateCommand::Status => {
+            db::migration::ensure_migrations_table(conn).await?;
+            let status = db::migration::migration_status(conn).await?;
+
+            println!("{:<16}{:<10}description", "version", "state");
+            for migration in db::migration::MIGRATOR
+                .iter()
+                .filter(|migration| !migration.migration_type.is_down_migration())
+            {
+                let state = if status.dirty == Some(migration.version) {
+                    "failed"
+                } else if status.pending.contains(&migration.version) {
+                    "pending"
+                } else if status.modified.contains(&migration.version) {
+                    "modified"
+                } else {
+                    "applied"
+                };
+                println!(
+                    "{:<16}{:<10}{}",
+                    migration.version, state, migration.description
+                );
+            }
+            for version in &status.unknown {
+                println!("{:<16}{:<10}(not in this binary)", version, "unknown");
+            }
+            Ok(())
+        }
+        MigrateCommand::Up => {
+            let applied = db::migration::run_migrations(conn).await?;
+            info!(applied = ?applied, "migrations applied");
+            Ok(())
+        }
+        MigrateCommand::Down { steps, target } => {
+            let target = match target {
+                Some(target) => target,
+                None => db::migration::undo_target(conn, steps).await?,
+            };
+            let reverted = db::migration::undo_migrations(conn, target).await?;
+            info!(reverted = ?reverted, "migrations reverted");
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,110 @@
+mod check_config;
+mod export;
+mod migrate;
+mod serve;
+mod teacher;
+
+use crate::config::ConfigSource;
+use crate::db;
+use crate::util::ConsoleOutput;
+use clap::{Parser, Subcommand};
+use sea_orm::DatabaseConnection;
+use std::path::PathBuf;
+use tracing::error;
+
+pub use export::ExportArgs;
+pub use migrate::MigrateCommand;
+pub use teacher::{CreateAdminArgs, ResetPasswordArgs};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// 設定檔路徑，同一目錄下的 base.yaml 會先載入作為共用設定
+    #[arg(long, env = "APP_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+    /// 未指定設定檔時載入 configs/<env>.yaml，預設依編譯模式為 development 或 production
+    #[arg(long, env = "APP_ENV", global = true)]
+    pub env: Option<String>,
+    /// 未指定時啟動伺服器
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    pub fn config_source(&self) -> ConfigSource {
+        ConfigSource {
+            file: self.config.clone(),
+            environment: self.env.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 啟動伺服器
+    Serve,
+    /// 管理資料庫 migration
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+    /// 建立主管理員帳號
+    CreateAdmin(CreateAdminArgs),
+    /// 重設教職員的密碼並解除登入鎖定
+    ResetPassword(ResetPasswordArgs),
+    /// 列出所有教職員
+    ListTeachers,
+    /// 將成員、教職員、學生、家長與公告資料匯出為 JSON
+    Export(ExportArgs),
+    /// 檢查設定檔與 JWT 金鑰，不連線資料庫
+    CheckConfig,
+}
+
+impl Command {
+    /// 伺服器的日誌寫到標準輸出，其他指令的結果才寫到標準輸出
+    pub fn console_output(&self) -> ConsoleOutput {
+        match self {
+            Command::Serve => ConsoleOutput::Stdout,
+            _ => ConsoleOutput::Stderr,
+        }
+    }
+}
+
+/// 執行指令，設定與日誌需已初始化。
+pub async fn run(command: Command, source: &ConfigSource) -> Result<(), String> {
+    if let Command::CheckConfig = command {
+        return check_config::run(source);
+    }
+
+    let conn = db::connection::db_connection()
+        .await
+        .map_err(|e| format!("failed to connect to database: {}", e))?;
+
+    // 伺服器依停止時限自行關閉連線池，不在這裡等待進行中的請求歸還連線
+    let closes_pool = !matches!(command, Command::Serve);
+    let result = execute(&conn, command).await;
+
+    if closes_pool {
+        if let Err(e) = conn.close().await {
+            error!(error = %e, "failed to close database pool");
+        }
+    }
+    result
+}
+
+async fn execute(conn: &DatabaseConnection, command: Command) -> Result<(), String> {
+    if !matches!(command, Command::Serve | Command::Migrate { .. }) {
+        // 管理指令不自動套用 migration，有尚未套用的 migration 或結構不相容時拒絕執行
+        db::migration::ensure_schema(conn, false).await?;
+    }
+
+    match command {
+        Command::Serve => serve::run(conn).await,
+        Command::Migrate { command } => migrate::run(conn, command).await,
+        Command::CreateAdmin(args) => teacher::create_admin(conn, args).await,
+        Command::ResetPassword(args) => teacher::reset_password(conn, args).await,
+        Command::ListTeachers => teacher::list_teachers(conn).await,
+        Command::Export(args) => export::run(conn, args).await,
+        Command::CheckConfig => unreachable!("check-config does not connect to the database"),
+    }
+}
@@ -0,0 +1,133 @@
+use crate::config::CONFIG;
+use crate::{db, routes, util};
+use sea_orm::DatabaseConnection;
+use std::future;
+use std::net::SocketAddr;
+use tokio::signal;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+pub(super) async fn run(conn: &DatabaseConnection) -> Result<(), String> {
+    if CONFIG.metrics.enabled {
+        util::init_metrics().map_err(|e| format!("failed to initialize metrics: {}", e))?;
+    }
+    util::init_jwt_keys();
+
+    db::migration::ensure_schema(conn, CONFIG.database.run_migrations_on_startup).await?;
+    db::connection::init(conn).await?;
+
+    // 初始化路由
+    let app = routes::new_route(conn.clone());
+
+    // 啟動伺服器
+    let addr = format!("{}:{}", CONFIG.server.address, CONFIG.server.port);
+    let addr: SocketAddr = addr
+        .parse()
+        .map_err(|e| format!("invalid server address {}: {}", addr, e))?;
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("failed to bind server address {}: {}", addr, e))?;
+    info!(address = %addr, "server listening");
+
+    if let Some(metrics_addr) = CONFIG
+        .metrics
+        .address
+        .as_ref()
+        .filter(|_| CONFIG.metrics.enabled)
+    {
+        let metrics_listener = tokio::net::TcpListener::bind(metrics_addr)
+            .await
+            .map_err(|e| format!("failed to bind metrics address {}: {}", metrics_addr, e))?;
+        let metrics_app = routes::metrics_route(conn.clone());
+
+        info!(address = %metrics_addr, "metrics listening");
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(metrics_listener, metrics_app).await {
+                error!(error = %e, "metrics server error");
+            }
+        });
+    }
+
+    // 收到停止訊號後不再接受新連線，等待進行中的請求完成，超過時限才強制結束
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(None);
+    let deadline_rx = shutdown_tx.subscribe();
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(Some(Instant::now() + CONFIG.server.shutdown_timeout()));
+    });
+    let drain_deadline = async {
+        let deadline = shutdown_rx
+            .wait_for(Option::is_some)
+            .await
+            .ok()
+            .and_then(|deadline| *deadline);
+        match deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline).await,
+            None => future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        result = server => {
+            if let Err(e) = result {
+                error!(error = %e, "server error");
+            }
+        }
+        _ = drain_deadline => {
+            warn!(
+                timeout_seconds = CONFIG.server.shutdown_timeout_seconds,
+                "requests still in flight after shutdown timeout, aborting"
+            );
+        }
+    }
+
+    // 強制結束後仍在執行的請求會佔住連線，關閉連線池最多只等到原本的時限
+    let deadline = (*deadline_rx.borrow())
+        .unwrap_or_else(|| Instant::now() + CONFIG.server.shutdown_timeout());
+    match tokio::time::timeout_at(deadline, conn.close_by_ref()).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => error!(error = %e, "failed to close database pool"),
+        Err(_) => {
+            warn!("database connections still in use after shutdown timeout, skipping pool close")
+        }
+    }
+
+    info!("server stopped");
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = signal::ctrl_c().await {
+            error!(error = %e, "failed to listen for ctrl-c");
+            future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(e) => {
+                error!(error = %e, "failed to listen for SIGTERM");
+                future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!(signal = "SIGINT", "shutdown requested"),
+        _ = terminate => info!(signal = "SIGTERM", "shutdown requested"),
+    }
+}
@@ -0,0 +1,238 @@
+use crate::config::CONFIG;
+use crate::db::connection::create_super_admin;
+use crate::db::entities::{members, roles, teachers, two_factor_recovery_codes};
+use crate::services::prelude::revoke_sessions_by_teacher;
+use crate::util::{check_password_strength, generate_temporary_password, hash_password};
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use clap::Args;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use tracing::info;
+
+/// 產生臨時密碼時最多嘗試的次數，避免密碼政策設定得無法滿足時無限重試
+const MAX_PASSWORD_ATTEMPTS: usize = 100;
+
+#[derive(Debug, Args)]
+pub struct CreateAdminArgs {
+    /// 登入帳號
+    pub username: String,
+    /// 顯示名稱，預設使用設定中的 `auth.default_name`
+    #[arg(long)]
+    pub name: Option<String>,
+    /// 從標準輸入讀取密碼；未指定時產生一組臨時密碼，首次登入時須變更
+    #[arg(long)]
+    pub password_stdin: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ResetPasswordArgs {
+    /// 登入帳號
+    pub username: String,
+    /// 從標準輸入讀取密碼；未指定時產生一組臨時密碼，首次登入時須變更
+    #[arg(long)]
+    pub password_stdin: bool,
+    /// 同時停用兩步驟驗證並刪除復原碼，用於遺失驗證器的情況
+    #[arg(long)]
+    pub reset_two_factor: bool,
+}
+
+pub(super) async fn create_admin(
+    db: &DatabaseConnection,
+    args: CreateAdminArgs,
+) -> Result<(), String> {
+    if args.username.chars().count() < 4 {
+        return Err("使用者名稱至少需要4個字元".to_string());
+    }
+    if find_teacher_by_username(db, &args.username)
+        .await?
+        .is_some()
+    {
+        return Err(format!("帳號 {} 已被使用", args.username));
+    }
+
+    let name = args
+        .name
+        .unwrap_or_else(|| CONFIG.auth.default_name.clone());
+    let (password, generated) =
+        read_or_generate_password(args.password_stdin, &[&args.username, &name])?;
+    let password_hash = hash_password(&password).map_err(|_| "無法產生密碼雜湊")?;
+
+    let teacher = create_super_admin(db, &name, &args.username, password_hash, generated).await?;
+
+    info!(username = %teacher.username, "super admin created");
+    println!("已建立主管理員 {}", teacher.username);
+    if generated {
+        println!("臨時密碼：{}（首次登入時須變更）", password);
+    }
+
+    Ok(())
+}
+
+pub(super) async fn reset_password(
+    db: &DatabaseConnection,
+    args: ResetPasswordArgs,
+) -> Result<(), String> {
+    let teacher = find_teacher_by_username(db, &args.username)
+        .await?
+        .ok_or_else(|| format!("找不到帳號 {}", args.username))?;
+    let member = members::Entity::find_by_id(teacher.member_id)
+        .one(db)
+        .await
+        .map_err(|e| format!("資料庫異常，異常原因：{}", e))?
+        .ok_or("找不到對應的成員資料")?;
+
+    let (password, generated) =
+        read_or_generate_password(args.password_stdin, &[&teacher.username, &member.name])?;
+    let password_hash = hash_password(&password).map_err(|_| "無法產生密碼雜湊")?;
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| format!("系統異常，異常原因：{}", e))?;
+
+    let teacher_id = teacher.member_id;
+    let username = teacher.username.clone();
+    let mut teacher: teachers::ActiveModel = teacher.into();
+    teacher.password = Set(password_hash);
+    teacher.must_change_password = Set(generated);
+    teacher.failed_login_attempts = Set(0);
+    teacher.locked_until = Set(None);
+    if args.reset_two_factor {
+        teacher.totp_secret = Set(None);
+        teacher.totp_enabled_at = Set(None);
+        teacher.totp_last_used_step = Set(None);
+    }
+    teacher.updated_at = Set(Utc::now().naive_utc());
+    teacher
+        .update(&txn)
+        .await
+        .map_err(|e| format!("無法更新教職員，異常原因：{}", e))?;
+
+    if args.reset_two_factor {
+        two_factor_recovery_codes::Entity::delete_many()
+            .filter(two_factor_recovery_codes::Column::TeacherId.eq(teacher_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| format!("無法刪除復原碼，異常原因：{}", e))?;
+    }
+
+    // 密碼被重設代表帳號可能已外洩，讓所有已登入的裝置重新登入
+    revoke_sessions_by_teacher(&txn, teacher_id)
+        .await
+        .map_err(|(_, response)| response.0.message)?;
+
+    txn.commit()
+        .await
+        .map_err(|e| format!("系統異常，原因：{}", e))?;
+
+    info!(
+        username = %username,
+        reset_two_factor = args.reset_two_factor,
+        "teacher password reset"
+    );
+    println!("已重設 {} 的密碼並解除登入鎖定", username);
+    if args.reset_two_factor {
+        println!("已停用兩步驟驗證");
+    }
+    if generated {
+        println!("臨時密碼：{}（首次登入時須變更）", password);
+    }
+
+    Ok(())
+}
+
+pub(super) async fn list_teachers(db: &DatabaseConnection) -> Result<(), String> {
+    let roles: HashMap<_, _> = roles::Entity::find()
+        .all(db)
+        .await
+        .map_err(|e| format!("資料庫異常，異常原因：{}", e))?
+        .into_iter()
+        .map(|role| (role.id, role.name))
+        .collect();
+
+    let teachers_with_members = teachers::Entity::find()
+        .filter(teachers::Column::DeletedAt.is_null())
+        .order_by_asc(teachers::Column::Username)
+        .find_also_related(members::Entity)
+        .all(db)
+        .await
+        .map_err(|e| format!("資料庫異常，異常原因：{}", e))?;
+
+    let now = Utc::now().naive_utc();
+    println!(
+        "{:<20}{:<16}{:<8}{:<30}{:<20}name",
+        "username", "role", "2fa", "status", "last login"
+    );
+    for (teacher, member) in teachers_with_members {
+        let status = match teacher.locked_until {
+            Some(locked_until) if locked_until > now => {
+                format!("locked until {}", format_time(locked_until))
+            }
+            _ if teacher.must_change_password => "must change password".to_string(),
+            _ => "active".to_string(),
+        };
+
+        println!(
+            "{:<20}{:<16}{:<8}{:<30}{:<20}{}",
+            teacher.username,
+            roles.get(&teacher.role_id).map_or("-", String::as_str),
+            match teacher.totp_enabled_at {
+                Some(_) => "on",
+                None => "off",
+            },
+            status,
+            teacher.last_login_at.map_or("-".to_string(), format_time),
+            member.map_or("-".to_string(), |member| member.name),
+        );
+    }
+
+    Ok(())
+}
+
+async fn find_teacher_by_username(
+    db: &DatabaseConnection,
+    username: &str,
+) -> Result<Option<teachers::Model>, String> {
+    teachers::Entity::find()
+        .filter(teachers::Column::Username.eq(username))
+        .filter(teachers::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| format!("資料庫異常，異常原因：{}", e))
+}
+
+/// 從標準輸入讀取密碼，或產生符合密碼政策的臨時密碼，回傳密碼與是否為產生的密碼。
+fn read_or_generate_password(
+    from_stdin: bool,
+    user_inputs: &[&str],
+) -> Result<(String, bool), String> {
+    if from_stdin {
+        let mut password = String::new();
+        io::stdin()
+            .lock()
+            .read_line(&mut password)
+            .map_err(|e| format!("無法讀取密碼：{}", e))?;
+        let password = password.trim_end_matches(['\r', '\n']).to_string();
+        check_password_strength(&password, user_inputs)?;
+        return Ok((password, false));
+    }
+
+    let length = CONFIG.auth.password_policy.min_length.max(16);
+    (0..MAX_PASSWORD_ATTEMPTS)
+        .map(|_| generate_temporary_password(length))
+        .find(|password| check_password_strength(password, user_inputs).is_ok())
+        .map(|password| (password, true))
+        .ok_or_else(|| "無法產生符合密碼政策的臨時密碼，請改用 --password-stdin".to_string())
+}
+
+fn format_time(time: NaiveDateTime) -> String {
+    Local
+        .from_utc_datetime(&time)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
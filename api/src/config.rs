@@ -1,5 +1,12 @@
-use serde::Deserialize;
-use std::{fs, sync::LazyLock};
+use axum::http::{HeaderName, Method};
+use ipnet::IpNet;
+use serde::{Deserialize, Deserializer};
+use serde_yml::{Mapping, Value};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, OnceLock};
+use std::{env, fs, time::Duration};
+use tracing_subscriber::EnvFilter;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
@@ -8,13 +15,74 @@ pub struct Config {
     pub database: DatabaseConfig,
     pub logger: LoggerConfig,
     pub auth: AuthConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// 另外開一個位址提供 `/metrics`，例如只綁定內網；未設定時與 API 使用同一個位址
+    pub address: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerConfig {
     pub address: String,
     pub port: u16,
-    pub cors: String,
+    pub cors: CorsConfig,
+    /// 收到停止訊號後，等待進行中的請求完成的秒數
+    #[serde(default = "default_shutdown_timeout_seconds")]
+    pub shutdown_timeout_seconds: u64,
+    /// 反向代理的 IP 或網段，只有來自這些位址的請求才會採用 `X-Forwarded-For`
+    #[serde(default, deserialize_with = "deserialize_string_list")]
+    pub trusted_proxies: Vec<String>,
+    /// 在 `/api/docs` 提供 Swagger UI，`/api/openapi.json` 則一律提供
+    #[serde(default)]
+    pub swagger_ui: bool,
+}
+
+impl ServerConfig {
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_seconds)
+    }
+
+    /// 解析 `trusted_proxies`，無效的項目已在啟動時的檢查中回報。
+    pub fn trusted_proxy_networks(&self) -> Vec<IpNet> {
+        self.trusted_proxies
+            .iter()
+            .filter_map(|proxy| parse_ip_net(proxy).ok())
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CorsConfig {
+    /// 允許的前端網址，`https://*.example.org` 可比對任意一層以上的子網域。
+    /// 也可以用逗號分隔的字串設定，方便從環境變數帶入
+    #[serde(deserialize_with = "deserialize_string_list")]
+    pub allowed_origins: Vec<String>,
+    #[serde(
+        default = "default_cors_allowed_methods",
+        deserialize_with = "deserialize_string_list"
+    )]
+    pub allowed_methods: Vec<String>,
+    /// CSRF 標頭一律允許，不需要列在這裡
+    #[serde(
+        default = "default_cors_allowed_headers",
+        deserialize_with = "deserialize_string_list"
+    )]
+    pub allowed_headers: Vec<String>,
+}
+
+impl CorsConfig {
+    /// 瀏覽器送出的 `Origin` 是否符合任一個允許的網址。
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|pattern| origin_matches(pattern, origin))
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -24,35 +92,1206 @@ pub struct DatabaseConfig {
     pub name: String,
     pub user: String,
     pub password: String,
+    #[serde(default = "default_database_max_connections")]
+    pub max_connections: u32,
+    #[serde(default = "default_database_min_connections")]
+    pub min_connections: u32,
+    #[serde(default = "default_database_connect_timeout_seconds")]
+    pub connect_timeout_seconds: u64,
+    /// 閒置超過此秒數的連線會被關閉，但不會低於 `min_connections`
+    #[serde(default = "default_database_idle_timeout_seconds")]
+    pub idle_timeout_seconds: u64,
+    /// 單一 SQL 的執行時限，未設定時使用資料庫本身的設定
+    #[serde(default)]
+    pub statement_timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub ssl_mode: DatabaseSslMode,
+    /// 驗證資料庫憑證用的 CA 憑證檔案，搭配 `verify-ca` 或 `verify-full` 使用
+    #[serde(default)]
+    pub ssl_root_cert: Option<String>,
+    /// 顯示在 `pg_stat_activity` 中，方便辨識連線來源
+    #[serde(default = "default_database_application_name")]
+    pub application_name: String,
+    #[serde(default)]
+    pub retry: DatabaseRetryConfig,
+    /// 啟動時自動套用尚未套用的 migration，關閉時需先執行 `migrate up`，否則拒絕啟動
+    #[serde(default)]
+    pub run_migrations_on_startup: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum DatabaseSslMode {
+    Disable,
+    Allow,
+    #[default]
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct DatabaseRetryConfig {
+    /// 啟動時連線資料庫的嘗試次數，容器中資料庫可能比 API 晚就緒
+    pub max_attempts: u32,
+    /// 第一次重試前等待的毫秒數，之後每次加倍
+    pub initial_backoff_millis: u64,
+    pub max_backoff_seconds: u64,
+}
+
+impl Default for DatabaseRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 10,
+            initial_backoff_millis: 500,
+            max_backoff_seconds: 30,
+        }
+    }
+}
+
+impl DatabaseRetryConfig {
+    /// 第 `attempt` 次失敗後（從 1 開始）要等待多久再重試
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let millis = self
+            .initial_backoff_millis
+            .saturating_mul(2u64.saturating_pow(exponent))
+            .min(self.max_backoff_seconds.saturating_mul(1000));
+        Duration::from_millis(millis)
+    }
+}
+
+impl DatabaseConfig {
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout_seconds)
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.idle_timeout_seconds)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct LoggerConfig {
+    /// 日誌等級，也可以使用 env-filter 的語法個別設定，例如 `info,sqlx=warn`
     pub level: String,
-    pub format: String,
+    pub format: LogFormat,
+    /// 未設定時只輸出到標準輸出
+    #[serde(default)]
+    pub file: Option<LogFileConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Console,
+    /// 每行一筆 JSON，供日誌收集程式解析
+    Json,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LogFileConfig {
+    pub directory: String,
+    /// 檔名前綴，輪替時會在後面加上日期或時間
+    #[serde(default = "default_log_file_prefix")]
+    pub prefix: String,
+    #[serde(default)]
+    pub rotation: LogRotation,
+    /// 保留的檔案數量，未設定時不刪除舊檔
+    #[serde(default)]
+    pub max_files: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    #[default]
+    Daily,
+    Never,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct AuthConfig {
-    pub jwt_secret: String,
+    /// 舊版的單一 HS256 密鑰。未設定 `jwt.keys` 時作為唯一的簽章金鑰，
+    /// 開啟 `jwt.accept_legacy_tokens` 時也用來驗證標頭中沒有 `kid` 的 token
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
+    #[serde(default)]
+    pub jwt: JwtConfig,
     pub default_name: String,
     pub default_username: String,
     pub default_password: String,
+    #[serde(default = "default_access_token_minutes")]
+    pub access_token_minutes: u64,
+    #[serde(default = "default_refresh_token_days")]
+    pub refresh_token_days: u64,
+    #[serde(default = "default_password_reset_code_minutes")]
+    pub password_reset_code_minutes: u64,
+    /// 家長登入後可使用的時數，家長帳號沒有 refresh token，到期後需重新登入
+    #[serde(default = "default_guardian_session_hours")]
+    pub guardian_session_hours: u64,
+    #[serde(default)]
+    pub login_throttle: LoginThrottleConfig,
+    #[serde(default)]
+    pub password_hashing: PasswordHashingConfig,
+    #[serde(default)]
+    pub password_policy: PasswordPolicyConfig,
+    #[serde(default)]
+    pub two_factor: TwoFactorConfig,
+    /// 未設定時停用 OpenID Connect 登入，只能使用帳號密碼
+    #[serde(default)]
+    pub oidc: Option<OidcConfig>,
 }
 
-pub static CONFIG: LazyLock<Config> =
-    LazyLock::new(|| load_config().expect("Failed to load initial config"));
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct JwtConfig {
+    /// 用來簽發新 token 的金鑰，其餘金鑰只用來驗證，從清單移除即代表停用
+    pub current_kid: Option<String>,
+    pub keys: Vec<JwtKeyConfig>,
+    /// 以 `jwt_secret` 驗證加入 `kid` 之前發出的 token，只在升級後舊的 access token
+    /// 過期之前暫時開啟，關閉後一律拒絕沒有 `kid` 的 token
+    pub accept_legacy_tokens: bool,
+}
 
-pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
-    let file_path = match cfg!(debug_assertions) {
-        true => "configs/development.yaml",
-        false => "configs/production.yaml",
-    };
+#[derive(Debug, Deserialize, Clone)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    pub algorithm: JwtAlgorithm,
+    /// HS256 使用的密鑰
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// RS256 / EdDSA 的 PEM 私鑰檔案，只用來驗證的舊金鑰可以省略
+    #[serde(default)]
+    pub private_key_file: Option<String>,
+    /// RS256 / EdDSA 的 PEM 公鑰檔案
+    #[serde(default)]
+    pub public_key_file: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum JwtAlgorithm {
+    HS256,
+    RS256,
+    EdDSA,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LoginThrottleConfig {
+    /// 同一帳號連續失敗幾次後開始鎖定
+    pub max_failed_attempts: u32,
+    /// 第一次鎖定的秒數，之後每多失敗一次加倍
+    pub lockout_base_seconds: u64,
+    pub lockout_max_seconds: u64,
+    /// 同一個 IP 在統計區間內允許的失敗次數
+    pub ip_max_failed_attempts: u32,
+    pub ip_window_seconds: u64,
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        Self {
+            max_failed_attempts: 5,
+            lockout_base_seconds: 60,
+            lockout_max_seconds: 60 * 60,
+            ip_max_failed_attempts: 20,
+            ip_window_seconds: 15 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct PasswordHashingConfig {
+    /// 新密碼使用的演算法，以其他演算法或參數保存的密碼會在下次登入時重新雜湊
+    pub algorithm: PasswordAlgorithm,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub bcrypt_cost: u32,
+}
+
+impl Default for PasswordHashingConfig {
+    /// argon2id 的預設值採用 OWASP 建議的 19 MiB、2 次迭代、平行度 1
+    fn default() -> Self {
+        Self {
+            algorithm: PasswordAlgorithm::Argon2id,
+            argon2_memory_kib: 19 * 1024,
+            argon2_iterations: 2,
+            argon2_parallelism: 1,
+            bcrypt_cost: 12,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordAlgorithm {
+    Argon2id,
+    Bcrypt,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct PasswordPolicyConfig {
+    pub min_length: usize,
+    pub max_length: usize,
+    /// 小寫、大寫、數字與符號中至少要包含幾種
+    pub min_character_classes: usize,
+    /// 拒絕常見的弱密碼
+    pub reject_common: bool,
+    /// 拒絕包含帳號名稱的密碼
+    pub reject_username: bool,
+}
+
+impl Default for PasswordPolicyConfig {
+    fn default() -> Self {
+        Self {
+            min_length: 10,
+            max_length: 128,
+            min_character_classes: 3,
+            reject_common: true,
+            reject_username: true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct OidcConfig {
+    /// 身分提供者的 issuer，會從 `{issuer}/.well-known/openid-configuration` 取得端點
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// 在身分提供者註冊的回呼網址，需指向 `/api/login/oidc/callback`
+    pub redirect_uri: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+    /// 登入成功後導向的前端網址
+    pub post_login_redirect: String,
+    /// 前端的登入頁，登入失敗時附上 `error` 參數，需要兩步驟驗證時附上 `two_factor` 參數
+    pub login_page: String,
+    /// 是否允許以已驗證的電子郵件對應到尚未綁定 subject 的教職員
+    #[serde(default = "default_oidc_match_email")]
+    pub match_email: bool,
+    /// 從跳轉到身分提供者到回呼之間的時限
+    #[serde(default = "default_oidc_flow_minutes")]
+    pub flow_minutes: u64,
+}
+
+impl OidcConfig {
+    pub fn flow_lifetime(&self) -> Duration {
+        Duration::from_secs(self.flow_minutes * 60)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct TwoFactorConfig {
+    /// 顯示在驗證器 App 中的發行者名稱
+    pub issuer: String,
+    /// 密碼驗證通過後，輸入驗證碼的時限
+    pub challenge_minutes: u64,
+}
+
+impl Default for TwoFactorConfig {
+    fn default() -> Self {
+        Self {
+            issuer: "Church Community Center".to_string(),
+            challenge_minutes: 5,
+        }
+    }
+}
+
+impl TwoFactorConfig {
+    pub fn challenge_lifetime(&self) -> Duration {
+        Duration::from_secs(self.challenge_minutes * 60)
+    }
+}
+
+impl LoginThrottleConfig {
+    /// 依照累計失敗次數計算鎖定時間，未達門檻時回傳 `None`。
+    pub fn lockout_duration(&self, failed_attempts: u32) -> Option<Duration> {
+        let exponent = failed_attempts.checked_sub(self.max_failed_attempts)?;
+        let seconds = self
+            .lockout_base_seconds
+            .saturating_mul(2u64.saturating_pow(exponent))
+            .min(self.lockout_max_seconds);
+        Some(Duration::from_secs(seconds))
+    }
+}
+
+impl AuthConfig {
+    pub fn access_token_lifetime(&self) -> Duration {
+        Duration::from_secs(self.access_token_minutes * 60)
+    }
+
+    pub fn refresh_token_lifetime(&self) -> Duration {
+        Duration::from_secs(self.refresh_token_days * 24 * 60 * 60)
+    }
+
+    pub fn password_reset_code_lifetime(&self) -> Duration {
+        Duration::from_secs(self.password_reset_code_minutes * 60)
+    }
+
+    pub fn guardian_session_lifetime(&self) -> Duration {
+        Duration::from_secs(self.guardian_session_hours * 60 * 60)
+    }
+}
+
+fn default_shutdown_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_cors_allowed_methods() -> Vec<String> {
+    ["GET", "POST", "PUT", "DELETE", "OPTIONS"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_cors_allowed_headers() -> Vec<String> {
+    ["content-type", "authorization", "accept"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn deserialize_string_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringList {
+        Joined(String),
+        List(Vec<String>),
+    }
+
+    Ok(match StringList::deserialize(deserializer)? {
+        StringList::Joined(value) => value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect(),
+        StringList::List(items) => items,
+    })
+}
+
+fn default_database_max_connections() -> u32 {
+    10
+}
+
+fn default_database_min_connections() -> u32 {
+    1
+}
+
+fn default_database_connect_timeout_seconds() -> u64 {
+    10
+}
+
+fn default_database_idle_timeout_seconds() -> u64 {
+    10 * 60
+}
+
+fn default_database_application_name() -> String {
+    "church-community-center-api".to_string()
+}
+
+fn default_log_file_prefix() -> String {
+    "api.log".to_string()
+}
+
+fn default_access_token_minutes() -> u64 {
+    15
+}
+
+fn default_refresh_token_days() -> u64 {
+    14
+}
+
+fn default_password_reset_code_minutes() -> u64 {
+    60
+}
+
+fn default_guardian_session_hours() -> u64 {
+    12
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    vec![
+        "openid".to_string(),
+        "email".to_string(),
+        "profile".to_string(),
+    ]
+}
+
+fn default_oidc_match_email() -> bool {
+    true
+}
 
-    let content = fs::read_to_string(file_path)?;
+fn default_oidc_flow_minutes() -> u64 {
+    10
+}
+
+/// 與環境設定檔放在同一目錄的共用設定，環境設定檔中的值會覆蓋它
+const BASE_CONFIG_FILE: &str = "base.yaml";
+const CONFIG_DIR: &str = "configs";
+/// 以 `APP__SECTION__KEY` 形式的環境變數覆蓋設定值
+const ENV_OVERRIDE_PREFIX: &str = "APP__";
+const ENV_OVERRIDE_SEPARATOR: &str = "__";
+
+static INITIAL_CONFIG: OnceLock<Config> = OnceLock::new();
+
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| match INITIAL_CONFIG.get() {
+    Some(config) => config.clone(),
+    None => load_config(&ConfigSource::from_env())
+        .unwrap_or_else(|e| panic!("Failed to load config: {}", e)),
+});
+
+/// 要載入哪一份設定檔，兩者都沒有指定時依編譯模式選擇 development 或 production。
+#[derive(Debug, Default, Clone)]
+pub struct ConfigSource {
+    /// 指定的設定檔路徑，優先於 `environment`
+    pub file: Option<PathBuf>,
+    /// 環境名稱，對應 `configs/<environment>.yaml`
+    pub environment: Option<String>,
+}
 
-    let interpolated_content = shellexpand::full(&content)?.to_string();
+impl ConfigSource {
+    pub fn from_env() -> Self {
+        Self {
+            file: env::var_os("APP_CONFIG").map(PathBuf::from),
+            environment: env::var("APP_ENV").ok(),
+        }
+    }
+
+    /// 實際要載入的設定檔路徑
+    pub fn config_file(&self) -> Result<PathBuf, String> {
+        if let Some(file) = &self.file {
+            return match file.is_file() {
+                true => Ok(file.clone()),
+                false => Err(format!("Config file {} does not exist", file.display())),
+            };
+        }
+
+        let environment = match &self.environment {
+            Some(environment) => environment.as_str(),
+            None if cfg!(debug_assertions) => "development",
+            None => "production",
+        };
+        let file_name = format!("{}.yaml", environment);
+
+        // 先找工作目錄下的 configs，找不到時再找執行檔旁邊的 configs
+        let candidates = [
+            Some(PathBuf::from(CONFIG_DIR)),
+            env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(|dir| dir.join(CONFIG_DIR))),
+        ];
+
+        candidates
+            .into_iter()
+            .flatten()
+            .map(|dir| dir.join(&file_name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                format!(
+                    "Config file {} was not found in ./{} or next to the executable",
+                    file_name, CONFIG_DIR
+                )
+            })
+    }
+}
+
+/// 載入並驗證設定，之後透過 `CONFIG` 取用，需在第一次使用 `CONFIG` 之前呼叫。
+pub fn init_config(source: &ConfigSource) -> Result<(), String> {
+    let config = load_config(source)?;
+
+    INITIAL_CONFIG
+        .set(config)
+        .map_err(|_| "Config has already been initialized".to_string())?;
+    LazyLock::force(&CONFIG);
+
+    Ok(())
+}
+
+/// 依序套用 `base.yaml`、環境設定檔與 `APP__` 環境變數，後者覆蓋前者。
+pub fn load_config(source: &ConfigSource) -> Result<Config, String> {
+    let file_path = source.config_file()?;
+    let base_path = file_path.with_file_name(BASE_CONFIG_FILE);
+
+    let mut value = match base_path.is_file() && base_path != file_path {
+        true => read_config_file(&base_path)?,
+        false => Value::Null,
+    };
+    merge_values(&mut value, read_config_file(&file_path)?);
+    apply_env_overrides(&mut value, env::vars())?;
+
+    let config: Config = serde_yml::from_value(value)
+        .map_err(|e| format!("Invalid config {}: {}", file_path.display(), e))?;
+    config.validate()?;
 
-    let config: Config = serde_yml::from_str(&interpolated_content)?;
     Ok(config)
 }
+
+fn read_config_file(path: &Path) -> Result<Value, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+
+    let interpolated_content = shellexpand::full(&content)
+        .map_err(|e| format!("Failed to expand config {}: {}", path.display(), e))?;
+
+    serde_yml::from_str(&interpolated_content)
+        .map_err(|e| format!("Failed to parse config {}: {}", path.display(), e))
+}
+
+/// 逐層合併設定，只有兩邊都是區塊時才往下合併，其餘情況由 `overlay` 取代。
+fn merge_values(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (_, Value::Null) => {}
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn apply_env_overrides(
+    root: &mut Value,
+    vars: impl Iterator<Item = (String, String)>,
+) -> Result<(), String> {
+    for (name, raw) in vars {
+        let Some(path) = name.strip_prefix(ENV_OVERRIDE_PREFIX) else {
+            continue;
+        };
+        let keys: Vec<String> = path
+            .split(ENV_OVERRIDE_SEPARATOR)
+            .map(|key| key.to_lowercase())
+            .collect();
+        if keys.iter().any(|key| key.is_empty()) {
+            return Err(format!("Invalid config override {}", name));
+        }
+
+        let (last, sections) = keys.split_last().expect("split always yields a key");
+        let mut current = &mut *root;
+        for key in sections {
+            if current.is_null() {
+                *current = Value::Mapping(Mapping::new());
+            }
+            let Value::Mapping(mapping) = current else {
+                return Err(format!("{} overrides a value that is not a section", name));
+            };
+            current = mapping
+                .entry(Value::String(key.clone()))
+                .or_insert(Value::Null);
+        }
+
+        if current.is_null() {
+            *current = Value::Mapping(Mapping::new());
+        }
+        let Value::Mapping(mapping) = current else {
+            return Err(format!("{} overrides a value that is not a section", name));
+        };
+
+        let key = Value::String(last.clone());
+        // 原本是字串的設定維持字串，避免像 `123456` 這樣的密鑰被解析成數字
+        let value = match mapping.get(&key) {
+            Some(Value::String(_)) => Value::String(raw),
+            _ if raw.is_empty() => Value::String(raw),
+            _ => serde_yml::from_str(&raw).unwrap_or(Value::String(raw)),
+        };
+        mapping.insert(key, value);
+    }
+
+    Ok(())
+}
+
+impl Config {
+    /// 檢查無法單靠型別表達的限制，一次列出所有問題。
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = Vec::new();
+
+        if format!("{}:{}", self.server.address, self.server.port)
+            .parse::<SocketAddr>()
+            .is_err()
+        {
+            problems.push(format!(
+                "server.address `{}` is not a valid IP address",
+                self.server.address
+            ));
+        }
+        let cors = &self.server.cors;
+        if cors.allowed_origins.is_empty() {
+            problems.push("server.cors.allowed_origins must not be empty".to_string());
+        }
+        for origin in &cors.allowed_origins {
+            if let Err(reason) = check_origin(origin) {
+                problems.push(format!(
+                    "server.cors.allowed_origins `{}` is not a valid origin: {}",
+                    origin, reason
+                ));
+            }
+        }
+        for proxy in &self.server.trusted_proxies {
+            if let Err(e) = parse_ip_net(proxy) {
+                problems.push(format!(
+                    "server.trusted_proxies `{}` is not a valid IP address or network: {}",
+                    proxy, e
+                ));
+            }
+        }
+        for method in &cors.allowed_methods {
+            if Method::from_bytes(method.as_bytes()).is_err() {
+                problems.push(format!(
+                    "server.cors.allowed_methods `{}` is not a valid method",
+                    method
+                ));
+            }
+        }
+        for header in &cors.allowed_headers {
+            if HeaderName::from_bytes(header.as_bytes()).is_err() {
+                problems.push(format!(
+                    "server.cors.allowed_headers `{}` is not a valid header name",
+                    header
+                ));
+            }
+        }
+
+        if let Err(e) = EnvFilter::try_new(&self.logger.level) {
+            problems.push(format!(
+                "logger.level `{}` is not a valid filter: {}",
+                self.logger.level, e
+            ));
+        }
+        if self
+            .logger
+            .file
+            .as_ref()
+            .is_some_and(|file| file.directory.trim().is_empty())
+        {
+            problems.push("logger.file.directory must not be empty".to_string());
+        }
+
+        for (key, value) in [
+            ("database.host", &self.database.host),
+            ("database.name", &self.database.name),
+            ("database.user", &self.database.user),
+            ("auth.default_username", &self.auth.default_username),
+            ("auth.default_password", &self.auth.default_password),
+        ] {
+            if value.trim().is_empty() {
+                problems.push(format!("{} must not be empty", key));
+            }
+        }
+
+        if let Some(address) = &self.metrics.address {
+            if address.parse::<SocketAddr>().is_err() {
+                problems.push(format!(
+                    "metrics.address `{}` is not a valid socket address",
+                    address
+                ));
+            }
+        }
+
+        let database = &self.database;
+        if database.max_connections == 0 || database.min_connections > database.max_connections {
+            problems.push(
+                "database.max_connections must be positive and not less than min_connections"
+                    .to_string(),
+            );
+        }
+        if database.connect_timeout_seconds == 0 {
+            problems.push("database.connect_timeout_seconds must be positive".to_string());
+        }
+        if database.statement_timeout_seconds == Some(0) {
+            problems.push(
+                "database.statement_timeout_seconds must be positive, omit it to disable"
+                    .to_string(),
+            );
+        }
+        if let Some(cert) = &database.ssl_root_cert {
+            if !Path::new(cert).is_file() {
+                problems.push(format!("database.ssl_root_cert {} does not exist", cert));
+            }
+        }
+        if database.retry.max_attempts == 0 {
+            problems.push("database.retry.max_attempts must be at least 1".to_string());
+        }
+
+        match &self.auth.jwt_secret {
+            Some(secret) if secret.trim().is_empty() => {
+                problems.push("auth.jwt_secret must not be empty".to_string());
+            }
+            None if self.auth.jwt.keys.is_empty() => {
+                problems
+                    .push("auth.jwt_secret is required unless auth.jwt.keys is set".to_string());
+            }
+            None if self.auth.jwt.accept_legacy_tokens => {
+                problems.push(
+                    "auth.jwt_secret is required when auth.jwt.accept_legacy_tokens is enabled"
+                        .to_string(),
+                );
+            }
+            _ => {}
+        }
+
+        if self.auth.access_token_minutes == 0 || self.auth.refresh_token_days == 0 {
+            problems.push(
+                "auth.access_token_minutes and auth.refresh_token_days must be positive"
+                    .to_string(),
+            );
+        }
+
+        let policy = &self.auth.password_policy;
+        if policy.min_length == 0 || policy.min_length > policy.max_length {
+            problems.push(
+                "auth.password_policy.min_length must be between 1 and max_length".to_string(),
+            );
+        }
+        if policy.min_character_classes > 4 {
+            problems
+                .push("auth.password_policy.min_character_classes must be at most 4".to_string());
+        }
+
+        let hashing = &self.auth.password_hashing;
+        if let Err(e) = argon2::Params::new(
+            hashing.argon2_memory_kib,
+            hashing.argon2_iterations,
+            hashing.argon2_parallelism,
+            None,
+        ) {
+            problems.push(format!(
+                "auth.password_hashing has invalid argon2 parameters: {}",
+                e
+            ));
+        }
+        if !(4..=31).contains(&hashing.bcrypt_cost) {
+            problems.push("auth.password_hashing.bcrypt_cost must be between 4 and 31".to_string());
+        }
+
+        if let Some(oidc) = &self.auth.oidc {
+            for (key, value) in [
+                ("auth.oidc.issuer", &oidc.issuer),
+                ("auth.oidc.redirect_uri", &oidc.redirect_uri),
+                ("auth.oidc.post_login_redirect", &oidc.post_login_redirect),
+                ("auth.oidc.login_page", &oidc.login_page),
+            ] {
+                if !value.starts_with("http://") && !value.starts_with("https://") {
+                    problems.push(format!("{} `{}` must be an http(s) URL", key, value));
+                }
+            }
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(format!(
+                "Invalid configuration:\n  - {}",
+                problems.join("\n  - ")
+            )),
+        }
+    }
+}
+
+/// 接受單一 IP（例如 `10.0.0.1`）或 CIDR 網段（例如 `10.0.0.0/8`）。
+fn parse_ip_net(value: &str) -> Result<IpNet, String> {
+    value
+        .parse::<IpNet>()
+        .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|e| e.to_string())
+}
+
+/// 瀏覽器送出的 `Origin` 只有 scheme、主機與連接埠，不能帶路徑或結尾的斜線。
+/// 主機最前面可以是 `*.`，代表任意子網域。
+fn check_origin(origin: &str) -> Result<(), &'static str> {
+    let Some((scheme, host)) = origin.split_once("://") else {
+        return Err("missing http:// or https://");
+    };
+
+    if scheme != "http" && scheme != "https" {
+        return Err("scheme must be http or https");
+    }
+    let host = host.strip_prefix("*.").unwrap_or(host);
+    if host.is_empty() || host.starts_with(['.', ':']) {
+        return Err("missing host");
+    }
+    if host.contains('*') {
+        return Err("`*` is only allowed as the first label, e.g. https://*.example.org");
+    }
+    if host.contains(['/', '?', '#']) {
+        return Err("must not contain a path, query or trailing slash");
+    }
+    if origin.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("must not contain whitespace");
+    }
+
+    Ok(())
+}
+
+fn origin_matches(pattern: &str, origin: &str) -> bool {
+    let Some((scheme, suffix)) = pattern.split_once("://*.") else {
+        return pattern.eq_ignore_ascii_case(origin);
+    };
+
+    // 萬用字元比對的是子網域，網域本身與連接埠仍需完全相同
+    let Some(host) = origin
+        .split_once("://")
+        .filter(|(origin_scheme, _)| origin_scheme.eq_ignore_ascii_case(scheme))
+        .map(|(_, host)| host.to_ascii_lowercase())
+    else {
+        return false;
+    };
+
+    host.strip_suffix(&suffix.to_ascii_lowercase())
+        .and_then(|subdomain| subdomain.strip_suffix('.'))
+        .is_some_and(|subdomain| {
+            !subdomain.is_empty()
+                && subdomain.split('.').all(|label| {
+                    !label.is_empty()
+                        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockout_starts_at_max_failed_attempts_and_doubles() {
+        let config = LoginThrottleConfig {
+            max_failed_attempts: 5,
+            lockout_base_seconds: 60,
+            lockout_max_seconds: 60 * 60,
+            ..Default::default()
+        };
+
+        assert_eq!(config.lockout_duration(0), None);
+        assert_eq!(config.lockout_duration(4), None);
+        assert_eq!(config.lockout_duration(5), Some(Duration::from_secs(60)));
+        assert_eq!(config.lockout_duration(6), Some(Duration::from_secs(120)));
+        assert_eq!(config.lockout_duration(8), Some(Duration::from_secs(480)));
+    }
+
+    #[test]
+    fn lockout_is_capped_without_overflow() {
+        let config = LoginThrottleConfig {
+            max_failed_attempts: 5,
+            lockout_base_seconds: 60,
+            lockout_max_seconds: 60 * 60,
+            ..Default::default()
+        };
+
+        assert_eq!(config.lockout_duration(11), Some(Duration::from_secs(3600)));
+        assert_eq!(
+            config.lockout_duration(u32::MAX),
+            Some(Duration::from_secs(3600))
+        );
+    }
+
+    #[test]
+    fn parses_trusted_proxies() {
+        assert_eq!(
+            parse_ip_net("10.0.0.1").unwrap(),
+            "10.0.0.1/32".parse::<IpNet>().unwrap()
+        );
+        assert_eq!(
+            parse_ip_net("::1").unwrap(),
+            "::1/128".parse::<IpNet>().unwrap()
+        );
+        assert!(parse_ip_net("10.0.0.0/8").is_ok());
+        assert!(parse_ip_net("proxy.lan").is_err());
+        assert!(parse_ip_net("10.0.0.0/33").is_err());
+    }
+
+    fn yaml(content: &str) -> Value {
+        serde_yml::from_str(content).unwrap()
+    }
+
+    fn env_vars(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    const MINIMAL_CONFIG: &str = r#"
+environment: test
+server:
+  address: 127.0.0.1
+  port: 8080
+  cors:
+    allowed_origins: ["https://app.example.org", "https://*.example.org"]
+database:
+  host: localhost
+  port: 5432
+  name: church-community-center
+  user: postgres
+  password: postgres
+logger:
+  level: info
+  format: console
+auth:
+  jwt_secret: secret
+  default_name: 管理員
+  default_username: admin
+  default_password: password
+"#;
+
+    fn config_with(overrides: &[(&str, &str)]) -> Config {
+        let mut value = yaml(MINIMAL_CONFIG);
+        apply_env_overrides(&mut value, env_vars(overrides)).unwrap();
+        serde_yml::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn merges_sections_recursively() {
+        let mut base = yaml(
+            r#"
+server:
+  port: 8080
+  cors:
+    allowed_origins: ["https://a.example.org"]
+    allowed_headers: [content-type]
+logger:
+  level: debug
+"#,
+        );
+        merge_values(
+            &mut base,
+            yaml(
+                r#"
+server:
+  cors:
+    allowed_origins: ["https://b.example.org"]
+logger:
+auth:
+  jwt_secret: secret
+"#,
+            ),
+        );
+
+        assert_eq!(
+            base,
+            yaml(
+                r#"
+server:
+  port: 8080
+  cors:
+    allowed_origins: ["https://b.example.org"]
+    allowed_headers: [content-type]
+logger:
+  level: debug
+auth:
+  jwt_secret: secret
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn merge_replaces_values_that_are_not_both_sections() {
+        let mut base = yaml("logger:\n  level: debug\n");
+        merge_values(&mut base, yaml("logger: off\n"));
+        assert_eq!(base, yaml("logger: off\n"));
+
+        let mut base = yaml("metrics: false\n");
+        merge_values(&mut base, yaml("metrics:\n  enabled: true\n"));
+        assert_eq!(base, yaml("metrics:\n  enabled: true\n"));
+    }
+
+    #[test]
+    fn env_overrides_set_nested_values() {
+        let mut value = yaml("server:\n  port: 8080\nauth:\n  jwt_secret: secret\n");
+        apply_env_overrides(
+            &mut value,
+            env_vars(&[
+                ("APP__SERVER__PORT", "9090"),
+                ("APP__AUTH__JWT_SECRET", "123456"),
+                ("APP__DATABASE__RUN_MIGRATIONS_ON_STARTUP", "true"),
+                (
+                    "APP__SERVER__CORS__ALLOWED_ORIGINS",
+                    "https://a.example.org",
+                ),
+                ("APP__AUTH__OIDC__CLIENT_SECRET", ""),
+                ("PATH", "/usr/bin"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            value,
+            yaml(
+                r#"
+server:
+  port: 9090
+  cors:
+    allowed_origins: "https://a.example.org"
+auth:
+  jwt_secret: "123456"
+  oidc:
+    client_secret: ""
+database:
+  run_migrations_on_startup: true
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_env_overrides() {
+        let mut value = yaml("server:\n  port: 8080\n");
+
+        for name in ["APP__", "APP__SERVER____PORT", "APP__SERVER__"] {
+            assert!(apply_env_overrides(&mut value, env_vars(&[(name, "1")])).is_err());
+        }
+        assert!(
+            apply_env_overrides(&mut value, env_vars(&[("APP__SERVER__PORT__NUMBER", "1")]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn minimal_config_is_valid() {
+        assert!(config_with(&[]).validate().is_ok());
+    }
+
+    #[test]
+    fn validate_reports_every_problem() {
+        let config = config_with(&[
+            ("APP__SERVER__ADDRESS", "localhost"),
+            ("APP__SERVER__TRUSTED_PROXIES", "10.0.0.0/8,proxy.lan"),
+            ("APP__LOGGER__LEVEL", "info,sqlx=nope=1"),
+            ("APP__DATABASE__MAX_CONNECTIONS", "0"),
+            ("APP__AUTH__JWT_SECRET", " "),
+            ("APP__AUTH__PASSWORD_HASHING__BCRYPT_COST", "3"),
+        ]);
+        let problems = config.validate().unwrap_err();
+
+        for key in [
+            "server.address",
+            "server.trusted_proxies `proxy.lan`",
+            "logger.level",
+            "database.max_connections",
+            "auth.jwt_secret",
+            "auth.password_hashing.bcrypt_cost",
+        ] {
+            assert!(problems.contains(key), "{} not in {}", key, problems);
+        }
+        assert!(!problems.contains("10.0.0.0/8"));
+    }
+
+    #[test]
+    fn validate_checks_jwt_settings() {
+        let mut config = config_with(&[]);
+        config.auth.jwt_secret = None;
+        assert!(config.validate().is_err());
+
+        config.auth.jwt.keys = vec![JwtKeyConfig {
+            kid: "2025-05".to_string(),
+            algorithm: JwtAlgorithm::HS256,
+            secret: Some("secret".to_string()),
+            private_key_file: None,
+            public_key_file: None,
+        }];
+        assert!(config.validate().is_ok());
+
+        config.auth.jwt.accept_legacy_tokens = true;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn accepts_valid_origins() {
+        for origin in [
+            "https://app.example.org",
+            "http://localhost:5173",
+            "https://*.example.org",
+            "https://*.example.org:8443",
+            "http://10.0.0.1:8080",
+        ] {
+            assert!(check_origin(origin).is_ok(), "{}", origin);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_origins() {
+        for origin in [
+            "app.example.org",
+            "ftp://app.example.org",
+            "https://",
+            "https://*.",
+            "https://:8080",
+            "https://.example.org",
+            "https://app.*.example.org",
+            "https://*",
+            "*",
+            "https://app.example.org/",
+            "https://app.example.org/login",
+            "https://app.example.org?x=1",
+            "https://app.example.org ",
+        ] {
+            assert!(check_origin(origin).is_err(), "{}", origin);
+        }
+    }
+
+    #[test]
+    fn exact_origin_must_match() {
+        assert!(origin_matches(
+            "https://app.example.org",
+            "https://app.example.org"
+        ));
+        assert!(origin_matches(
+            "https://app.example.org",
+            "HTTPS://APP.EXAMPLE.ORG"
+        ));
+        assert!(!origin_matches(
+            "https://app.example.org",
+            "http://app.example.org"
+        ));
+        assert!(!origin_matches(
+            "https://app.example.org",
+            "https://app.example.org:8443"
+        ));
+        assert!(!origin_matches(
+            "https://app.example.org",
+            "https://app.example.org.evil.example.net"
+        ));
+    }
+
+    #[test]
+    fn wildcard_matches_only_subdomains() {
+        let pattern = "https://*.example.org";
+
+        assert!(origin_matches(pattern, "https://app.example.org"));
+        assert!(origin_matches(pattern, "https://a.b.example.org"));
+        assert!(origin_matches(pattern, "https://App.Example.org"));
+
+        assert!(!origin_matches(pattern, "https://evil-example.org"));
+        assert!(!origin_matches(pattern, "https://example.org"));
+        assert!(!origin_matches(pattern, "https://.example.org"));
+        assert!(!origin_matches(pattern, "https://a..example.org"));
+        assert!(!origin_matches(pattern, "https://example.org.evil.net"));
+        assert!(!origin_matches(pattern, "https://evil.net/.example.org"));
+        assert!(!origin_matches(pattern, "https://evil.net?.example.org"));
+        assert!(!origin_matches(pattern, "http://app.example.org"));
+        assert!(!origin_matches(pattern, "https://app.example.org:8443"));
+    }
+
+    #[test]
+    fn wildcard_keeps_the_port() {
+        let pattern = "https://*.example.org:8443";
+
+        assert!(origin_matches(pattern, "https://app.example.org:8443"));
+        assert!(!origin_matches(pattern, "https://app.example.org"));
+        assert!(!origin_matches(pattern, "https://app.example.org:9443"));
+    }
+}
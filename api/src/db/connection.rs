@@ -1,49 +1,133 @@
-use crate::config::CONFIG;
-use crate::db::entities::{members, teachers};
-use crate::models;
-use bcrypt::{hash, DEFAULT_COST};
+use crate::config::{DatabaseConfig, DatabaseSslMode, CONFIG};
+use crate::db::entities::{members, role_permissions, roles, teachers};
+use crate::models::{self, Permission, SUPER_ADMIN_ROLE};
+use crate::util::{hash_password, verify_password};
 use chrono::Utc;
-use log::info;
+use log::LevelFilter;
+use sea_orm::sea_query::OnConflict;
 use sea_orm::{
-    ActiveModelTrait, Database, DatabaseConnection, DbErr, EntityTrait, QuerySelect, Set,
-    TransactionTrait,
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, RuntimeErr, Set, SqlxPostgresConnector, TransactionTrait,
 };
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use sqlx::ConnectOptions;
+use tracing::{info, warn};
 
+/// 建立連線池，資料庫尚未就緒時依設定的間隔重試。
 pub async fn db_connection() -> Result<DatabaseConnection, DbErr> {
-    let database_url = format!(
-        "postgresql://{}:{}@{}:{}/{}",
-        CONFIG.database.user,
-        CONFIG.database.password,
-        CONFIG.database.host,
-        CONFIG.database.port,
-        CONFIG.database.name
-    );
-
-    Database::connect(&database_url).await
+    let config = &CONFIG.database;
+    let options = connect_options(config);
+
+    let mut attempt = 1;
+    loop {
+        match pool_options(config).connect_with(options.clone()).await {
+            Ok(pool) => return Ok(SqlxPostgresConnector::from_sqlx_postgres_pool(pool)),
+            Err(e) if attempt < config.retry.max_attempts => {
+                let backoff = config.retry.backoff(attempt);
+                warn!(
+                    error = %e,
+                    attempt,
+                    max_attempts = config.retry.max_attempts,
+                    retry_in_ms = backoff.as_millis() as u64,
+                    "database connection failed, retrying"
+                );
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(DbErr::Conn(RuntimeErr::SqlxError(e))),
+        }
+    }
+}
+
+fn connect_options(config: &DatabaseConfig) -> PgConnectOptions {
+    // 與 sea-orm 預設相同，以 INFO 層級記錄執行的 SQL
+    let mut options = PgConnectOptions::new_without_pgpass()
+        .host(&config.host)
+        .port(config.port)
+        .username(&config.user)
+        .password(&config.password)
+        .database(&config.name)
+        .ssl_mode(ssl_mode(config.ssl_mode))
+        .application_name(&config.application_name)
+        .log_statements(LevelFilter::Info);
+
+    if let Some(cert) = &config.ssl_root_cert {
+        options = options.ssl_root_cert(cert);
+    }
+    if let Some(seconds) = config.statement_timeout_seconds {
+        options = options.options([("statement_timeout", format!("{}s", seconds))]);
+    }
+
+    options
+}
+
+fn pool_options(config: &DatabaseConfig) -> PgPoolOptions {
+    PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.connect_timeout())
+        .idle_timeout(Some(config.idle_timeout()))
+}
+
+fn ssl_mode(mode: DatabaseSslMode) -> PgSslMode {
+    match mode {
+        DatabaseSslMode::Disable => PgSslMode::Disable,
+        DatabaseSslMode::Allow => PgSslMode::Allow,
+        DatabaseSslMode::Prefer => PgSslMode::Prefer,
+        DatabaseSslMode::Require => PgSslMode::Require,
+        DatabaseSslMode::VerifyCa => PgSslMode::VerifyCa,
+        DatabaseSslMode::VerifyFull => PgSslMode::VerifyFull,
+    }
 }
 
 pub async fn init(db: &DatabaseConnection) -> Result<(), String> {
+    sync_super_admin_permissions(db).await?;
+
     let teacher_exists = teachers::Entity::find()
         .limit(1)
         .one(db)
         .await
-        .map_err(|e| format!("資料庫異常，異常原因：{}", e.to_string()))?
+        .map_err(|e| format!("資料庫異常，異常原因：{}", e))?
         .is_some();
 
     if teacher_exists {
-        return Ok(());
+        return flag_default_password(db).await;
     }
 
-    let password_hash = hash(CONFIG.auth.default_password.as_bytes(), DEFAULT_COST)
-        .map_err(|_| "無法產生預設密碼")?;
+    let password_hash =
+        hash_password(&CONFIG.auth.default_password).map_err(|_| "無法產生預設密碼")?;
+
+    create_super_admin(
+        db,
+        &CONFIG.auth.default_name,
+        &CONFIG.auth.default_username,
+        password_hash,
+        true,
+    )
+    .await?;
+
+    info!(username = %CONFIG.auth.default_username, "default teacher created");
+
+    Ok(())
+}
+
+/// 建立主管理員帳號與對應的成員資料。
+pub async fn create_super_admin(
+    db: &DatabaseConnection,
+    name: &str,
+    username: &str,
+    password_hash: String,
+    must_change_password: bool,
+) -> Result<teachers::Model, String> {
+    let super_admin_role = find_super_admin_role(db).await?;
 
     let txn = db
         .begin()
         .await
-        .map_err(|e| format!("系統異常，異常原因：{}", e.to_string()))?;
+        .map_err(|e| format!("系統異常，異常原因：{}", e))?;
 
     let new_member = members::ActiveModel {
-        name: Set(CONFIG.auth.default_name.clone()),
+        name: Set(name.to_string()),
         joined_at: Set(Utc::now().naive_utc()),
         ..Default::default()
     };
@@ -51,27 +135,92 @@ pub async fn init(db: &DatabaseConnection) -> Result<(), String> {
     let new_member = new_member
         .insert(&txn)
         .await
-        .map_err(|e| format!("無法建立新的成員，異常原因：{}", e.to_string()))?;
+        .map_err(|e| format!("無法建立新的成員，異常原因：{}", e))?;
 
     let new_teacher = teachers::ActiveModel {
         member_id: Set(new_member.id),
-        username: Set(CONFIG.auth.default_username.clone()),
+        username: Set(username.to_string()),
         password: Set(password_hash),
-        role_type: Set(models::RoleType::SuperAdmin.into()),
+        role_id: Set(super_admin_role.id),
         employment_type: Set(models::EmploymentType::FullTime.into()),
+        must_change_password: Set(must_change_password),
         ..Default::default()
     };
 
-    new_teacher
+    let new_teacher = new_teacher
         .insert(&txn)
         .await
-        .map_err(|e| format!("無法建立新的教職員，異常原因：{}", e.to_string()))?;
+        .map_err(|e| format!("無法建立新的教職員，異常原因：{}", e))?;
 
     txn.commit()
         .await
-        .map_err(|e| format!("系統異常，原因：{}", e.to_string()))?;
+        .map_err(|e| format!("系統異常，原因：{}", e))?;
+
+    Ok(new_teacher)
+}
+
+/// 預設帳號若仍在使用設定檔中的預設密碼，要求在下次登入時變更密碼。
+async fn flag_default_password(db: &DatabaseConnection) -> Result<(), String> {
+    let Some(teacher) = teachers::Entity::find()
+        .filter(teachers::Column::Username.eq(&CONFIG.auth.default_username))
+        .filter(teachers::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| format!("資料庫異常，異常原因：{}", e))?
+    else {
+        return Ok(());
+    };
+
+    if teacher.must_change_password
+        || !verify_password(&CONFIG.auth.default_password, &teacher.password).unwrap_or(false)
+    {
+        return Ok(());
+    }
+
+    let username = teacher.username.clone();
+    let mut teacher: teachers::ActiveModel = teacher.into();
+    teacher.must_change_password = Set(true);
+    teacher
+        .update(db)
+        .await
+        .map_err(|e| format!("無法更新預設教職員，異常原因：{}", e))?;
 
-    info!("已成功建立預設教職員");
+    warn!(username = %username, "default account still uses the default password, requiring a change");
 
     Ok(())
 }
+
+/// 主管理員角色固定擁有所有權限，新增權限後於啟動時自動補上。
+async fn sync_super_admin_permissions(db: &DatabaseConnection) -> Result<(), String> {
+    let super_admin_role = find_super_admin_role(db).await?;
+
+    let permissions = Permission::ALL.map(|permission| role_permissions::ActiveModel {
+        role_id: Set(super_admin_role.id),
+        permission: Set(permission.into()),
+    });
+
+    role_permissions::Entity::insert_many(permissions)
+        .on_conflict(
+            OnConflict::columns([
+                role_permissions::Column::RoleId,
+                role_permissions::Column::Permission,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await
+        .map_err(|e| format!("無法更新主管理員權限，異常原因：{}", e))?;
+
+    Ok(())
+}
+
+async fn find_super_admin_role(db: &DatabaseConnection) -> Result<roles::Model, String> {
+    roles::Entity::find()
+        .filter(roles::Column::Name.eq(SUPER_ADMIN_ROLE))
+        .one(db)
+        .await
+        .map_err(|e| format!("資料庫異常，異常原因：{}", e))?
+        .ok_or_else(|| "找不到主管理員角色，請確認資料庫遷移是否完成".to_string())
+}
@@ -32,12 +32,20 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_one = "super::guardians::Entity")]
+    Guardians,
     #[sea_orm(has_one = "super::students::Entity")]
     Students,
     #[sea_orm(has_one = "super::teachers::Entity")]
     Teachers,
 }
 
+impl Related<super::guardians::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Guardians.def()
+    }
+}
+
 impl Related<super::students::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Students.def()
@@ -3,12 +3,26 @@
 pub mod prelude;
 
 pub mod announcements;
+pub mod api_token_permissions;
+pub mod api_tokens;
 pub mod attendance_records;
 pub mod attendance_students;
+pub mod guardian_login_events;
+pub mod guardian_sessions;
+pub mod guardians;
+pub mod login_events;
 pub mod member_family_relations;
 pub mod members;
+pub mod password_reset_codes;
+pub mod role_permissions;
+pub mod roles;
+pub mod session_refresh_tokens;
 pub mod student_exams;
 pub mod student_infos;
 pub mod students;
+pub mod system_settings;
 pub mod teacher_assignments;
+pub mod teacher_oidc_identities;
+pub mod teacher_sessions;
 pub mod teachers;
+pub mod two_factor_recovery_codes;
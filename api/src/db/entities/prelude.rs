@@ -1,12 +1,26 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
 pub use super::announcements::Entity as Announcements;
+pub use super::api_token_permissions::Entity as ApiTokenPermissions;
+pub use super::api_tokens::Entity as ApiTokens;
 pub use super::attendance_records::Entity as AttendanceRecords;
 pub use super::attendance_students::Entity as AttendanceStudents;
+pub use super::guardian_login_events::Entity as GuardianLoginEvents;
+pub use super::guardian_sessions::Entity as GuardianSessions;
+pub use super::guardians::Entity as Guardians;
+pub use super::login_events::Entity as LoginEvents;
 pub use super::member_family_relations::Entity as MemberFamilyRelations;
 pub use super::members::Entity as Members;
+pub use super::password_reset_codes::Entity as PasswordResetCodes;
+pub use super::role_permissions::Entity as RolePermissions;
+pub use super::roles::Entity as Roles;
+pub use super::session_refresh_tokens::Entity as SessionRefreshTokens;
 pub use super::student_exams::Entity as StudentExams;
 pub use super::student_infos::Entity as StudentInfos;
 pub use super::students::Entity as Students;
+pub use super::system_settings::Entity as SystemSettings;
 pub use super::teacher_assignments::Entity as TeacherAssignments;
+pub use super::teacher_oidc_identities::Entity as TeacherOidcIdentities;
+pub use super::teacher_sessions::Entity as TeacherSessions;
 pub use super::teachers::Entity as Teachers;
+pub use super::two_factor_recovery_codes::Entity as TwoFactorRecoveryCodes;
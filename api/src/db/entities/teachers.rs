@@ -11,7 +11,6 @@ pub struct Model {
     pub username: String,
     #[sea_orm(column_type = "Text")]
     pub password: String,
-    pub role_type: i16,
     pub employment_type: i16,
     #[sea_orm(column_type = "Text", nullable)]
     pub responsibility: Option<String>,
@@ -21,12 +20,22 @@ pub struct Model {
     pub updated_at: DateTime,
     pub deleted_at: Option<DateTime>,
     pub last_login_at: Option<DateTime>,
+    pub failed_login_attempts: i16,
+    pub locked_until: Option<DateTime>,
+    pub must_change_password: bool,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<DateTime>,
+    pub totp_last_used_step: Option<i64>,
+    pub role_id: Uuid,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::announcements::Entity")]
     Announcements,
+    #[sea_orm(has_many = "super::login_events::Entity")]
+    LoginEvents,
     #[sea_orm(
         belongs_to = "super::members::Entity",
         from = "Column::MemberId",
@@ -35,8 +44,22 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Members,
+    #[sea_orm(
+        belongs_to = "super::roles::Entity",
+        from = "Column::RoleId",
+        to = "super::roles::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Roles,
     #[sea_orm(has_many = "super::teacher_assignments::Entity")]
     TeacherAssignments,
+    #[sea_orm(has_one = "super::teacher_oidc_identities::Entity")]
+    TeacherOidcIdentities,
+    #[sea_orm(has_many = "super::teacher_sessions::Entity")]
+    TeacherSessions,
+    #[sea_orm(has_many = "super::two_factor_recovery_codes::Entity")]
+    TwoFactorRecoveryCodes,
 }
 
 impl Related<super::announcements::Entity> for Entity {
@@ -45,18 +68,48 @@ impl Related<super::announcements::Entity> for Entity {
     }
 }
 
+impl Related<super::login_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LoginEvents.def()
+    }
+}
+
 impl Related<super::members::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Members.def()
     }
 }
 
+impl Related<super::roles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Roles.def()
+    }
+}
+
 impl Related<super::teacher_assignments::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::TeacherAssignments.def()
     }
 }
 
+impl Related<super::teacher_oidc_identities::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TeacherOidcIdentities.def()
+    }
+}
+
+impl Related<super::teacher_sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TeacherSessions.def()
+    }
+}
+
+impl Related<super::two_factor_recovery_codes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TwoFactorRecoveryCodes.def()
+    }
+}
+
 impl Related<super::students::Entity> for Entity {
     fn to() -> RelationDef {
         super::teacher_assignments::Relation::Students.def()
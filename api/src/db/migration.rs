@@ -0,0 +1,178 @@
+use sea_orm::DatabaseConnection;
+use sqlx::migrate::{Migrate, Migrator};
+use std::collections::HashMap;
+use tracing::info;
+
+/// 編譯時嵌入 `migrations` 目錄中的所有 migration
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// 資料庫中已套用的 migration 與這個版本的程式所需的比較結果。
+#[derive(Debug, Default)]
+pub struct MigrationStatus {
+    pub applied: usize,
+    pub expected: usize,
+    /// 尚未套用的版本
+    pub pending: Vec<i64>,
+    /// 套用後內容又被修改過的版本
+    pub modified: Vec<i64>,
+    /// 資料庫中有、這個版本的程式卻沒有的版本，代表資料庫結構比程式新
+    pub unknown: Vec<i64>,
+    /// 執行到一半失敗的版本
+    pub dirty: Option<i64>,
+}
+
+impl MigrationStatus {
+    pub fn is_current(&self) -> bool {
+        self.pending.is_empty()
+            && self.modified.is_empty()
+            && self.unknown.is_empty()
+            && self.dirty.is_none()
+    }
+}
+
+pub async fn migration_status(db: &DatabaseConnection) -> Result<MigrationStatus, String> {
+    let mut conn = db
+        .get_postgres_connection_pool()
+        .acquire()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let dirty = conn.dirty_version().await.map_err(|e| e.to_string())?;
+    let mut applied: HashMap<i64, Vec<u8>> = conn
+        .list_applied_migrations()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|migration| (migration.version, migration.checksum.into_owned()))
+        .collect();
+
+    let mut status = MigrationStatus {
+        applied: applied.len(),
+        dirty,
+        ..Default::default()
+    };
+    for migration in MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+    {
+        status.expected += 1;
+        match applied.remove(&migration.version) {
+            None => status.pending.push(migration.version),
+            Some(checksum) if *checksum != *migration.checksum => {
+                status.modified.push(migration.version)
+            }
+            Some(_) => {}
+        }
+    }
+    status.unknown = applied.into_keys().collect();
+    status.unknown.sort_unstable();
+
+    Ok(status)
+}
+
+/// 建立記錄 migration 的資料表，全新的資料庫需要先建立才能查詢狀態。
+pub async fn ensure_migrations_table(db: &DatabaseConnection) -> Result<(), String> {
+    let mut conn = db
+        .get_postgres_connection_pool()
+        .acquire()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    conn.ensure_migrations_table()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 套用所有尚未套用的 migration，回傳這次套用的版本。
+pub async fn run_migrations(db: &DatabaseConnection) -> Result<Vec<i64>, String> {
+    ensure_migrations_table(db).await?;
+    let pending = migration_status(db).await?.pending;
+
+    MIGRATOR
+        .run(db.get_postgres_connection_pool())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(pending)
+}
+
+/// 依序還原版本大於 `target` 的 migration，回傳被還原的版本（由新到舊）。
+pub async fn undo_migrations(db: &DatabaseConnection, target: i64) -> Result<Vec<i64>, String> {
+    let mut reverted = applied_versions(db).await?;
+    reverted.retain(|version| *version > target);
+
+    MIGRATOR
+        .undo(db.get_postgres_connection_pool(), target)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(reverted)
+}
+
+/// 還原最近 `steps` 個 migration 時應傳給 [`undo_migrations`] 的版本。
+pub async fn undo_target(db: &DatabaseConnection, steps: usize) -> Result<i64, String> {
+    let applied = applied_versions(db).await?;
+    Ok(applied.get(steps).copied().unwrap_or(0))
+}
+
+/// 已套用的版本，由新到舊排序
+async fn applied_versions(db: &DatabaseConnection) -> Result<Vec<i64>, String> {
+    ensure_migrations_table(db).await?;
+    let mut conn = db
+        .get_postgres_connection_pool()
+        .acquire()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut versions: Vec<i64> = conn
+        .list_applied_migrations()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+    versions.sort_unstable_by(|a, b| b.cmp(a));
+
+    Ok(versions)
+}
+
+/// 啟動前檢查資料庫結構。資料庫比程式新、migration 曾經失敗或被修改時拒絕啟動；
+/// 有尚未套用的 migration 時，`run_pending` 為 true 才自動套用，否則同樣拒絕啟動。
+pub async fn ensure_schema(db: &DatabaseConnection, run_pending: bool) -> Result<(), String> {
+    ensure_migrations_table(db).await?;
+    let status = migration_status(db).await?;
+
+    if let Some(version) = status.dirty {
+        return Err(format!(
+            "migration {} failed previously, the database must be repaired manually",
+            version
+        ));
+    }
+    if !status.unknown.is_empty() {
+        return Err(format!(
+            "database schema is newer than this binary, unknown migrations: {:?}",
+            status.unknown
+        ));
+    }
+    if !status.modified.is_empty() {
+        return Err(format!(
+            "applied migrations have been modified: {:?}",
+            status.modified
+        ));
+    }
+    if status.pending.is_empty() {
+        return Ok(());
+    }
+
+    if !run_pending {
+        return Err(format!(
+            "database has pending migrations {:?}, run `migrate up` to apply them \
+             or enable database.run_migrations_on_startup",
+            status.pending
+        ));
+    }
+
+    let applied = run_migrations(db).await?;
+    info!(applied = ?applied, "migrations applied");
+    Ok(())
+}
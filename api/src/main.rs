@@ -1,32 +1,32 @@
-use church_community_center_api::config::CONFIG;
-use church_community_center_api::{db, routes};
-use tracing::info;
-use std::net::SocketAddr;
+use church_community_center_api::cli::{self, Cli, Command};
+use church_community_center_api::config::{self, CONFIG};
+use church_community_center_api::util;
+use clap::Parser;
+use std::process;
+use tracing::{error, info};
 
 #[tokio::main]
 async fn main() {
-    // 初始化日誌
-    tracing_subscriber::fmt().init();
-
-    // 載入環境變數
-    info!("Initial Config: {}", CONFIG.environment);
-
-    // 初始化資料庫
-    let conn = db::connection::db_connection()
-        .await
-        .expect("Failed to connect to database");
+    let cli = Cli::parse();
+    let source = cli.config_source();
+    let command = cli.command.unwrap_or(Command::Serve);
 
-    db::connection::init(&conn).await.expect("Init failed");
+    // 載入設定，日誌尚未初始化，錯誤直接輸出到標準錯誤
+    if let Err(e) = config::init_config(&source) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 
-    // 初始化路由
-    let app = routes::new_route(conn);
-
-    // 啟動伺服器
-    let addr: SocketAddr = format!("{}:{}", CONFIG.server.address, CONFIG.server.port)
-        .parse()
-        .expect("無法解析地址");
+    // 初始化日誌
+    let _log_guard =
+        util::init_logger(&CONFIG.logger, command.console_output()).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+    info!(environment = %CONFIG.environment, "config loaded");
 
-    info!("Server is work on: {}", addr);
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    if let Err(e) = cli::run(command, &source).await {
+        error!(error = %e, "command failed");
+        process::exit(1);
+    }
 }
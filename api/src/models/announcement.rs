@@ -1,19 +1,55 @@
 use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Deserialize, Validate)]
+/// 公告的對象，資料庫中以 snake_case 字串儲存。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnouncementAudience {
+    /// 只有教職員看得到
+    #[default]
+    Staff,
+    /// 只有家長看得到
+    Guardians,
+    Everyone,
+}
+
+impl AnnouncementAudience {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnouncementAudience::Staff => "staff",
+            AnnouncementAudience::Guardians => "guardians",
+            AnnouncementAudience::Everyone => "everyone",
+        }
+    }
+
+    /// 無法辨識的值視為只給教職員，避免誤把內部公告給家長看到
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "guardians" => AnnouncementAudience::Guardians,
+            "everyone" => AnnouncementAudience::Everyone,
+            _ => AnnouncementAudience::Staff,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpsertAnnouncementRequest {
     pub title: String,
     pub content: String,
+    /// 未提供時，新增的公告只給教職員，修改時則維持原本的對象
+    pub audience: Option<AnnouncementAudience>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AnnouncementView {
     pub id: Uuid,
     pub name: String,
     pub title: String,
     pub content: String,
+    pub audience: AnnouncementAudience,
+    #[schema(value_type = String, format = DateTime)]
     pub updated_at: DateTimeWithTimeZone,
-}
\ No newline at end of file
+}
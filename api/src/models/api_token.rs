@@ -0,0 +1,69 @@
+use crate::db::entities::api_tokens;
+use crate::models::Permission;
+use chrono::{TimeZone, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateApiTokenRequest {
+    /// token 代表的教職員，排程或平板等服務請使用專用的教職員帳號
+    pub teacher_id: Uuid,
+    #[validate(length(min = 2, message = "名稱至少需要2個字元"))]
+    pub name: String,
+    #[validate(length(min = 1, message = "至少需要一項權限"))]
+    pub permissions: Vec<Permission>,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTimeWithTimeZone,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiTokenView {
+    pub id: Uuid,
+    pub teacher_id: Uuid,
+    pub created_by: Uuid,
+    pub name: String,
+    /// token 的開頭幾個字元，用來辨識是哪一組 token
+    pub token_prefix: String,
+    pub permissions: Vec<Permission>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTimeWithTimeZone,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTimeWithTimeZone,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_used_at: Option<DateTimeWithTimeZone>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub revoked_at: Option<DateTimeWithTimeZone>,
+}
+
+/// 只有在建立時會回傳完整的 token
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedApiTokenView {
+    pub token: String,
+    #[serde(flatten)]
+    pub api_token: ApiTokenView,
+}
+
+pub fn api_token_to_view(
+    api_token: api_tokens::Model,
+    permissions: Vec<Permission>,
+) -> ApiTokenView {
+    ApiTokenView {
+        id: api_token.id,
+        teacher_id: api_token.teacher_id,
+        created_by: api_token.created_by,
+        name: api_token.name,
+        token_prefix: api_token.token_prefix,
+        permissions,
+        created_at: Utc.from_utc_datetime(&api_token.created_at).into(),
+        expires_at: Utc.from_utc_datetime(&api_token.expires_at).into(),
+        last_used_at: api_token
+            .last_used_at
+            .map(|last_used_at| Utc.from_utc_datetime(&last_used_at).into()),
+        revoked_at: api_token
+            .revoked_at
+            .map(|revoked_at| Utc.from_utc_datetime(&revoked_at).into()),
+    }
+}
@@ -0,0 +1,22 @@
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// 教職員可以存取的學生範圍。
+#[derive(Debug, Clone)]
+pub enum StudentScope {
+    All,
+    Assigned(HashSet<Uuid>),
+}
+
+impl StudentScope {
+    pub fn contains(&self, student_id: Uuid) -> bool {
+        match self {
+            StudentScope::All => true,
+            StudentScope::Assigned(student_ids) => student_ids.contains(&student_id),
+        }
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        matches!(self, StudentScope::Assigned(_))
+    }
+}
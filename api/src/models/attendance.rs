@@ -1,30 +1,35 @@
 use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpsertAttendanceRequest {
+    /// 整份簽到表的備註，只能看到指派學生的教職員更新時會忽略
     pub note: Option<String>,
     pub attendance_students: Vec<AttendanceStudent>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AttendanceStudent {
     pub student_id: Uuid,
     pub attendance_status: bool,
     pub note: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AttendanceView {
     pub id: String,
     pub note: Option<String>,
+    #[schema(value_type = String, format = DateTime)]
     pub created_at: DateTimeWithTimeZone,
+    #[schema(value_type = String, format = DateTime)]
     pub updated_at: DateTimeWithTimeZone,
     pub attendance_students: Vec<AttendanceStudent>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct AttendanceQuery {
     pub date: String,
 }
@@ -1,30 +1,51 @@
-use crate::models::RoleType;
-use sea_orm::prelude::Uuid;
+use crate::models::Permission;
+use sea_orm::prelude::{DateTimeWithTimeZone, Uuid};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+pub struct LoginResponse {
+    pub two_factor_required: bool,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct MeResponse {
-    id: Uuid,
-    username: String,
-    name: String,
-    role: RoleType,
-    exp: i64,
+    pub id: Uuid,
+    pub username: String,
+    pub name: String,
+    pub role: String,
+    pub permissions: Vec<Permission>,
+    pub must_change_password: bool,
+    pub two_factor_enabled: bool,
+    pub must_enroll_two_factor: bool,
+    pub exp: i64,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    #[validate(length(min = 8, message = "密碼至少需要8個字元"))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct RedeemPasswordResetRequest {
+    pub username: String,
+    pub code: String,
+    #[validate(length(min = 8, message = "密碼至少需要8個字元"))]
+    pub new_password: String,
 }
 
-impl MeResponse {
-    pub fn new(id: Uuid, username: String, name: String, role: RoleType, exp: i64) -> Self {
-        Self {
-            id,
-            username,
-            name,
-            role,
-            exp,
-        }
-    }
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PasswordResetCodeView {
+    pub code: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTimeWithTimeZone,
 }
@@ -1,8 +1,10 @@
 use axum::http::StatusCode;
 use axum::Json;
 use serde::Serialize;
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+/// 所有 API 共用的回應格式，錯誤時只有 `message`。
+#[derive(Serialize, ToSchema)]
 pub struct AppResponse<T = ()>
 where
     T: Serialize,
@@ -41,4 +43,4 @@ impl AppResponse<()> {
             }),
         )
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,80 @@
+use crate::db::entities::guardians;
+use chrono::{NaiveDate, TimeZone, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AddGuardianRequest {
+    /// 家長本身的成員資料，子女透過 `member_family_relations` 連結
+    pub member_id: Uuid,
+    #[validate(length(min = 4, message = "帳號至少需要4個字元"))]
+    pub username: String,
+    #[validate(length(min = 8, message = "密碼至少需要8個字元"))]
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ResetGuardianPasswordRequest {
+    #[validate(length(min = 8, message = "密碼至少需要8個字元"))]
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GuardianView {
+    pub member_id: Uuid,
+    pub name: String,
+    pub username: String,
+    pub must_change_password: bool,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_login_at: Option<DateTimeWithTimeZone>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GuardianChildView {
+    pub student_id: Uuid,
+    pub name: String,
+    /// 家長與學生的關係，例如「父親」、「祖母」
+    pub relation_type: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GuardianMeResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub name: String,
+    pub must_change_password: bool,
+    pub children: Vec<GuardianChildView>,
+    pub exp: i64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GuardianAttendanceQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GuardianAttendanceView {
+    pub date: NaiveDate,
+    pub attendance_status: bool,
+    pub note: Option<String>,
+}
+
+pub fn guardian_to_view(guardian: guardians::Model, name: String) -> GuardianView {
+    GuardianView {
+        member_id: guardian.member_id,
+        name,
+        username: guardian.username,
+        must_change_password: guardian.must_change_password,
+        last_login_at: guardian
+            .last_login_at
+            .map(|last_login_at| Utc.from_utc_datetime(&last_login_at).into()),
+        created_at: Utc.from_utc_datetime(&guardian.created_at).into(),
+    }
+}
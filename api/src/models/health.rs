@@ -0,0 +1,58 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Error,
+}
+
+/// 檢查失敗的原因，詳細的錯誤只寫入日誌，不回傳給呼叫端。
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckError {
+    /// 超過檢查的時限
+    Timeout,
+    /// 無法連線或查詢失敗
+    QueryFailed,
+    /// 資料庫無法連線，沒有進行這項檢查
+    DatabaseUnavailable,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessView {
+    pub status: CheckStatus,
+    pub checks: ReadinessChecks,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessChecks {
+    pub database: DatabaseCheck,
+    pub migrations: MigrationsCheck,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DatabaseCheck {
+    pub status: CheckStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<CheckError>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MigrationsCheck {
+    pub status: CheckStatus,
+    pub applied: usize,
+    pub expected: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pending: Vec<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modified: Vec<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dirty: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<CheckError>,
+}
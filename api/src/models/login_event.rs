@@ -0,0 +1,74 @@
+use crate::db::entities::login_events;
+use chrono::{TimeZone, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginEventType {
+    Success = 0,
+    /// 帳號不存在、密碼錯誤或 IP 被暫時限制
+    Failure = 1,
+    /// 帳號因失敗次數過多被鎖定，或在鎖定期間嘗試登入
+    Lockout = 2,
+    Logout = 3,
+}
+
+impl TryFrom<i16> for LoginEventType {
+    type Error = &'static str;
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(LoginEventType::Success),
+            1 => Ok(LoginEventType::Failure),
+            2 => Ok(LoginEventType::Lockout),
+            3 => Ok(LoginEventType::Logout),
+            _ => Err("無效值"),
+        }
+    }
+}
+
+impl From<LoginEventType> for i16 {
+    fn from(event_type: LoginEventType) -> i16 {
+        match event_type {
+            LoginEventType::Success => 0,
+            LoginEventType::Failure => 1,
+            LoginEventType::Lockout => 2,
+            LoginEventType::Logout => 3,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LoginHistoryQuery {
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LoginEventView {
+    pub id: Uuid,
+    pub username: Option<String>,
+    pub event_type: LoginEventType,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTimeWithTimeZone,
+}
+
+impl TryFrom<login_events::Model> for LoginEventView {
+    type Error = &'static str;
+
+    fn try_from(event: login_events::Model) -> Result<Self, Self::Error> {
+        Ok(LoginEventView {
+            id: event.id,
+            username: event.username,
+            event_type: LoginEventType::try_from(event.event_type)?,
+            ip_address: event.ip_address,
+            user_agent: event.user_agent,
+            created_at: Utc.from_utc_datetime(&event.created_at).into(),
+        })
+    }
+}
@@ -2,15 +2,18 @@ use crate::db::entities::members;
 use chrono::{TimeZone, Utc};
 use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct MemberDto {
     #[validate(length(min = 2, message = "名稱至少需要2個字元"))]
     pub name: String,
     #[validate(length(min = 10, message = "身份證格式不正確"))]
     pub id_number: Option<String>,
     pub gender: Option<i16>,
+    #[schema(value_type = Option<String>, format = DateTime)]
     pub birth_date: Option<DateTimeWithTimeZone>,
     pub home_phone_number: Option<String>,
     pub mobile_phone_number: Option<String>,
@@ -18,16 +21,41 @@ pub struct MemberDto {
     pub title: Option<String>,
     pub line_id: Option<String>,
     pub comment: Option<String>,
+    #[schema(value_type = String, format = DateTime)]
     pub joined_at: DateTimeWithTimeZone,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+impl MemberDto {
+    /// 清除身分證字號、生日與聯絡方式等個人資料。
+    pub fn redact_pii(&mut self) {
+        self.id_number = None;
+        self.birth_date = None;
+        self.home_phone_number = None;
+        self.mobile_phone_number = None;
+        self.address = None;
+        self.line_id = None;
+    }
+
+    /// 以資料庫中既有的個人資料取代請求內容，避免無法讀取個資的人在更新時將其清空。
+    pub fn keep_pii(&mut self, member: &members::Model) {
+        self.id_number = member.id_number.clone();
+        self.birth_date = member
+            .birth_date
+            .map(|birth_date| Utc.from_utc_datetime(&birth_date).into());
+        self.home_phone_number = member.home_phone_number.clone();
+        self.mobile_phone_number = member.mobile_phone_number.clone();
+        self.address = member.address.clone();
+        self.line_id = member.line_id.clone();
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpsertMemberRequest {
     #[serde(flatten)]
     pub member_dto: MemberDto,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct MemberView {
     #[serde(flatten)]
     pub member_dto: MemberDto,
@@ -52,3 +80,19 @@ impl From<members::Model> for MemberDto {
         }
     }
 }
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpsertFamilyRelationRequest {
+    /// 親屬對此成員的關係，例如「父親」、「祖母」
+    #[validate(length(min = 1, message = "請填寫關係"))]
+    pub relation_type: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FamilyRelationView {
+    pub relative_id: Uuid,
+    pub name: String,
+    pub relation_type: String,
+    /// 此親屬是否已開通家長帳號
+    pub has_guardian_account: bool,
+}
@@ -1,17 +1,39 @@
 mod announcement;
+mod api_token;
+mod assignment;
 mod attendance;
 mod auth;
 mod common;
+mod guardian;
+mod health;
+mod login_event;
+mod member;
+mod oidc;
+mod permission;
+mod role;
+mod session;
+mod setting;
 mod student;
 mod student_info;
 mod teacher;
-mod member;
+mod two_factor;
 
 pub use announcement::*;
+pub use api_token::*;
+pub use assignment::*;
 pub use attendance::*;
 pub use auth::*;
 pub use common::*;
+pub use guardian::*;
+pub use health::*;
+pub use login_event::*;
+pub use member::*;
+pub use oidc::*;
+pub use permission::*;
+pub use role::*;
+pub use session::*;
+pub use setting::*;
 pub use student::*;
 pub use student_info::*;
 pub use teacher::*;
-pub use member::*;
+pub use two_factor::*;
@@ -0,0 +1,42 @@
+use crate::db::entities::teacher_oidc_identities;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// 使用者在身分提供者取消登入等情況下，會帶回錯誤而不是授權碼
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpsertOidcIdentityRequest {
+    /// 身分提供者中的 subject，未提供時於第一次以電子郵件登入時自動綁定
+    #[validate(length(min = 1, message = "subject 不能為空白"))]
+    pub subject: Option<String>,
+    #[validate(email(message = "電子郵件格式不正確"))]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcIdentityView {
+    pub teacher_id: Uuid,
+    pub issuer: Option<String>,
+    pub subject: Option<String>,
+    pub email: Option<String>,
+}
+
+impl From<teacher_oidc_identities::Model> for OidcIdentityView {
+    fn from(identity: teacher_oidc_identities::Model) -> Self {
+        OidcIdentityView {
+            teacher_id: identity.teacher_id,
+            issuer: identity.issuer,
+            subject: identity.subject,
+            email: identity.email,
+        }
+    }
+}
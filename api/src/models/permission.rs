@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::openapi::schema::{ObjectBuilder, Schema, Type};
+use utoipa::openapi::RefOr;
+use utoipa::{PartialSchema, ToSchema};
+
+/// 可指派給角色的權限，資料庫與 API 中以 `資源.動作` 形式的名稱表示。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Permission {
+    TeachersRead,
+    TeachersManage,
+    MembersRead,
+    MembersWrite,
+    StudentsRead,
+    StudentsReadPii,
+    StudentsWrite,
+    StudentsAll,
+    AttendanceRead,
+    AttendanceWrite,
+    AnnouncementsWrite,
+    AnnouncementsManage,
+    RolesManage,
+    SettingsManage,
+    ApiTokensManage,
+    GuardiansManage,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 16] = [
+        Permission::TeachersRead,
+        Permission::TeachersManage,
+        Permission::MembersRead,
+        Permission::MembersWrite,
+        Permission::StudentsRead,
+        Permission::StudentsReadPii,
+        Permission::StudentsWrite,
+        Permission::StudentsAll,
+        Permission::AttendanceRead,
+        Permission::AttendanceWrite,
+        Permission::AnnouncementsWrite,
+        Permission::AnnouncementsManage,
+        Permission::RolesManage,
+        Permission::SettingsManage,
+        Permission::ApiTokensManage,
+        Permission::GuardiansManage,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::TeachersRead => "teachers.read",
+            Permission::TeachersManage => "teachers.manage",
+            Permission::MembersRead => "members.read",
+            Permission::MembersWrite => "members.write",
+            Permission::StudentsRead => "students.read",
+            Permission::StudentsReadPii => "students.read_pii",
+            Permission::StudentsWrite => "students.write",
+            Permission::StudentsAll => "students.all",
+            Permission::AttendanceRead => "attendance.read",
+            Permission::AttendanceWrite => "attendance.write",
+            Permission::AnnouncementsWrite => "announcements.write",
+            Permission::AnnouncementsManage => "announcements.manage",
+            Permission::RolesManage => "roles.manage",
+            Permission::SettingsManage => "settings.manage",
+            Permission::ApiTokensManage => "api_tokens.manage",
+            Permission::GuardiansManage => "guardians.manage",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Permission::TeachersRead => "查看教職員",
+            Permission::TeachersManage => "新增、刪除教職員與管理其帳號安全設定",
+            Permission::MembersRead => "查看成員",
+            Permission::MembersWrite => "新增與修改成員",
+            Permission::StudentsRead => "查看學生",
+            Permission::StudentsReadPii => "查看學生的身分證字號、聯絡方式與家庭經濟狀況",
+            Permission::StudentsWrite => "新增、修改與刪除學生及學生資料",
+            Permission::StudentsAll => "不受學生指派限制，可存取所有學生",
+            Permission::AttendanceRead => "查看出缺勤紀錄",
+            Permission::AttendanceWrite => "登記與修改出缺勤紀錄",
+            Permission::AnnouncementsWrite => "發布公告",
+            Permission::AnnouncementsManage => "修改與刪除他人的公告",
+            Permission::RolesManage => "管理角色與指派角色",
+            Permission::SettingsManage => "管理系統設定",
+            Permission::ApiTokensManage => "發出與撤銷 API token",
+            Permission::GuardiansManage => "管理家長帳號與重設家長密碼",
+        }
+    }
+}
+
+impl TryFrom<String> for Permission {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Permission::ALL
+            .into_iter()
+            .find(|permission| permission.as_str() == value)
+            .ok_or_else(|| format!("無效的權限：{}", value))
+    }
+}
+
+impl From<Permission> for String {
+    fn from(permission: Permission) -> String {
+        permission.as_str().to_string()
+    }
+}
+
+/// 與序列化的格式相同，以權限名稱的字串表示
+impl PartialSchema for Permission {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .enum_values(Some(Permission::ALL.map(|permission| permission.as_str())))
+            .into()
+    }
+}
+
+impl ToSchema for Permission {}
+
+/// 目前登入者所擁有的權限，由 `auth_middleware` 依角色載入。
+#[derive(Debug, Clone, Default)]
+pub struct PermissionSet(HashSet<Permission>);
+
+impl PermissionSet {
+    pub fn contains(&self, permission: Permission) -> bool {
+        self.0.contains(&permission)
+    }
+
+    /// 是否擁有 `other` 中的每一個權限
+    pub fn contains_all(&self, other: &PermissionSet) -> bool {
+        other.0.is_subset(&self.0)
+    }
+
+    /// 依 `Permission::ALL` 的順序列出擁有的權限
+    pub fn to_vec(&self) -> Vec<Permission> {
+        Permission::ALL
+            .into_iter()
+            .filter(|permission| self.contains(*permission))
+            .collect()
+    }
+}
+
+impl FromIterator<Permission> for PermissionSet {
+    fn from_iter<I: IntoIterator<Item = Permission>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PermissionView {
+    pub name: Permission,
+    pub description: &'static str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_all_requires_every_permission() {
+        let super_admin: PermissionSet = Permission::ALL.into_iter().collect();
+        let manager: PermissionSet = [
+            Permission::TeachersRead,
+            Permission::TeachersManage,
+            Permission::MembersRead,
+        ]
+        .into_iter()
+        .collect();
+        let teacher: PermissionSet = [Permission::TeachersRead, Permission::MembersRead]
+            .into_iter()
+            .collect();
+
+        // 有 teachers.manage 也不能管理主管理員
+        assert!(!manager.contains_all(&super_admin));
+        assert!(manager.contains_all(&teacher));
+        assert!(manager.contains_all(&manager));
+        assert!(manager.contains_all(&PermissionSet::default()));
+        assert!(super_admin.contains_all(&manager));
+        assert!(!teacher.contains_all(&manager));
+    }
+}
@@ -0,0 +1,43 @@
+use crate::db::entities::roles;
+use crate::models::Permission;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// 系統內建、擁有所有權限的角色
+pub const SUPER_ADMIN_ROLE: &str = "super_admin";
+/// 新增教職員時預設指派的角色
+pub const DEFAULT_ROLE: &str = "admin";
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpsertRoleRequest {
+    #[validate(length(min = 2, message = "角色名稱至少需要2個字元"))]
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AssignRoleRequest {
+    pub role_id: Uuid,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RoleView {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub is_system: bool,
+    pub permissions: Vec<Permission>,
+}
+
+pub fn role_to_view(role: roles::Model, permissions: Vec<Permission>) -> RoleView {
+    RoleView {
+        id: role.id,
+        name: role.name,
+        description: role.description,
+        is_system: role.is_system,
+        permissions,
+    }
+}
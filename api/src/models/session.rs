@@ -0,0 +1,29 @@
+use crate::db::entities::teacher_sessions;
+use chrono::{TimeZone, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionView {
+    pub id: Uuid,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTimeWithTimeZone,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTimeWithTimeZone,
+    pub is_current: bool,
+}
+
+pub fn session_to_view(session: teacher_sessions::Model, current_session_id: Uuid) -> SessionView {
+    SessionView {
+        id: session.id,
+        ip_address: session.ip_address,
+        user_agent: session.user_agent,
+        created_at: Utc.from_utc_datetime(&session.created_at).into(),
+        expires_at: Utc.from_utc_datetime(&session.expires_at).into(),
+        is_current: session.id == current_session_id,
+    }
+}
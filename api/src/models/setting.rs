@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SecuritySettings {
+    pub require_two_factor: bool,
+    /// 開啟後，沒有 `students.all` 權限的教職員只能存取指派給自己的學生
+    pub restrict_student_access: bool,
+}
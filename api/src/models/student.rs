@@ -3,10 +3,11 @@ use crate::models::MemberDto;
 use chrono::{TimeZone, Utc};
 use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct StudentDto {
     pub school_name: Option<String>,
     pub grade: Option<i16>,
@@ -18,10 +19,33 @@ pub struct StudentDto {
     pub occupation: Option<String>,
     pub subsidy: Option<String>,
     pub home_ownership: Option<i16>,
+    #[schema(value_type = String, format = DateTime)]
     pub class_joined_at: DateTimeWithTimeZone,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+impl StudentDto {
+    /// 清除家庭狀況等敏感資料。
+    pub fn redact_pii(&mut self) {
+        self.family_type = None;
+        self.family_members = None;
+        self.breadwinner = None;
+        self.occupation = None;
+        self.subsidy = None;
+        self.home_ownership = None;
+    }
+
+    /// 以資料庫中既有的家庭狀況取代請求內容。
+    pub fn keep_pii(&mut self, student: &students::Model) {
+        self.family_type = student.family_type.clone();
+        self.family_members = student.family_members;
+        self.breadwinner = student.breadwinner.clone();
+        self.occupation = student.occupation.clone();
+        self.subsidy = student.subsidy.clone();
+        self.home_ownership = student.home_ownership;
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct AddStudentRequest {
     pub member_id: Option<Uuid>,
     #[serde(flatten)]
@@ -30,7 +54,7 @@ pub struct AddStudentRequest {
     pub student_dto: StudentDto,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateStudentRequest {
     #[serde(flatten)]
     pub member_dto: MemberDto,
@@ -38,7 +62,7 @@ pub struct UpdateStudentRequest {
     pub student_dto: StudentDto,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct StudentView {
     pub member_id: Uuid,
     #[serde(flatten)]
@@ -47,6 +71,13 @@ pub struct StudentView {
     pub student_dto: StudentDto,
 }
 
+impl StudentView {
+    pub fn redact_pii(&mut self) {
+        self.member_dto.redact_pii();
+        self.student_dto.redact_pii();
+    }
+}
+
 pub fn student_and_member_to_view(student: students::Model, member: members::Model) -> StudentView {
     let member_id = member.id;
     let member_dto = MemberDto::from(member);
@@ -1,8 +1,10 @@
+use crate::db::entities::{student_exams, student_infos};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct StudentInfoDto {
     pub academic_year: i16,
     pub chinese_book: Option<String>,
@@ -13,7 +15,7 @@ pub struct StudentInfoDto {
     pub comment: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct StudentExamDto {
     pub semester: i16,
     pub exam_type: i16,
@@ -24,14 +26,14 @@ pub struct StudentExamDto {
     pub social_studies_score: Option<i16>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpsertStudentInfoRequest {
     #[serde(flatten)]
     pub info_dto: StudentInfoDto,
     pub exams_dto: Vec<StudentExamDto>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct StudentInfoView {
     pub id: Uuid,
     pub name: String,
@@ -39,3 +41,39 @@ pub struct StudentInfoView {
     pub info_dto: StudentInfoDto,
     pub exams_dto: Vec<StudentExamDto>,
 }
+
+pub fn student_info_to_view(
+    info: student_infos::Model,
+    exams: Vec<student_exams::Model>,
+    name: String,
+) -> StudentInfoView {
+    let info_dto = StudentInfoDto {
+        academic_year: info.academic_year,
+        chinese_book: info.chinese_book,
+        english_book: info.english_book,
+        math_book: info.math_book,
+        science_book: info.science_book,
+        social_studies_book: info.social_studies_book,
+        comment: info.comment,
+    };
+
+    let exams_dto = exams
+        .into_iter()
+        .map(|exam| StudentExamDto {
+            semester: exam.semester,
+            exam_type: exam.exam_type,
+            chinese_score: exam.chinese_score,
+            english_score: exam.english_score,
+            math_score: exam.math_score,
+            science_score: exam.science_score,
+            social_studies_score: exam.social_studies_score,
+        })
+        .collect();
+
+    StudentInfoView {
+        id: info.id,
+        name,
+        info_dto,
+        exams_dto,
+    }
+}
@@ -1,10 +1,15 @@
 use crate::db::entities::{members, teachers};
 use crate::models::MemberDto;
+use chrono::{TimeZone, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::{Deserialize, Serialize};
+use utoipa::openapi::schema::{ObjectBuilder, OneOfBuilder, Schema, Type};
+use utoipa::openapi::RefOr;
+use utoipa::{PartialSchema, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct AddTeacherRequest {
     pub member_id: Option<Uuid>,
     #[validate(length(min = 4, message = "使用者名稱至少需要4個字元"))]
@@ -18,8 +23,9 @@ pub struct AddTeacherRequest {
     pub member_dto: MemberDto,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateTeacherRequest {
+    /// 只能用來重設其他教職員的密碼，本人請使用 `PUT /api/me/password`
     #[validate(length(min = 8, message = "密碼至少需要8個字元"))]
     pub password: Option<String>,
     pub employment_type: EmploymentType,
@@ -29,13 +35,18 @@ pub struct UpdateTeacherRequest {
     pub member_dto: MemberDto,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TeacherView {
     pub member_id: Uuid,
     pub username: String,
+    pub role_id: Uuid,
     pub employment_type: EmploymentType,
     pub responsibility: Option<String>,
     pub background: Option<String>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_login_at: Option<DateTimeWithTimeZone>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub locked_until: Option<DateTimeWithTimeZone>,
     #[serde(flatten)]
     pub member_dto: MemberDto,
 }
@@ -46,41 +57,20 @@ pub fn teacher_and_member_to_view(teacher: teachers::Model, member: members::Mod
     TeacherView {
         member_id,
         username: teacher.username,
+        role_id: teacher.role_id,
         employment_type: EmploymentType::from(teacher.employment_type),
         responsibility: teacher.responsibility,
         background: teacher.background,
+        last_login_at: teacher
+            .last_login_at
+            .map(|last_login_at| Utc.from_utc_datetime(&last_login_at).into()),
+        locked_until: teacher
+            .locked_until
+            .map(|locked_until| Utc.from_utc_datetime(&locked_until).into()),
         member_dto,
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum RoleType {
-    SuperAdmin,
-    Admin,
-}
-
-impl TryFrom<i16> for RoleType {
-    type Error = &'static str;
-
-    fn try_from(value: i16) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(RoleType::SuperAdmin),
-            1 => Ok(RoleType::Admin),
-            _ => Err("無效值"),
-        }
-    }
-}
-
-impl From<RoleType> for i16 {
-    fn from(role: RoleType) -> i16 {
-        match role {
-            RoleType::SuperAdmin => 0,
-            RoleType::Admin => 1,
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(from = "i16")]
@@ -110,3 +100,23 @@ impl From<EmploymentType> for i16 {
         }
     }
 }
+
+/// 回應中是 snake_case 的字串，請求中則以 0（全職）、1（半職）、2（志工）表示
+impl PartialSchema for EmploymentType {
+    fn schema() -> RefOr<Schema> {
+        OneOfBuilder::new()
+            .item(
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .enum_values(Some(["full_time", "half_time", "volunteer"])),
+            )
+            .item(
+                ObjectBuilder::new()
+                    .schema_type(Type::Integer)
+                    .enum_values(Some([0, 1, 2])),
+            )
+            .into()
+    }
+}
+
+impl ToSchema for EmploymentType {}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorSetupView {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct EnableTwoFactorRequest {
+    pub code: String,
+}
+
+/// 停用兩步驟驗證或重新產生復原碼前，需再次確認目前的密碼
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConfirmPasswordRequest {
+    pub current_password: String,
+}
+
+/// 驗證器 App 的 6 位數驗證碼或復原碼皆可使用
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TwoFactorLoginRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodesView {
+    pub recovery_codes: Vec<String>,
+}
@@ -1,3 +1,5 @@
+mod openapi;
 mod route;
 
-pub use route::new_route;
+pub use openapi::openapi;
+pub use route::{metrics_route, new_route};
@@ -0,0 +1,310 @@
+use crate::models::AppResponse;
+use crate::services::prelude::*;
+use axum::Json;
+use std::sync::LazyLock;
+use utoipa::openapi::path::{Operation, PathItem};
+use utoipa::openapi::schema::{ObjectBuilder, Type};
+use utoipa::openapi::security::{
+    ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme,
+};
+use utoipa::openapi::{Content, Ref, RefOr, Response, ResponseBuilder};
+use utoipa::{OpenApi, PartialSchema, ToSchema};
+
+/// 產生一次後重複使用，內容只取決於程式本身
+static OPENAPI: LazyLock<utoipa::openapi::OpenApi> = LazyLock::new(openapi);
+
+/// 教職員的路由，路徑相對於 `/api`
+#[derive(OpenApi)]
+#[openapi(paths(
+    me_handler,
+    change_password,
+    disable_two_factor,
+    setup_two_factor,
+    enable_two_factor,
+    regenerate_recovery_codes,
+    get_my_students,
+    get_members,
+    add_member,
+    update_member,
+    get_family_relations,
+    upsert_family_relation,
+    delete_family_relation,
+    get_guardians,
+    add_guardian,
+    delete_guardian,
+    reset_guardian_password,
+    get_teachers,
+    add_teacher,
+    update_teacher,
+    delete_teacher,
+    unlock_teacher,
+    assign_teacher_role,
+    get_teacher_students,
+    assign_student,
+    unassign_student,
+    get_teacher_oidc_identity,
+    upsert_teacher_oidc_identity,
+    delete_teacher_oidc_identity,
+    issue_password_reset_code,
+    get_login_history,
+    reset_teacher_two_factor,
+    get_teacher_sessions,
+    revoke_teacher_sessions,
+    revoke_teacher_session,
+    get_students,
+    add_student,
+    update_student,
+    delete_student,
+    get_student_infos,
+    add_student_infos,
+    update_student_infos,
+    delete_student_infos,
+    get_announcements,
+    add_announcement,
+    update_announcement,
+    delete_announcement,
+    get_security_settings,
+    update_security_settings,
+    get_roles,
+    add_role,
+    update_role,
+    delete_role,
+    get_permissions,
+    get_api_tokens,
+    create_api_token,
+    revoke_api_token,
+    get_attendance_record,
+    add_attendance_record,
+    update_attendance,
+))]
+struct StaffApi;
+
+/// 家長入口的路由，路徑相對於 `/api/guardian`
+#[derive(OpenApi)]
+#[openapi(paths(
+    guardian_me_handler,
+    change_guardian_password,
+    get_guardian_announcements,
+    get_child_attendance,
+    get_child_exams,
+))]
+struct GuardianApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Church Community Center API"),
+    paths(
+        login_handler,
+        two_factor_login_handler,
+        oidc_login_handler,
+        oidc_callback_handler,
+        logout_handler,
+        refresh_handler,
+        redeem_password_reset_code,
+        guardian_login_handler,
+        guardian_logout_handler,
+        health_handler,
+        readiness_handler,
+        metrics_handler,
+    ),
+    tags(
+        (name = "auth", description = "教職員登入、登出與換發 token"),
+        (name = "me", description = "目前登入的教職員"),
+        (name = "members", description = "成員與家庭關係"),
+        (name = "guardians", description = "家長帳號管理"),
+        (name = "teachers", description = "教職員管理"),
+        (name = "students", description = "學生"),
+        (name = "student-infos", description = "學生的考試與其他紀錄"),
+        (name = "announcements", description = "公告"),
+        (name = "settings", description = "系統設定"),
+        (name = "roles", description = "角色與權限"),
+        (name = "api-tokens", description = "供其他系統使用的 API token"),
+        (name = "attendance", description = "點名"),
+        (name = "guardian-portal", description = "家長入口"),
+        (name = "health", description = "探測與指標"),
+    )
+)]
+struct ApiDoc;
+
+/// 教職員可使用 cookie 或 API token 驗證
+const STAFF_SCHEMES: &[&str] = &["auth_token", "api_token"];
+/// 以 `session_only` 包起來的路由只接受 cookie
+const SESSION_SCHEMES: &[&str] = &["auth_token"];
+/// 家長入口只接受 cookie
+const GUARDIAN_SCHEMES: &[&str] = &["guardian_token"];
+const CSRF_SCHEME: &str = "csrf_token";
+const NO_SCOPES: [&str; 0] = [];
+
+/// 完整的 OpenAPI 文件，包含驗證方式與共用的錯誤回應。
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut staff = StaffApi::openapi();
+    set_security(&mut staff, STAFF_SCHEMES);
+    let mut guardian = GuardianApi::openapi();
+    set_security(&mut guardian, GUARDIAN_SCHEMES);
+
+    let mut doc = ApiDoc::openapi()
+        .nest("/api", staff)
+        .nest("/api/guardian", guardian);
+    doc.info.version = env!("CARGO_PKG_VERSION").to_string();
+    doc.info.description = Some(
+        "所有 JSON 回應都使用 `AppResponse` 格式，成功時資料放在 `data`，錯誤時只有 `message`。\
+         以 cookie 驗證時，POST、PUT 與 DELETE 請求需要在 `X-CSRF-Token` 帶上 `csrf_token` cookie 的值。"
+            .to_string(),
+    );
+
+    let components = doc.components.get_or_insert_with(Default::default);
+    // 沒有資料的 `AppResponse` 以 `()` 作為 `data` 的型別，不會自動加入 components
+    components.schemas.insert(
+        <()>::name().into_owned(),
+        ObjectBuilder::new().schema_type(Type::Null).into(),
+    );
+    components.add_security_scheme(
+        "auth_token",
+        SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+            "auth_token",
+            "教職員登入後設定的 cookie",
+        ))),
+    );
+    components.add_security_scheme(
+        "api_token",
+        SecurityScheme::Http(
+            HttpBuilder::new()
+                .scheme(HttpAuthScheme::Bearer)
+                .description(Some("在系統中建立的 API token，不需要 CSRF token"))
+                .build(),
+        ),
+    );
+    components.add_security_scheme(
+        "guardian_token",
+        SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+            GUARDIAN_TOKEN_COOKIE,
+            "家長登入後設定的 cookie",
+        ))),
+    );
+    components.add_security_scheme(
+        CSRF_SCHEME,
+        SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+            "X-CSRF-Token",
+            "與 `csrf_token` cookie 相同的值",
+        ))),
+    );
+
+    for (path, item) in doc.paths.paths.iter_mut() {
+        // 探測與指標不經過 API 的 middleware，只回傳各自文件中的狀態碼
+        if !path.starts_with("/api/") {
+            continue;
+        }
+        for operation in operations_mut(item) {
+            add_error_responses(operation);
+        }
+    }
+
+    doc
+}
+
+/// 回傳 OpenAPI 文件，不需要登入。
+pub async fn openapi_handler() -> Json<&'static utoipa::openapi::OpenApi> {
+    Json(&OPENAPI)
+}
+
+/// 以 cookie 驗證時，修改資料的請求還需要 CSRF token；API token 不需要。
+fn set_security(doc: &mut utoipa::openapi::OpenApi, schemes: &[&str]) {
+    for item in doc.paths.paths.values_mut() {
+        if let Some(operation) = item.get.as_mut() {
+            require_login(operation, schemes, false);
+        }
+        for operation in [&mut item.put, &mut item.post, &mut item.delete] {
+            if let Some(operation) = operation.as_mut() {
+                require_login(operation, schemes, true);
+            }
+        }
+    }
+}
+
+fn require_login(operation: &mut Operation, schemes: &[&str], needs_csrf: bool) {
+    // handler 已標示 `security` 的是不接受 API token 的路由，
+    // 是否與 route.rs 中以 `session_only` 包起來的路由一致由 route.rs 的測試檢查
+    let schemes = match operation.security {
+        Some(_) => SESSION_SCHEMES,
+        None => schemes,
+    };
+    let requirements = schemes.iter().map(|&scheme| {
+        let requirement = SecurityRequirement::new(scheme, NO_SCOPES);
+        match needs_csrf && scheme != "api_token" {
+            true => requirement.add(CSRF_SCHEME, NO_SCOPES),
+            false => requirement,
+        }
+    });
+    operation.security = Some(requirements.collect());
+    operation
+        .responses
+        .responses
+        .entry("401".to_string())
+        .or_insert_with(|| {
+            ResponseBuilder::new()
+                .description("未登入、登入已過期或 token 無效，沒有回應內容")
+                .build()
+                .into()
+        });
+}
+
+fn add_error_responses(operation: &mut Operation) {
+    let responses = &mut operation.responses.responses;
+    let has_input = operation.request_body.is_some()
+        || operation
+            .parameters
+            .as_ref()
+            .is_some_and(|parameters| !parameters.is_empty());
+    if has_input {
+        responses
+            .entry("400".to_string())
+            .or_insert_with(|| error_response("參數錯誤"));
+        // 路徑、查詢參數或 JSON 無法解析時由 axum 直接回傳純文字
+        if operation.request_body.is_some() {
+            responses
+                .entry("422".to_string())
+                .or_insert_with(|| text_response("JSON 欄位缺少或格式不符"));
+        }
+    }
+
+    // 權限不足、CSRF token 無效或尚未完成必要的帳號設定
+    if operation.security.is_some() {
+        responses
+            .entry("403".to_string())
+            .or_insert_with(|| error_response("沒有權限執行此操作"));
+    }
+
+    responses
+        .entry("500".to_string())
+        .or_insert_with(|| error_response("伺服器發生異常"));
+}
+
+fn error_response(description: &str) -> RefOr<Response> {
+    ResponseBuilder::new()
+        .description(description)
+        .content(
+            "application/json",
+            Content::new(Some(Ref::from_schema_name(<AppResponse>::name()))),
+        )
+        .build()
+        .into()
+}
+
+fn text_response(description: &str) -> RefOr<Response> {
+    ResponseBuilder::new()
+        .description(description)
+        .content("text/plain", Content::new(Some(String::schema())))
+        .build()
+        .into()
+}
+
+fn operations_mut(item: &mut PathItem) -> impl Iterator<Item = &mut Operation> {
+    [
+        &mut item.get,
+        &mut item.put,
+        &mut item.post,
+        &mut item.delete,
+    ]
+    .into_iter()
+    .filter_map(Option::as_mut)
+}
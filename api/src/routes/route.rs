@@ -1,113 +1,686 @@
-use crate::config::CONFIG;
+use super::openapi::openapi_handler;
+use crate::config::{CorsConfig, CONFIG};
+use crate::models::{AppResponse, Permission, PermissionSet};
 use crate::services::prelude::*;
 use crate::util;
 use axum::body::Body;
-use axum::http::{header, HeaderValue, Method, Request, StatusCode};
-use axum::routing::{get, post, put};
+use axum::extract::{MatchedPath, State};
+use axum::http::{header, HeaderMap, HeaderName, Method, Request, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{delete, get, post, put, MethodRouter};
 use axum::{middleware, middleware::Next, response::Response, Router};
-use log::info;
 use sea_orm::DatabaseConnection;
 use tower_cookies::CookieManagerLayer;
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
+use tracing::{debug, info};
+use utoipa_swagger_ui::{Config as SwaggerConfig, SwaggerUi};
+
+/// 必須變更密碼的帳號仍可存取的路由（相對於 `/api`）
+const PASSWORD_CHANGE_ALLOWED_PATHS: &[&str] = &["/me", "/me/password"];
+/// 系統要求兩步驟驗證、但尚未啟用的帳號仍可存取的路由
+const TWO_FACTOR_ENROLLMENT_ALLOWED_PATHS: &[&str] = &[
+    "/me",
+    "/me/password",
+    "/me/two-factor/setup",
+    "/me/two-factor/enable",
+];
+
+/// 必須變更密碼的家長帳號仍可存取的路由（相對於 `/api/guardian`）
+const GUARDIAN_PASSWORD_CHANGE_ALLOWED_PATHS: &[&str] = &["/me", "/me/password"];
 
 pub fn new_route(db: DatabaseConnection) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(CONFIG.server.cors.parse::<HeaderValue>().unwrap())
-        .allow_methods([
-            Method::GET,
-            Method::POST,
-            Method::PUT,
-            Method::DELETE,
-            Method::OPTIONS,
-        ])
-        .allow_credentials(true)
-        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION, header::ACCEPT]);
+    let cors = cors_layer(&CONFIG.server.cors);
+
+    let protected_routes = protected_routes()
+        .layer(middleware::from_fn_with_state(db.clone(), auth_middleware))
+        .layer(middleware::from_fn(csrf_middleware));
+
+    // 家長入口只能讀取自己子女的資料，與教職員的路由分開驗證
+    let guardian_routes = Router::new()
+        .route("/me", get(guardian_me_handler))
+        .route("/me/password", put(change_guardian_password))
+        .route("/announcements", get(get_guardian_announcements))
+        .route(
+            "/children/{student_id}/attendance",
+            get(get_child_attendance),
+        )
+        .route("/children/{student_id}/exams", get(get_child_exams))
+        .layer(middleware::from_fn_with_state(
+            db.clone(),
+            guardian_middleware,
+        ))
+        .layer(middleware::from_fn(csrf_middleware));
+
+    let router = Router::new()
+        .route("/api/login", post(login_handler))
+        .route("/api/login/two-factor", post(two_factor_login_handler))
+        .route("/api/login/oidc", get(oidc_login_handler))
+        .route("/api/login/oidc/callback", get(oidc_callback_handler))
+        .route(
+            "/api/logout",
+            post(logout_handler).layer(middleware::from_fn(csrf_middleware)),
+        )
+        .route(
+            "/api/refresh",
+            post(refresh_handler).layer(middleware::from_fn(csrf_middleware)),
+        )
+        .route("/api/password-reset", post(redeem_password_reset_code))
+        .route("/api/openapi.json", get(openapi_handler))
+        .route("/api/guardian/login", post(guardian_login_handler))
+        .route(
+            "/api/guardian/logout",
+            post(guardian_logout_handler).layer(middleware::from_fn(csrf_middleware)),
+        )
+        .nest("/api/guardian", guardian_routes)
+        .nest("/api", protected_routes)
+        .layer(CookieManagerLayer::new())
+        .layer(cors)
+        .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn(log_request))
+        // 探測路由加在上面的 layer 之後，不需要 CORS，也不會每隔幾秒寫一筆請求日誌
+        .route("/healthz", get(health_handler))
+        .route("/readyz", get(readiness_handler));
 
-    let protected_routes = Router::new()
-        .route("/me", get(me_handler))
-        .route("/members", get(get_members).post(add_member))
-        .route("/members/{id}", put(update_member))
-        .route("/teachers", get(get_teachers).post(add_teacher))
-        .route("/teachers/{id}", put(update_teacher).delete(delete_teacher))
-        .route("/students", get(get_students).post(add_student))
-        .route("/students/{id}", put(update_student).delete(delete_student))
+    // Swagger UI 讀取上面的 `/api/openapi.json`，不另外提供一份文件
+    let router = match CONFIG.server.swagger_ui {
+        true => router
+            .merge(SwaggerUi::new("/api/docs").config(SwaggerConfig::new(["/api/openapi.json"]))),
+        false => router,
+    };
+
+    // 未另外指定位址時，指標與 API 使用同一個位址
+    let router = match CONFIG.metrics.enabled && CONFIG.metrics.address.is_none() {
+        true => router.route("/metrics", get(metrics_handler)),
+        false => router,
+    };
+
+    router.with_state(db)
+}
+
+/// 需要教職員登入的路由（相對於 `/api`），驗證身分的 middleware 由 `new_route` 加上。
+fn protected_routes() -> Router<DatabaseConnection> {
+    Router::new()
+        .route("/me", session_only(get(me_handler)))
+        .route("/me/password", session_only(put(change_password)))
+        .route("/me/two-factor", session_only(delete(disable_two_factor)))
+        .route("/me/two-factor/setup", session_only(post(setup_two_factor)))
+        .route(
+            "/me/two-factor/enable",
+            session_only(post(enable_two_factor)),
+        )
+        .route(
+            "/me/two-factor/recovery-codes",
+            session_only(post(regenerate_recovery_codes)),
+        )
+        .route(
+            "/me/students",
+            with_permission(get(get_my_students), Permission::StudentsRead),
+        )
+        .route(
+            "/members",
+            with_permission(get(get_members), Permission::MembersRead)
+                .merge(with_permission(post(add_member), Permission::MembersWrite)),
+        )
+        .route(
+            "/members/{id}",
+            with_permission(put(update_member), Permission::MembersWrite),
+        )
+        .route(
+            "/members/{id}/relatives",
+            with_permission(get(get_family_relations), Permission::MembersRead),
+        )
+        .route(
+            "/members/{id}/relatives/{relative_id}",
+            with_permission(
+                put(upsert_family_relation).delete(delete_family_relation),
+                Permission::MembersWrite,
+            ),
+        )
+        .route(
+            "/guardians",
+            with_permission(
+                get(get_guardians).post(add_guardian),
+                Permission::GuardiansManage,
+            ),
+        )
+        .route(
+            "/guardians/{id}",
+            with_permission(delete(delete_guardian), Permission::GuardiansManage),
+        )
+        .route(
+            "/guardians/{id}/password",
+            with_permission(put(reset_guardian_password), Permission::GuardiansManage),
+        )
+        .route(
+            "/teachers",
+            with_permission(get(get_teachers), Permission::TeachersRead).merge(with_permission(
+                post(add_teacher),
+                Permission::TeachersManage,
+            )),
+        )
+        // 教職員可以修改自己的資料，權限在 handler 內判斷
+        .route(
+            "/teachers/{id}",
+            session_only(put(update_teacher)).merge(with_permission(
+                delete(delete_teacher),
+                Permission::TeachersManage,
+            )),
+        )
+        .route(
+            "/teachers/{id}/unlock",
+            with_permission(post(unlock_teacher), Permission::TeachersManage),
+        )
+        .route(
+            "/teachers/{id}/role",
+            with_permission(put(assign_teacher_role), Permission::RolesManage),
+        )
+        .route(
+            "/teachers/{id}/students",
+            with_permission(get(get_teacher_students), Permission::TeachersManage),
+        )
+        .route(
+            "/teachers/{id}/students/{student_id}",
+            with_permission(
+                put(assign_student).delete(unassign_student),
+                Permission::TeachersManage,
+            ),
+        )
+        .route(
+            "/teachers/{id}/oidc-identity",
+            with_permission(
+                get(get_teacher_oidc_identity)
+                    .put(upsert_teacher_oidc_identity)
+                    .delete(delete_teacher_oidc_identity),
+                Permission::TeachersManage,
+            ),
+        )
+        .route(
+            "/teachers/{id}/password-reset-code",
+            with_permission(post(issue_password_reset_code), Permission::TeachersManage),
+        )
+        .route(
+            "/teachers/{id}/login-history",
+            session_only(get(get_login_history)),
+        )
+        .route(
+            "/teachers/{id}/two-factor",
+            with_permission(delete(reset_teacher_two_factor), Permission::TeachersManage),
+        )
+        .route(
+            "/teachers/{id}/sessions",
+            with_permission(
+                get(get_teacher_sessions).delete(revoke_teacher_sessions),
+                Permission::TeachersManage,
+            ),
+        )
+        .route(
+            "/teachers/{id}/sessions/{session_id}",
+            with_permission(delete(revoke_teacher_session), Permission::TeachersManage),
+        )
+        .route(
+            "/students",
+            with_permission(get(get_students), Permission::StudentsRead).merge(with_permission(
+                post(add_student),
+                Permission::StudentsWrite,
+            )),
+        )
+        .route(
+            "/students/{id}",
+            with_permission(
+                put(update_student).delete(delete_student),
+                Permission::StudentsWrite,
+            ),
+        )
         .route(
             "/student_infos",
-            get(get_student_infos).post(add_student_infos),
+            with_permission(get(get_student_infos), Permission::StudentsRead).merge(
+                with_permission(post(add_student_infos), Permission::StudentsWrite),
+            ),
         )
         .route(
             "/student_infos/{id}",
-            put(update_student_infos).delete(delete_student_infos),
+            with_permission(
+                put(update_student_infos).delete(delete_student_infos),
+                Permission::StudentsWrite,
+            ),
         )
+        // 公告的修改與刪除允許發布者本人，權限在 handler 內判斷
         .route(
             "/announcements",
-            get(get_announcements).post(add_announcement),
+            session_only(get(get_announcements)).merge(with_permission(
+                post(add_announcement),
+                Permission::AnnouncementsWrite,
+            )),
         )
         .route(
             "/announcements/{id}",
-            put(update_announcement).delete(delete_announcement),
+            session_only(put(update_announcement).delete(delete_announcement)),
+        )
+        .route(
+            "/settings/security",
+            with_permission(
+                get(get_security_settings).put(update_security_settings),
+                Permission::SettingsManage,
+            ),
+        )
+        .route(
+            "/roles",
+            with_permission(get(get_roles).post(add_role), Permission::RolesManage),
+        )
+        .route(
+            "/roles/{id}",
+            with_permission(
+                put(update_role).delete(delete_role),
+                Permission::RolesManage,
+            ),
+        )
+        .route(
+            "/api-tokens",
+            session_only(with_permission(
+                get(get_api_tokens).post(create_api_token),
+                Permission::ApiTokensManage,
+            )),
+        )
+        .route(
+            "/api-tokens/{id}",
+            session_only(with_permission(
+                delete(revoke_api_token),
+                Permission::ApiTokensManage,
+            )),
+        )
+        .route(
+            "/permissions",
+            with_permission(get(get_permissions), Permission::RolesManage),
+        )
+        .route(
+            "/attendance-records",
+            with_permission(get(get_attendance_record), Permission::AttendanceRead),
         )
-        .route("/attendance-records", get(get_attendance_record))
         .route(
             "/attendance-records/{id}",
-            post(add_attendance_record).put(update_attendance),
+            with_permission(
+                post(add_attendance_record).put(update_attendance),
+                Permission::AttendanceWrite,
+            ),
         )
-        .layer(middleware::from_fn(auth_middleware));
+}
 
+/// 在 `metrics.address` 上單獨提供 `/metrics` 的路由。
+pub fn metrics_route(db: DatabaseConnection) -> Router {
     Router::new()
-        .route("/api/login", post(login_handler))
-        .route("/api/logout", post(logout_handler))
-        .nest("/api", protected_routes)
-        .layer(CookieManagerLayer::new())
-        .layer(cors)
-        .layer(TraceLayer::new_for_http())
-        .layer(middleware::from_fn(log_request))
+        .route("/metrics", get(metrics_handler))
         .with_state(db)
 }
 
-async fn auth_middleware(mut req: Request<Body>, next: Next) -> Result<Response, StatusCode> {
-    let cookies = req
-        .headers()
-        .get(header::COOKIE)
-        .ok_or(StatusCode::UNAUTHORIZED)?
-        .to_str()
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+async fn auth_middleware(
+    State(db): State<DatabaseConnection>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let (claims, permissions) = match find_bearer_token(req.headers()) {
+        Some(token) => {
+            let (claims, permissions) = authenticate_api_token(&db, &token)
+                .await
+                .map_err(|(status_code, _)| status_code)?
+                .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    let token_cookie = cookies
-        .split(';')
-        .find_map(|cookie| {
-            let cookie = cookie.trim();
-            if cookie.starts_with("auth_token=") {
-                Some(cookie.trim_start_matches("auth_token=").to_string())
-            } else {
-                None
+            req.extensions_mut().insert(ApiTokenAuth);
+            (claims, permissions)
+        }
+        None => {
+            let token_cookie =
+                find_cookie(req.headers(), "auth_token").ok_or(StatusCode::UNAUTHORIZED)?;
+
+            let token_data = util::decode_token(&token_cookie)?;
+
+            // 已登出或被撤銷的登入階段，即使 token 尚未過期也不允許使用
+            find_active_session(&db, token_data.claims.jti)
+                .await
+                .map_err(|(status_code, _)| status_code)?
+                .ok_or(StatusCode::UNAUTHORIZED)?;
+
+            // 尚未完成必要的帳號設定前，只允許存取完成設定所需的路由
+            if let Some((allowed_paths, message)) = restricted_paths(&token_data.claims) {
+                if !allowed_paths.contains(&req.uri().path()) {
+                    return Ok(AppResponse::error(StatusCode::FORBIDDEN, message).into_response());
+                }
             }
-        })
+
+            // 每次請求都從資料庫讀取權限，角色調整後不需要重新登入即可生效
+            let permissions = load_teacher_permissions(&db, token_data.claims.sub)
+                .await
+                .map_err(|(status_code, _)| status_code)?;
+
+            (token_data.claims, permissions)
+        }
+    };
+
+    req.extensions_mut().insert(claims);
+    req.extensions_mut().insert(permissions);
+    Ok(next.run(req).await)
+}
+
+async fn guardian_middleware(
+    State(db): State<DatabaseConnection>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let token_cookie =
+        find_cookie(req.headers(), GUARDIAN_TOKEN_COOKIE).ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let token_data = util::decode_guardian_token(&token_cookie)?;
+
+    find_active_guardian_session(&db, token_data.claims.jti)
+        .await
+        .map_err(|(status_code, _)| status_code)?
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    let token_data = util::decode_token(&token_cookie)?;
+    if token_data.claims.must_change_password
+        && !GUARDIAN_PASSWORD_CHANGE_ALLOWED_PATHS.contains(&req.uri().path())
+    {
+        return Ok(AppResponse::error(StatusCode::FORBIDDEN, "請先變更密碼").into_response());
+    }
+
     req.extensions_mut().insert(token_data.claims);
     Ok(next.run(req).await)
 }
 
+/// 以 cookie 驗證身分的路由，在修改資料的請求上要求 double-submit CSRF token，
+/// 並在瀏覽器帶有 `Origin` 或 `Referer` 時確認來自允許的前端網址。
+async fn csrf_middleware(req: Request<Body>, next: Next) -> Response {
+    // 使用 API token 的請求不會自動帶上憑證，不需要 CSRF 防護
+    if matches!(
+        *req.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    ) || find_bearer_token(req.headers()).is_some()
+    {
+        return next.run(req).await;
+    }
+
+    let headers = req.headers();
+    let source = headers
+        .get(header::ORIGIN)
+        .or_else(|| headers.get(header::REFERER))
+        .map(|value| value.to_str().unwrap_or_default());
+    if source.is_some_and(|source| !util::is_allowed_origin(source)) {
+        return AppResponse::error(StatusCode::FORBIDDEN, "不允許的請求來源").into_response();
+    }
+
+    let cookie_token = find_cookie(headers, util::CSRF_COOKIE);
+    let header_token = headers
+        .get(util::CSRF_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let is_valid = match (cookie_token.as_deref(), header_token) {
+        (Some(cookie_token), Some(header_token)) => {
+            util::csrf_token_matches(cookie_token, header_token)
+        }
+        _ => false,
+    };
+
+    if !is_valid {
+        return AppResponse::error(StatusCode::FORBIDDEN, "CSRF token 無效，請重新整理頁面")
+            .into_response();
+    }
+
+    next.run(req).await
+}
+
+fn find_bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(|token| token.trim().to_string())
+}
+
+fn find_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .find_map(|cookie| {
+            let (key, value) = cookie.trim().split_once('=')?;
+            (key == name).then(|| value.to_string())
+        })
+}
+
+/// 不檢查權限的路由必須用這個包起來，API token 只能使用 `with_permission` 限定範圍的路由，
+/// 也不能用來管理 token 本身，避免 token 外洩後被用來變更密碼或接管帳號。
+fn session_only(
+    method_router: MethodRouter<DatabaseConnection>,
+) -> MethodRouter<DatabaseConnection> {
+    method_router.route_layer(middleware::from_fn(deny_api_token))
+}
+
+async fn deny_api_token(req: Request<Body>, next: Next) -> Response {
+    if req.extensions().get::<ApiTokenAuth>().is_some() {
+        return AppResponse::error(StatusCode::FORBIDDEN, "API token 無法使用此功能")
+            .into_response();
+    }
+
+    next.run(req).await
+}
+
+/// 為路由加上權限檢查，缺少指定權限時回傳 403。
+fn with_permission(
+    method_router: MethodRouter<DatabaseConnection>,
+    permission: Permission,
+) -> MethodRouter<DatabaseConnection> {
+    method_router.route_layer(middleware::from_fn_with_state(
+        permission,
+        require_permission,
+    ))
+}
+
+async fn require_permission(
+    State(permission): State<Permission>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let allowed = req
+        .extensions()
+        .get::<PermissionSet>()
+        .is_some_and(|permissions| permissions.contains(permission));
+
+    if !allowed {
+        return AppResponse::error(StatusCode::FORBIDDEN, "權限不足").into_response();
+    }
+
+    next.run(req).await
+}
+
+fn restricted_paths(claims: &util::Claims) -> Option<(&'static [&'static str], &'static str)> {
+    if claims.must_change_password {
+        return Some((PASSWORD_CHANGE_ALLOWED_PATHS, "請先變更密碼"));
+    }
+
+    if claims.must_enroll_two_factor {
+        return Some((TWO_FACTOR_ENROLLMENT_ALLOWED_PATHS, "請先啟用兩步驟驗證"));
+    }
+
+    None
+}
+
+/// 方法與標頭已在載入設定時檢查過，`filter_map` 實際上不會略過任何設定。
+fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    let methods: Vec<Method> = config
+        .allowed_methods
+        .iter()
+        .filter_map(|method| Method::from_bytes(method.as_bytes()).ok())
+        .collect();
+    let headers: Vec<HeaderName> = config
+        .allowed_headers
+        .iter()
+        .filter_map(|header| HeaderName::from_bytes(header.as_bytes()).ok())
+        .chain([HeaderName::from_static(util::CSRF_HEADER)])
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(|origin, _| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| CONFIG.server.cors.allows_origin(origin))
+        }))
+        .allow_methods(methods)
+        .allow_credentials(true)
+        .allow_headers(headers)
+}
+
 async fn log_request(req: Request<Body>, next: Next) -> Response {
     let start = std::time::Instant::now();
     let method = req.method().clone();
     let uri = req.uri().clone();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
 
-    info!("收到請求: {} {}", method, uri);
+    debug!(method = %method, uri = %uri, "request started");
 
     let response = next.run(req).await;
 
     let duration = start.elapsed();
+    let status = response.status().as_u16();
+    util::record_http_request(method.as_str(), &route, status, duration);
+
     info!(
-        "請求完成: {} {} - 狀態碼: {} - 處理時間: {:?}",
-        method,
-        uri,
-        response.status(),
-        duration
+        method = %method,
+        uri = %uri,
+        route = %route,
+        status,
+        latency_ms = duration.as_millis() as u64,
+        "request completed"
     );
 
     response
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    const TEST_ID: &str = "00000000-0000-0000-0000-000000000001";
+
+    /// 模擬 `auth_middleware` 驗證過一個擁有全部權限的 API token
+    async fn as_api_token(mut req: Request<Body>, next: Next) -> Response {
+        req.extensions_mut().insert(ApiTokenAuth);
+        req.extensions_mut()
+            .insert(Permission::ALL.into_iter().collect::<PermissionSet>());
+        next.run(req).await
+    }
+
+    /// 沒有任何權限的 API token，請求會在 `session_only` 或 `with_permission` 被擋下，不會進入 handler
+    async fn as_api_token_without_permissions(mut req: Request<Body>, next: Next) -> Response {
+        req.extensions_mut().insert(ApiTokenAuth);
+        req.extensions_mut().insert(PermissionSet::default());
+        next.run(req).await
+    }
+
+    #[tokio::test]
+    async fn api_tokens_cannot_update_teachers() {
+        let app = protected_routes()
+            .layer(middleware::from_fn(as_api_token))
+            .with_state(DatabaseConnection::Disconnected);
+
+        let req = Request::put(format!("/teachers/{}", TEST_ID))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"password":"Str0ng-Passphrase!"}"#))
+            .unwrap();
+        let response = app.oneshot(req).await.unwrap();
+
+        // 即使 token 擁有 teachers.manage，也不能透過 API token 修改教職員或重設密碼
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    /// 以 API token 送出請求，回傳是否被 `session_only` 拒絕；路由不存在時回傳 `None`
+    async fn denied_for_api_token(app: &Router, method: &Method, path: &str) -> Option<bool> {
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = app.clone().oneshot(req).await.unwrap();
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+        ) {
+            return None;
+        }
+
+        assert_eq!(
+            response.status(),
+            StatusCode::FORBIDDEN,
+            "{} {}",
+            method,
+            path
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        Some(body["message"] == "API token 無法使用此功能")
+    }
+
+    /// OpenAPI 文件中教職員的每個操作都必須有對應的路由，且只有 `session_only` 的路由不列出 API token，
+    /// 文件沒有列出的方法也不能有路由。
+    #[tokio::test]
+    async fn openapi_security_matches_session_only_routes() {
+        let app = protected_routes()
+            .layer(middleware::from_fn(as_api_token_without_permissions))
+            .with_state(DatabaseConnection::Disconnected);
+        let doc = serde_json::to_value(super::super::openapi()).unwrap();
+
+        let mut checked = 0;
+        for (path, item) in doc["paths"].as_object().unwrap() {
+            let operations = item.as_object().unwrap();
+            let schemes = |operation: &Value, scheme: &str| {
+                operation["security"]
+                    .as_array()
+                    .is_some_and(|security| security.iter().any(|item| item.get(scheme).is_some()))
+            };
+            if !operations
+                .values()
+                .any(|operation| schemes(operation, "auth_token"))
+            {
+                continue;
+            }
+
+            let path = path.strip_prefix("/api").unwrap();
+            let uri = path
+                .split('/')
+                .map(|segment| match segment.starts_with('{') {
+                    true => TEST_ID,
+                    false => segment,
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+
+            for method in [Method::GET, Method::POST, Method::PUT, Method::DELETE] {
+                let denied = denied_for_api_token(&app, &method, &uri).await;
+                let Some(operation) = operations.get(&method.as_str().to_lowercase()) else {
+                    assert_eq!(denied, None, "{} {} is not documented", method, path);
+                    continue;
+                };
+
+                let accepts_api_token = schemes(operation, "api_token");
+                assert_eq!(
+                    denied,
+                    Some(!accepts_api_token),
+                    "{} {} security does not match the route",
+                    method,
+                    path
+                );
+                checked += 1;
+            }
+        }
+
+        assert!(checked > 0);
+    }
+}
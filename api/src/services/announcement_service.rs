@@ -1,18 +1,30 @@
 use crate::db::entities::{announcements, members, teachers};
-use crate::models::{AnnouncementView, AppResponse, RoleType, UpsertAnnouncementRequest};
+use crate::models::{
+    AnnouncementAudience, AnnouncementView, AppResponse, Permission, PermissionSet,
+    UpsertAnnouncementRequest,
+};
 use crate::services::member_service::get_members_name_hashmap;
 use crate::util::Claims;
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::{Extension, Json};
 use chrono::{TimeZone, Utc};
-use log::error;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect,
 };
+use tracing::error;
 use uuid::Uuid;
 
+#[utoipa::path(
+    post,
+    path = "/announcements",
+    tag = "announcements",
+    request_body = UpsertAnnouncementRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn add_announcement(
     Extension(claims): Extension<Claims>,
     State(db): State<DatabaseConnection>,
@@ -20,20 +32,30 @@ pub async fn add_announcement(
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
     let new_announcement = announcements::ActiveModel {
         id: Default::default(),
-        publisher_id: Set(claims.sub.clone()),
+        publisher_id: Set(claims.sub),
         title: Set(payload.title),
         content: Set(payload.content),
+        audience: Set(payload.audience.unwrap_or_default().as_str().to_string()),
         ..Default::default()
     };
 
     new_announcement.insert(&db).await.map_err(|e| {
-        error!("{}", e);
+        error!(error = %e, "add_announcement failed");
         AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
     })?;
 
     Ok(AppResponse::success("建立成功"))
 }
 
+#[utoipa::path(
+    get,
+    path = "/announcements",
+    tag = "announcements",
+    security(("auth_token" = [])),
+    responses(
+        (status = 200, body = AppResponse<Vec<AnnouncementView>>, description = "成功"),
+    ),
+)]
 pub async fn get_announcements(
     State(db): State<DatabaseConnection>,
 ) -> Result<Json<AppResponse<Vec<AnnouncementView>>>, (StatusCode, Json<AppResponse>)> {
@@ -42,7 +64,7 @@ pub async fn get_announcements(
         .all(&db)
         .await
         .map_err(|e| {
-            error!("{}", e);
+            error!(error = %e, "get_announcements failed");
             AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
         })?;
 
@@ -60,6 +82,7 @@ pub async fn get_announcements(
                 .unwrap_or_else(|| "未知姓名".to_string()),
             title: announcement.title,
             content: announcement.content,
+            audience: AnnouncementAudience::from_db(&announcement.audience),
             updated_at: Utc.from_utc_datetime(&announcement.updated_at).into(),
         })
         .collect::<Vec<_>>();
@@ -67,18 +90,33 @@ pub async fn get_announcements(
     Ok(AppResponse::success_with_data(result))
 }
 
+#[utoipa::path(
+    put,
+    path = "/announcements/{id}",
+    tag = "announcements",
+    security(("auth_token" = [])),
+    params(("id" = Uuid, Path, description = "公告 id")),
+    request_body = UpsertAnnouncementRequest,
+    responses(
+        (status = 200, body = AppResponse<AnnouncementView>, description = "成功"),
+    ),
+)]
 pub async fn update_announcement(
     Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Path(announcement_id): Path<Uuid>,
     Json(payload): Json<UpsertAnnouncementRequest>,
 ) -> Result<Json<AppResponse<AnnouncementView>>, (StatusCode, Json<AppResponse>)> {
     if let Some(announcement) = find_announcement_by_id(&db, announcement_id).await? {
-        check_permission(claims.sub, announcement.publisher_id, claims.role)?;
+        check_permission(claims.sub, announcement.publisher_id, &permissions)?;
 
         let mut announcement: announcements::ActiveModel = announcement.into();
         announcement.title = Set(payload.title);
         announcement.content = Set(payload.content);
+        if let Some(audience) = payload.audience {
+            announcement.audience = Set(audience.as_str().to_string());
+        }
         announcement.updated_at = Set(Utc::now().naive_utc());
 
         let announcement: announcements::Model = announcement
@@ -101,6 +139,7 @@ pub async fn update_announcement(
             name,
             title: announcement.title,
             content: announcement.content,
+            audience: AnnouncementAudience::from_db(&announcement.audience),
             updated_at: Utc.from_utc_datetime(&announcement.updated_at).into(),
         };
 
@@ -113,13 +152,24 @@ pub async fn update_announcement(
     ))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/announcements/{id}",
+    tag = "announcements",
+    security(("auth_token" = [])),
+    params(("id" = Uuid, Path, description = "公告 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn delete_announcement(
     Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Path(announcement_id): Path<Uuid>,
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
     if let Some(announcement) = find_announcement_by_id(&db, announcement_id).await? {
-        check_permission(claims.sub, announcement.publisher_id, claims.role)?;
+        check_permission(claims.sub, announcement.publisher_id, &permissions)?;
 
         let mut announcement: announcements::ActiveModel = announcement.into();
         announcement.updated_at = Set(Utc::now().naive_utc());
@@ -149,7 +199,7 @@ async fn find_announcement_by_id(
         .one(db)
         .await
         .map_err(|e| {
-            error!("{}", e);
+            error!(error = %e, "find_announcement_by_id failed");
             AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
         })
 }
@@ -157,14 +207,14 @@ async fn find_announcement_by_id(
 fn check_permission(
     source_id: Uuid,
     target_id: Uuid,
-    role_type: RoleType,
+    permissions: &PermissionSet,
 ) -> Result<(), (StatusCode, Json<AppResponse>)> {
-    if role_type.eq(&RoleType::SuperAdmin) || source_id.eq(&target_id) {
+    if permissions.contains(Permission::AnnouncementsManage) || source_id.eq(&target_id) {
         return Ok(());
     }
 
     Err(AppResponse::error(
         StatusCode::FORBIDDEN,
-        "只有本人或是超級管理員能修改公告",
+        "只有本人或是公告管理者能修改公告",
     ))
 }
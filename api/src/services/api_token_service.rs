@@ -0,0 +1,348 @@
+use crate::db::entities::{api_token_permissions, api_tokens};
+use crate::models::{
+    api_token_to_view, ApiTokenView, AppResponse, CreateApiTokenRequest, CreatedApiTokenView,
+    Permission, PermissionSet,
+};
+use crate::services::role_service::load_teacher_permissions;
+use crate::services::teacher_service::find_teacher_by_id;
+use crate::util::{generate_opaque_token, hash_token, Claims};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+/// 方便在程式碼或設定檔中辨識出外洩的 token
+const API_TOKEN_PREFIX: &str = "ccc_";
+/// 顯示在列表中的 token 開頭長度（包含前綴）
+const API_TOKEN_DISPLAY_LENGTH: usize = 12;
+
+#[utoipa::path(
+    get,
+    path = "/api-tokens",
+    tag = "api-tokens",
+    security(("auth_token" = [])),
+    responses(
+        (status = 200, body = AppResponse<Vec<ApiTokenView>>, description = "成功"),
+    ),
+)]
+pub async fn get_api_tokens(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<AppResponse<Vec<ApiTokenView>>>, (StatusCode, Json<AppResponse>)> {
+    let tokens_with_permissions = api_tokens::Entity::find()
+        .order_by_desc(api_tokens::Column::CreatedAt)
+        .find_with_related(api_token_permissions::Entity)
+        .all(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_api_tokens failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let result = tokens_with_permissions
+        .into_iter()
+        .map(|(api_token, permissions)| {
+            api_token_to_view(api_token, parse_permissions(permissions).to_vec())
+        })
+        .collect();
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api-tokens",
+    tag = "api-tokens",
+    security(("auth_token" = [])),
+    request_body = CreateApiTokenRequest,
+    responses(
+        (status = 200, body = AppResponse<CreatedApiTokenView>, description = "成功"),
+    ),
+)]
+pub async fn create_api_token(
+    Extension(claims): Extension<Claims>,
+    Extension(creator_permissions): Extension<PermissionSet>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateApiTokenRequest>,
+) -> Result<Json<AppResponse<CreatedApiTokenView>>, (StatusCode, Json<AppResponse>)> {
+    if let Err(err) = payload.validate() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("參數不正確。錯誤參數：{}", err),
+        ));
+    }
+
+    let now = Utc::now().naive_utc();
+    let expires_at = payload.expires_at.naive_utc();
+    if expires_at <= now {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "到期時間必須晚於現在",
+        ));
+    }
+
+    if find_teacher_by_id(&db, payload.teacher_id).await?.is_none() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "找不到對應的教職員",
+        ));
+    }
+
+    let permissions: PermissionSet = payload.permissions.into_iter().collect();
+    let owner_permissions = match payload.teacher_id == claims.sub {
+        true => None,
+        false => Some(load_teacher_permissions(&db, payload.teacher_id).await?),
+    };
+    check_api_token_grant(
+        &creator_permissions,
+        &permissions,
+        owner_permissions.as_ref(),
+    )
+    .map_err(|message| AppResponse::error(StatusCode::FORBIDDEN, message))?;
+
+    let token = format!("{}{}", API_TOKEN_PREFIX, generate_opaque_token());
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "create_api_token failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let new_token = api_tokens::ActiveModel {
+        teacher_id: Set(payload.teacher_id),
+        created_by: Set(claims.sub),
+        name: Set(payload.name),
+        token_hash: Set(hash_token(&token)),
+        token_prefix: Set(token[..API_TOKEN_DISPLAY_LENGTH].to_string()),
+        created_at: Set(now),
+        expires_at: Set(expires_at),
+        ..Default::default()
+    };
+
+    let api_token = new_token.insert(&txn).await.map_err(|e| {
+        error!(error = %e, "create_api_token failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let new_permissions: Vec<api_token_permissions::ActiveModel> = permissions
+        .to_vec()
+        .into_iter()
+        .map(|permission| api_token_permissions::ActiveModel {
+            api_token_id: Set(api_token.id),
+            permission: Set(permission.as_str().to_string()),
+        })
+        .collect();
+
+    api_token_permissions::Entity::insert_many(new_permissions)
+        .exec(&txn)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "create_api_token failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "create_api_token failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    info!(
+        actor = %claims.sub,
+        teacher_id = %api_token.teacher_id,
+        token_id = %api_token.id,
+        "api token created"
+    );
+
+    Ok(AppResponse::success_with_data(CreatedApiTokenView {
+        token,
+        api_token: api_token_to_view(api_token, permissions.to_vec()),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api-tokens/{id}",
+    tag = "api-tokens",
+    security(("auth_token" = [])),
+    params(("id" = Uuid, Path, description = "API token id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn revoke_api_token(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let result = api_tokens::Entity::update_many()
+        .col_expr(
+            api_tokens::Column::RevokedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(api_tokens::Column::Id.eq(id))
+        .filter(api_tokens::Column::RevokedAt.is_null())
+        .exec(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "revoke_api_token failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    if result.rows_affected == 0 {
+        return Err(AppResponse::error(
+            StatusCode::NOT_FOUND,
+            "找不到對應的 API token",
+        ));
+    }
+
+    info!(actor = %claims.sub, token_id = %id, "api token revoked");
+
+    Ok(AppResponse::success("已撤銷 API token"))
+}
+
+/// 以 API token 驗證的請求會在 extensions 帶有此標記，用來拒絕只允許登入階段使用的操作。
+#[derive(Debug, Clone, Copy)]
+pub struct ApiTokenAuth;
+
+/// 驗證 `Authorization: Bearer` 帶入的 API token。
+///
+/// 實際可用的權限是 token 的權限範圍與擁有者目前角色權限的交集，
+/// 擁有者被降級或刪除後，token 也會跟著失去權限。
+pub(crate) async fn authenticate_api_token(
+    db: &DatabaseConnection,
+    token: &str,
+) -> Result<Option<(Claims, PermissionSet)>, (StatusCode, Json<AppResponse>)> {
+    let now = Utc::now().naive_utc();
+
+    let Some((api_token, permissions)) = api_tokens::Entity::find()
+        .filter(api_tokens::Column::TokenHash.eq(hash_token(token)))
+        .filter(api_tokens::Column::RevokedAt.is_null())
+        .filter(api_tokens::Column::ExpiresAt.gt(now))
+        .find_with_related(api_token_permissions::Entity)
+        .all(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "authenticate_api_token failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?
+        .pop()
+    else {
+        return Ok(None);
+    };
+
+    if find_teacher_by_id(db, api_token.teacher_id)
+        .await?
+        .is_none()
+    {
+        return Ok(None);
+    }
+
+    let teacher_permissions = load_teacher_permissions(db, api_token.teacher_id).await?;
+    let permissions: PermissionSet = parse_permissions(permissions)
+        .to_vec()
+        .into_iter()
+        .filter(|permission| teacher_permissions.contains(*permission))
+        .collect();
+
+    api_tokens::Entity::update_many()
+        .col_expr(api_tokens::Column::LastUsedAt, Expr::value(now))
+        .filter(api_tokens::Column::Id.eq(api_token.id))
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "authenticate_api_token failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let claims = Claims {
+        sub: api_token.teacher_id,
+        jti: api_token.id,
+        must_change_password: false,
+        must_enroll_two_factor: false,
+        exp: api_token.expires_at.and_utc().timestamp(),
+    };
+
+    Ok(Some((claims, permissions)))
+}
+
+fn parse_permissions(permissions: Vec<api_token_permissions::Model>) -> PermissionSet {
+    permissions
+        .into_iter()
+        .filter_map(
+            |permission| match Permission::try_from(permission.permission) {
+                Ok(permission) => Some(permission),
+                Err(e) => {
+                    warn!(error = %e, "unknown api token permission ignored");
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// 發出 API token 時不能取得自己沒有的權限。`owner_permissions` 為替其他教職員發出時，
+/// 該教職員目前的權限；token 驗證時會與擁有者的權限取交集，所以擁有者的權限也不能比自己多。
+fn check_api_token_grant(
+    creator_permissions: &PermissionSet,
+    requested: &PermissionSet,
+    owner_permissions: Option<&PermissionSet>,
+) -> Result<(), &'static str> {
+    if !creator_permissions.contains_all(requested) {
+        return Err("無法發出包含自己沒有的權限的 API token");
+    }
+    if owner_permissions.is_some_and(|owner| !creator_permissions.contains_all(owner)) {
+        return Err("無法替擁有自己沒有的權限的教職員發出 API token");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permissions(permissions: &[Permission]) -> PermissionSet {
+        permissions.iter().copied().collect()
+    }
+
+    #[test]
+    fn rejects_permissions_the_creator_lacks() {
+        let creator = permissions(&[Permission::ApiTokensManage, Permission::MembersRead]);
+
+        assert!(
+            check_api_token_grant(&creator, &permissions(&[Permission::MembersRead]), None).is_ok()
+        );
+        assert!(check_api_token_grant(
+            &creator,
+            &permissions(&[Permission::MembersRead, Permission::RolesManage]),
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_owners_with_more_permissions_than_the_creator() {
+        let creator = permissions(&[Permission::ApiTokensManage, Permission::MembersRead]);
+        let requested = permissions(&[Permission::MembersRead]);
+        let super_admin: PermissionSet = Permission::ALL.into_iter().collect();
+
+        assert!(check_api_token_grant(&creator, &requested, Some(&super_admin)).is_err());
+        assert!(check_api_token_grant(
+            &creator,
+            &requested,
+            Some(&permissions(&[Permission::MembersRead]))
+        )
+        .is_ok());
+        // 權限相同的教職員可以
+        assert!(check_api_token_grant(&creator, &requested, Some(&creator)).is_ok());
+        // 主管理員可以替任何人發出
+        assert!(check_api_token_grant(&super_admin, &requested, Some(&super_admin)).is_ok());
+    }
+}
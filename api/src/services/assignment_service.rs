@@ -0,0 +1,232 @@
+use crate::db::entities::teacher_assignments;
+use crate::models::{AppResponse, Permission, PermissionSet, StudentScope, StudentView};
+use crate::services::setting_service::is_student_access_restricted;
+use crate::services::student_service::{find_student_by_id, find_student_views};
+use crate::services::teacher_service::find_teacher_by_id;
+use crate::util::Claims;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::Utc;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use tracing::{error, info};
+use uuid::Uuid;
+
+#[utoipa::path(
+    get,
+    path = "/teachers/{id}/students",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    responses(
+        (status = 200, body = AppResponse<Vec<StudentView>>, description = "成功"),
+    ),
+)]
+pub async fn get_teacher_students(
+    Extension(permissions): Extension<PermissionSet>,
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+) -> Result<Json<AppResponse<Vec<StudentView>>>, (StatusCode, Json<AppResponse>)> {
+    let scope = find_assigned_students(&db, teacher_id).await?;
+    let result = find_student_views(
+        &db,
+        &scope,
+        permissions.contains(Permission::StudentsReadPii),
+    )
+    .await?;
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/students",
+    tag = "me",
+    responses(
+        (status = 200, body = AppResponse<Vec<StudentView>>, description = "成功"),
+    ),
+)]
+pub async fn get_my_students(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<AppResponse<Vec<StudentView>>>, (StatusCode, Json<AppResponse>)> {
+    let scope = find_assigned_students(&db, claims.sub).await?;
+    let result = find_student_views(
+        &db,
+        &scope,
+        permissions.contains(Permission::StudentsReadPii),
+    )
+    .await?;
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+#[utoipa::path(
+    put,
+    path = "/teachers/{id}/students/{student_id}",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id"), ("student_id" = Uuid, Path, description = "學生的成員 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn assign_student(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Path((teacher_id, student_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if find_teacher_by_id(&db, teacher_id).await?.is_none() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "找不到對應的教職員",
+        ));
+    }
+
+    if find_student_by_id(&db, student_id).await?.is_none() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "無法找到學生資料",
+        ));
+    }
+
+    insert_assignment(&db, teacher_id, student_id).await?;
+
+    info!(
+        actor = %claims.sub,
+        student_id = %student_id,
+        teacher_id = %teacher_id,
+        "student assigned"
+    );
+
+    Ok(AppResponse::success("指派成功"))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/teachers/{id}/students/{student_id}",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id"), ("student_id" = Uuid, Path, description = "學生的成員 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn unassign_student(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Path((teacher_id, student_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let result = teacher_assignments::Entity::delete_by_id((teacher_id, student_id))
+        .exec(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "unassign_student failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    if result.rows_affected == 0 {
+        return Err(AppResponse::error(
+            StatusCode::NOT_FOUND,
+            "此學生沒有指派給該教職員",
+        ));
+    }
+
+    info!(
+        actor = %claims.sub,
+        student_id = %student_id,
+        teacher_id = %teacher_id,
+        "student unassigned"
+    );
+
+    Ok(AppResponse::success("已取消指派"))
+}
+
+/// 依系統設定與權限決定教職員可以存取的學生範圍。
+pub(crate) async fn find_student_scope<C>(
+    db: &C,
+    teacher_id: Uuid,
+    permissions: &PermissionSet,
+) -> Result<StudentScope, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    if permissions.contains(Permission::StudentsAll) || !is_student_access_restricted(db).await? {
+        return Ok(StudentScope::All);
+    }
+
+    find_assigned_students(db, teacher_id).await
+}
+
+pub(crate) fn ensure_student_in_scope(
+    scope: &StudentScope,
+    student_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)> {
+    if scope.contains(student_id) {
+        return Ok(());
+    }
+
+    Err(AppResponse::error(
+        StatusCode::FORBIDDEN,
+        "只能存取指派給自己的學生",
+    ))
+}
+
+/// 指派學生給教職員，已指派過則不做任何事。
+pub(crate) async fn insert_assignment<C>(
+    db: &C,
+    teacher_id: Uuid,
+    student_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let assignment = teacher_assignments::ActiveModel {
+        teacher_id: Set(teacher_id),
+        student_id: Set(student_id),
+        assigned_at: Set(Utc::now().naive_utc()),
+    };
+
+    teacher_assignments::Entity::insert(assignment)
+        .on_conflict(
+            OnConflict::columns([
+                teacher_assignments::Column::TeacherId,
+                teacher_assignments::Column::StudentId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "insert_assignment failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(())
+}
+
+async fn find_assigned_students<C>(
+    db: &C,
+    teacher_id: Uuid,
+) -> Result<StudentScope, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let assignments = teacher_assignments::Entity::find()
+        .filter(teacher_assignments::Column::TeacherId.eq(teacher_id))
+        .all(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "find_assigned_students failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(StudentScope::Assigned(
+        assignments
+            .into_iter()
+            .map(|assignment| assignment.student_id)
+            .collect(),
+    ))
+}
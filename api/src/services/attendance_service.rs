@@ -1,12 +1,15 @@
 use crate::db::entities::{attendance_records, attendance_students};
 use crate::models::{
-    AppResponse, AttendanceQuery, AttendanceStudent, AttendanceView, UpsertAttendanceRequest,
+    AppResponse, AttendanceQuery, AttendanceStudent, AttendanceView, PermissionSet, StudentScope,
+    UpsertAttendanceRequest,
 };
+use crate::services::assignment_service::{ensure_student_in_scope, find_student_scope};
+use crate::util::Claims;
 use axum::extract::Query;
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    Json,
+    Extension, Json,
 };
 use chrono::{NaiveDate, TimeZone, Utc};
 use sea_orm::{
@@ -14,7 +17,18 @@ use sea_orm::{
     QueryFilter, Set, TransactionTrait,
 };
 
+#[utoipa::path(
+    get,
+    path = "/attendance-records",
+    tag = "attendance",
+    params(AttendanceQuery),
+    responses(
+        (status = 200, body = AppResponse<AttendanceView>, description = "成功"),
+    ),
+)]
 pub async fn get_attendance_record(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Query(query): Query<AttendanceQuery>,
 ) -> Result<Json<AppResponse<AttendanceView>>, (StatusCode, Json<AppResponse>)> {
@@ -28,6 +42,8 @@ pub async fn get_attendance_record(
         .await
         .map_err(|_| AppResponse::error(StatusCode::NOT_FOUND, "資料庫異常"))?;
 
+    let scope = find_student_scope(&db, claims.sub, &permissions).await?;
+
     let response = AttendanceView {
         id: record.id,
         note: record.note,
@@ -35,6 +51,7 @@ pub async fn get_attendance_record(
         updated_at: Utc.from_utc_datetime(&record.updated_at).into(),
         attendance_students: students
             .into_iter()
+            .filter(|student| scope.contains(student.student_id))
             .map(|student| AttendanceStudent {
                 student_id: student.student_id,
                 attendance_status: student.attendance_status,
@@ -45,11 +62,28 @@ pub async fn get_attendance_record(
     Ok(AppResponse::success_with_data(response))
 }
 
+#[utoipa::path(
+    post,
+    path = "/attendance-records/{id}",
+    tag = "attendance",
+    params(("id" = String, Path, description = "日期，格式為 YYYY-MM-DD")),
+    request_body = UpsertAttendanceRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn add_attendance_record(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Path(date): Path<String>,
     Json(payload): Json<UpsertAttendanceRequest>,
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let scope = find_student_scope(&db, claims.sub, &permissions).await?;
+    for student in &payload.attendance_students {
+        ensure_student_in_scope(&scope, student.student_id)?;
+    }
+
     let txn = db
         .begin()
         .await
@@ -96,11 +130,28 @@ pub async fn add_attendance_record(
     Ok(AppResponse::success("建立成功"))
 }
 
+#[utoipa::path(
+    put,
+    path = "/attendance-records/{id}",
+    tag = "attendance",
+    params(("id" = String, Path, description = "日期，格式為 YYYY-MM-DD")),
+    request_body = UpsertAttendanceRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn update_attendance(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Path(date): Path<String>,
     Json(payload): Json<UpsertAttendanceRequest>,
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let scope = find_student_scope(&db, claims.sub, &permissions).await?;
+    for student in &payload.attendance_students {
+        ensure_student_in_scope(&scope, student.student_id)?;
+    }
+
     // 解析日期取得 ID
     let (_, attendance_id) = parse_date(&date)?;
 
@@ -116,7 +167,10 @@ pub async fn update_attendance(
         .ok_or_else(|| AppResponse::error(StatusCode::NOT_FOUND, "沒有該日期的簽到表"))?;
 
     let mut record: attendance_records::ActiveModel = record.into();
-    record.note = Set(payload.note);
+    // 簽到表的備註由所有教職員共用，受指派限制時只能修改自己學生的備註
+    if !scope.is_restricted() {
+        record.note = Set(payload.note);
+    }
     record.updated_at = Set(Utc::now().naive_utc());
 
     record
@@ -125,8 +179,15 @@ pub async fn update_attendance(
         .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新主表失敗"))?;
 
     // 刪除原有的學生出席記錄，使用解析後的 attendance_id
-    attendance_students::Entity::delete_many()
-        .filter(attendance_students::Column::AttendanceRecordId.eq(attendance_id.clone()))
+    // 受指派限制時只替換自己學生的紀錄，保留其他教職員登記的部分
+    let mut delete_query = attendance_students::Entity::delete_many()
+        .filter(attendance_students::Column::AttendanceRecordId.eq(attendance_id.clone()));
+    if let StudentScope::Assigned(student_ids) = scope {
+        delete_query =
+            delete_query.filter(attendance_students::Column::StudentId.is_in(student_ids));
+    }
+
+    delete_query
         .exec(&txn)
         .await
         .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "刪除舊記錄失敗"))?;
@@ -140,7 +201,6 @@ pub async fn update_attendance(
             student_id: Set(student.student_id),
             attendance_status: Set(student.attendance_status),
             note: Set(student.note),
-            ..Default::default()
         })
         .collect();
 
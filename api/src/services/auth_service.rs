@@ -1,59 +1,229 @@
+use crate::config::CONFIG;
 use crate::db::entities::{members, teachers};
-use crate::models::{AppResponse, LoginRequest, MeResponse, RoleType};
-use crate::util::{create_token, Claims};
+use crate::models::{
+    AppResponse, LoginEventType, LoginRequest, LoginResponse, MeResponse, PermissionSet,
+    TwoFactorLoginRequest,
+};
+use crate::services::login_event_service::record_login_event;
+use crate::services::role_service::find_role_by_id;
+use crate::services::session_service::{
+    create_session, find_session_by_refresh_token, issue_refresh_token, revoke_session,
+    rotate_refresh_token,
+};
+use crate::services::setting_service::is_two_factor_required;
+use crate::services::teacher_service::find_teacher_by_id;
+use crate::services::two_factor_service::verify_two_factor_code;
+use crate::util::{
+    clear_ip_failures, create_token, create_two_factor_challenge, decode_token_ignoring_expiry,
+    decode_two_factor_challenge, generate_opaque_token, hash_password, ip_retry_after,
+    password_needs_rehash, record_ip_failure, verify_password, Claims, ClientInfo, CSRF_COOKIE,
+};
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::{Extension, Json};
-use bcrypt::verify;
-use chrono::{Duration, Utc};
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use tower_cookies::cookie::time::OffsetDateTime;
 use tower_cookies::{Cookie, Cookies};
-use tracing::error;
+use tracing::{error, warn};
+use uuid::Uuid;
 
+const TWO_FACTOR_CHALLENGE_COOKIE: &str = "two_factor_challenge";
+
+#[utoipa::path(
+    post,
+    path = "/api/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, body = AppResponse<LoginResponse>, description = "登入成功時設定 auth_token、refresh_token 與 CSRF cookie；需要兩步驟驗證時只回傳 two_factor_required"),
+        (status = 400, body = AppResponse, description = "帳號或密碼錯誤"),
+        (status = 429, body = AppResponse, description = "失敗次數過多或帳號已鎖定，暫時無法登入"),
+    ),
+)]
 pub async fn login_handler(
     cookies: Cookies,
+    client: ClientInfo,
     State(db): State<DatabaseConnection>,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+) -> Result<Json<AppResponse<LoginResponse>>, (StatusCode, Json<AppResponse>)> {
+    check_ip_throttle(&db, &client, None, &payload.username).await?;
+
     let teacher = teachers::Entity::find()
-        .filter(teachers::Column::Username.eq(payload.username))
+        .filter(teachers::Column::Username.eq(&payload.username))
         .filter(teachers::Column::DeletedAt.is_null())
         .one(&db)
         .await
         .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常"))?;
 
+    let teacher_id = teacher.as_ref().map(|teacher| teacher.member_id);
+    let mut event_type = LoginEventType::Failure;
+
     if let Some(teacher) = teacher {
-        let is_valid = verify(&payload.password, &teacher.password)
-            .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常"))?;
+        check_account_lock(&db, &client, &teacher).await?;
+
+        let is_valid = verify_password(&payload.password, &teacher.password).map_err(|e| {
+            error!(error = %e, "login_handler failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?;
 
         if is_valid {
-            let token = create_token(
-                teacher.member_id,
-                RoleType::try_from(teacher.role_type).unwrap(),
-            )
-            .map_err(|status_code| AppResponse::error(status_code, "伺服器發生異常"))?;
+            if password_needs_rehash(&teacher.password) {
+                rehash_teacher_password(&db, teacher.member_id, &payload.password).await;
+            }
 
-            let mut cookie = Cookie::new("auth_token", token);
-            cookie.set_http_only(true);
-            cookie.set_secure(true);
-            cookie.set_same_site(tower_cookies::cookie::SameSite::Strict);
-            cookie.set_path("/");
+            if teacher.totp_enabled_at.is_none() {
+                return complete_login(&db, &cookies, client, teacher).await;
+            }
 
-            cookies.add(cookie);
+            // 密碼正確但尚未通過兩步驟驗證，先發出短期的驗證憑證
+            let challenge = create_two_factor_challenge(teacher.member_id)
+                .map_err(|status_code| AppResponse::error(status_code, "伺服器發生異常"))?;
+            cookies.add(two_factor_challenge_cookie(challenge));
 
-            return Ok(AppResponse::success("登入成功"));
+            return Ok(AppResponse::success_with_data(LoginResponse {
+                two_factor_required: true,
+            }));
         }
+
+        event_type = record_failed_login(&db, &teacher).await?;
     }
 
+    if let Some(ip) = client.ip_address.as_deref() {
+        record_ip_failure(ip, &payload.username);
+    }
+
+    record_login_event(&db, teacher_id, Some(payload.username), event_type, &client).await;
+
     Err(AppResponse::error(
         StatusCode::BAD_REQUEST,
         "使用者名稱或密碼錯誤",
     ))
 }
 
+/// 以目前的雜湊設定重新保存密碼，失敗時只記錄錯誤，不影響這次登入。
+async fn rehash_teacher_password(db: &DatabaseConnection, teacher_id: Uuid, password: &str) {
+    let password_hash = match hash_password(password) {
+        Ok(password_hash) => password_hash,
+        Err(e) => {
+            error!(error = %e, "rehash_teacher_password failed");
+            return;
+        }
+    };
+
+    if let Err(e) = teachers::Entity::update_many()
+        .col_expr(teachers::Column::Password, Expr::value(password_hash))
+        .filter(teachers::Column::MemberId.eq(teacher_id))
+        .exec(db)
+        .await
+    {
+        error!(error = %e, "rehash_teacher_password failed");
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/login/two-factor",
+    tag = "auth",
+    request_body = TwoFactorLoginRequest,
+    responses(
+        (status = 200, body = AppResponse<LoginResponse>, description = "成功"),
+        (status = 400, body = AppResponse, description = "驗證碼錯誤"),
+        (status = 401, body = AppResponse, description = "驗證已逾時，需重新登入"),
+    ),
+)]
+pub async fn two_factor_login_handler(
+    cookies: Cookies,
+    client: ClientInfo,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<TwoFactorLoginRequest>,
+) -> Result<Json<AppResponse<LoginResponse>>, (StatusCode, Json<AppResponse>)> {
+    let challenge = cookies
+        .get(TWO_FACTOR_CHALLENGE_COOKIE)
+        .and_then(|cookie| decode_two_factor_challenge(cookie.value()).ok())
+        .ok_or_else(|| AppResponse::error(StatusCode::UNAUTHORIZED, "驗證逾時，請重新登入"))?;
+
+    let teacher = find_teacher_by_id(&db, challenge.claims.sub)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::UNAUTHORIZED, "驗證逾時，請重新登入"))?;
+
+    check_ip_throttle(&db, &client, Some(teacher.member_id), &teacher.username).await?;
+    check_account_lock(&db, &client, &teacher).await?;
+
+    if verify_two_factor_code(&db, &teacher, &payload.code).await? {
+        cookies.add(removal_cookie(TWO_FACTOR_CHALLENGE_COOKIE));
+        return complete_login(&db, &cookies, client, teacher).await;
+    }
+
+    let event_type = record_failed_login(&db, &teacher).await?;
+
+    if let Some(ip) = client.ip_address.as_deref() {
+        record_ip_failure(ip, &teacher.username);
+    }
+
+    record_login_event(
+        &db,
+        Some(teacher.member_id),
+        Some(teacher.username),
+        event_type,
+        &client,
+    )
+    .await;
+
+    Err(AppResponse::error(StatusCode::BAD_REQUEST, "驗證碼錯誤"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/refresh",
+    tag = "auth",
+    security(("csrf_token" = [])),
+    responses(
+        (status = 200, body = AppResponse, description = "以 refresh_token cookie 換發新的 access token"),
+        (status = 401, body = AppResponse, description = "refresh token 無效或已過期"),
+    ),
+)]
+pub async fn refresh_handler(
+    cookies: Cookies,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let refresh_token = cookies
+        .get("refresh_token")
+        .map(|cookie| cookie.value().to_string())
+        .ok_or_else(|| AppResponse::error(StatusCode::UNAUTHORIZED, "請重新登入"))?;
+
+    let (session, new_refresh_token) = rotate_refresh_token(&db, &refresh_token).await?;
+
+    let teacher = teachers::Entity::find()
+        .filter(teachers::Column::MemberId.eq(session.teacher_id))
+        .filter(teachers::Column::DeletedAt.is_null())
+        .one(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "refresh_handler failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?
+        .ok_or_else(|| AppResponse::error(StatusCode::UNAUTHORIZED, "請重新登入"))?;
+
+    issue_access_token(&db, &cookies, &teacher, session.id).await?;
+    set_refresh_token_cookie(&cookies, new_refresh_token);
+
+    Ok(AppResponse::success("已更新登入憑證"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me",
+    tag = "me",
+    security(("auth_token" = [])),
+    responses(
+        (status = 200, body = AppResponse<MeResponse>, description = "成功"),
+    ),
+)]
 pub async fn me_handler(
     Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
 ) -> Result<Json<AppResponse<MeResponse>>, (StatusCode, Json<AppResponse>)> {
     let teacher_with_member = teachers::Entity::find()
@@ -63,7 +233,7 @@ pub async fn me_handler(
         .one(&db)
         .await
         .map_err(|e| {
-            error!("Something is wrong:{}", e);
+            error!(error = %e, "me_handler failed");
             AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
         })?;
 
@@ -73,41 +243,321 @@ pub async fn me_handler(
                 .map(|m| m.name)
                 .unwrap_or_else(|| "未提供姓名".to_string());
 
-            let resp = MeResponse::new(
-                teacher.member_id,
-                teacher.username,
-                member_name,
-                RoleType::try_from(teacher.role_type).map_err(|_| {
+            let role = find_role_by_id(&db, teacher.role_id)
+                .await?
+                .ok_or_else(|| {
                     AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "無效的角色類型")
-                })?,
-                claims.exp,
-            );
+                })?;
+
+            let resp = MeResponse {
+                id: teacher.member_id,
+                username: teacher.username,
+                name: member_name,
+                role: role.name,
+                permissions: permissions.to_vec(),
+                must_change_password: teacher.must_change_password,
+                two_factor_enabled: teacher.totp_enabled_at.is_some(),
+                must_enroll_two_factor: claims.must_enroll_two_factor,
+                exp: claims.exp,
+            };
 
             Ok(AppResponse::success_with_data(resp))
         }
-        None => {
-            Err(AppResponse::error(
-                StatusCode::UNAUTHORIZED,
-                "請檢查登入是否成功",
-            ))
-        }
+        None => Err(AppResponse::error(
+            StatusCode::UNAUTHORIZED,
+            "請檢查登入是否成功",
+        )),
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/logout",
+    tag = "auth",
+    security(("csrf_token" = [])),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn logout_handler(
     cookies: Cookies,
+    client: ClientInfo,
+    State(db): State<DatabaseConnection>,
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
-    if let Some(_cookie) = cookies.get("auth_token") {
-        let mut removal_cookie = Cookie::new("auth_token", "");
-        let expiration_time = (Utc::now() - Duration::days(1)).timestamp();
-        removal_cookie.set_expires(OffsetDateTime::from_unix_timestamp(expiration_time).unwrap());
-        removal_cookie.set_http_only(true);
-        removal_cookie.set_secure(true);
-        removal_cookie.set_same_site(tower_cookies::cookie::SameSite::Strict);
-        removal_cookie.set_path("/");
-
-        cookies.add(removal_cookie);
+    // access token 過期後 refresh token 仍可換發，登入階段一樣要撤銷
+    let mut session = cookies
+        .get("auth_token")
+        .and_then(|cookie| decode_token_ignoring_expiry(cookie.value()).ok())
+        .map(|token_data| (token_data.claims.jti, token_data.claims.sub));
+    if session.is_none() {
+        if let Some(cookie) = cookies.get("refresh_token") {
+            session = find_session_by_refresh_token(&db, cookie.value())
+                .await?
+                .map(|session| (session.id, session.teacher_id));
+        }
+    }
+
+    if let Some((session_id, teacher_id)) = session {
+        revoke_session(&db, session_id).await?;
+        record_login_event(&db, Some(teacher_id), None, LoginEventType::Logout, &client).await;
+    }
+
+    if cookies.get("auth_token").is_some() {
+        cookies.add(removal_cookie("auth_token"));
+    }
+
+    if cookies.get("refresh_token").is_some() {
+        cookies.add(removal_cookie("refresh_token"));
+    }
+
+    if cookies.get(TWO_FACTOR_CHALLENGE_COOKIE).is_some() {
+        cookies.add(removal_cookie(TWO_FACTOR_CHALLENGE_COOKIE));
+    }
+
+    if cookies.get(CSRF_COOKIE).is_some() {
+        cookies.add(removal_cookie(CSRF_COOKIE));
     }
 
     Ok(AppResponse::success("登出成功"))
 }
+
+/// 通過所有驗證步驟後建立登入階段並發出憑證。
+pub(crate) async fn complete_login(
+    db: &DatabaseConnection,
+    cookies: &Cookies,
+    client: ClientInfo,
+    teacher: teachers::Model,
+) -> Result<Json<AppResponse<LoginResponse>>, (StatusCode, Json<AppResponse>)> {
+    if let Some(ip) = client.ip_address.as_deref() {
+        clear_ip_failures(ip, &teacher.username);
+    }
+    record_successful_login(db, teacher.member_id).await?;
+
+    let session = create_session(db, teacher.member_id, client.clone()).await?;
+    let refresh_token = issue_refresh_token(db, session.id).await?;
+
+    issue_access_token(db, cookies, &teacher, session.id).await?;
+    set_refresh_token_cookie(cookies, refresh_token);
+
+    // 憑證都發出後才記錄成功，避免建立登入階段失敗時留下成功的紀錄
+    record_login_event(
+        db,
+        Some(teacher.member_id),
+        Some(teacher.username),
+        LoginEventType::Success,
+        &client,
+    )
+    .await;
+
+    Ok(AppResponse::success_with_data(LoginResponse {
+        two_factor_required: false,
+    }))
+}
+
+/// 依照教職員目前的狀態發出 access token，並寫入 cookie。
+pub(crate) async fn issue_access_token<C>(
+    db: &C,
+    cookies: &Cookies,
+    teacher: &teachers::Model,
+    session_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let must_enroll_two_factor =
+        teacher.totp_enabled_at.is_none() && is_two_factor_required(db).await?;
+
+    let token = create_token(
+        teacher.member_id,
+        session_id,
+        teacher.must_change_password,
+        must_enroll_two_factor,
+    )
+    .map_err(|status_code| AppResponse::error(status_code, "伺服器發生異常"))?;
+
+    let mut cookie = Cookie::new("auth_token", token);
+    cookie.set_http_only(true);
+    cookie.set_secure(true);
+    cookie.set_same_site(tower_cookies::cookie::SameSite::Strict);
+    cookie.set_path("/");
+
+    cookies.add(cookie);
+    // 每次發出 access token 都一併更換 CSRF token
+    cookies.add(csrf_cookie(generate_opaque_token()));
+
+    Ok(())
+}
+
+async fn check_ip_throttle(
+    db: &DatabaseConnection,
+    client: &ClientInfo,
+    teacher_id: Option<Uuid>,
+    username: &str,
+) -> Result<(), (StatusCode, Json<AppResponse>)> {
+    let Some(retry_after) = client.ip_address.as_deref().and_then(ip_retry_after) else {
+        return Ok(());
+    };
+
+    record_login_event(
+        db,
+        teacher_id,
+        Some(username.to_string()),
+        LoginEventType::Failure,
+        client,
+    )
+    .await;
+
+    Err(AppResponse::error(
+        StatusCode::TOO_MANY_REQUESTS,
+        format!(
+            "登入失敗次數過多，請於 {} 分鐘後再試",
+            retry_after.as_secs().div_ceil(60)
+        ),
+    ))
+}
+
+pub(crate) async fn check_account_lock(
+    db: &DatabaseConnection,
+    client: &ClientInfo,
+    teacher: &teachers::Model,
+) -> Result<(), (StatusCode, Json<AppResponse>)> {
+    let now = Utc::now().naive_utc();
+    let Some(locked_until) = teacher.locked_until.filter(|until| *until > now) else {
+        return Ok(());
+    };
+
+    record_login_event(
+        db,
+        Some(teacher.member_id),
+        Some(teacher.username.clone()),
+        LoginEventType::Lockout,
+        client,
+    )
+    .await;
+
+    Err(AppResponse::error(
+        StatusCode::TOO_MANY_REQUESTS,
+        format!(
+            "帳號已暫時鎖定，請於 {} 分鐘後再試",
+            ((locked_until - now).num_seconds() as u64).div_ceil(60)
+        ),
+    ))
+}
+
+async fn record_successful_login(
+    db: &DatabaseConnection,
+    teacher_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)> {
+    teachers::Entity::update_many()
+        .col_expr(teachers::Column::FailedLoginAttempts, Expr::value(0))
+        .col_expr(
+            teachers::Column::LockedUntil,
+            Expr::value(None::<NaiveDateTime>),
+        )
+        .col_expr(
+            teachers::Column::LastLoginAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(teachers::Column::MemberId.eq(teacher_id))
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "record_successful_login failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?;
+
+    Ok(())
+}
+
+/// 累計帳號的登入失敗次數，達到門檻後依失敗次數以指數增加鎖定時間。
+///
+/// 密碼與兩步驟驗證碼輸入錯誤都會累計。回傳這次嘗試應記錄的事件類型，
+/// 達到門檻而鎖定時為 `Lockout`，其餘為 `Failure`。
+async fn record_failed_login(
+    db: &DatabaseConnection,
+    teacher: &teachers::Model,
+) -> Result<LoginEventType, (StatusCode, Json<AppResponse>)> {
+    let updated = teachers::Entity::update_many()
+        .col_expr(
+            teachers::Column::FailedLoginAttempts,
+            Expr::col(teachers::Column::FailedLoginAttempts).add(1),
+        )
+        .filter(teachers::Column::MemberId.eq(teacher.member_id))
+        .exec_with_returning(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "record_failed_login failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?;
+
+    let Some(updated) = updated.into_iter().next() else {
+        return Ok(LoginEventType::Failure);
+    };
+
+    let failed_attempts = updated.failed_login_attempts.max(0) as u32;
+    if let Some(duration) = CONFIG.auth.login_throttle.lockout_duration(failed_attempts) {
+        let locked_until = Utc::now().naive_utc() + duration;
+
+        teachers::Entity::update_many()
+            .col_expr(teachers::Column::LockedUntil, Expr::value(locked_until))
+            .filter(teachers::Column::MemberId.eq(teacher.member_id))
+            .exec(db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "record_failed_login failed");
+                AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+            })?;
+
+        warn!(
+            username = %updated.username,
+            failed_attempts,
+            locked_until = %locked_until,
+            "account locked after failed logins"
+        );
+
+        return Ok(LoginEventType::Lockout);
+    }
+
+    Ok(LoginEventType::Failure)
+}
+
+fn set_refresh_token_cookie(cookies: &Cookies, refresh_token: String) {
+    let expiration_time = (Utc::now() + CONFIG.auth.refresh_token_lifetime()).timestamp();
+    let mut refresh_cookie = Cookie::new("refresh_token", refresh_token);
+    refresh_cookie.set_expires(OffsetDateTime::from_unix_timestamp(expiration_time).unwrap());
+    refresh_cookie.set_http_only(true);
+    refresh_cookie.set_secure(true);
+    refresh_cookie.set_same_site(tower_cookies::cookie::SameSite::Strict);
+    refresh_cookie.set_path("/");
+
+    cookies.add(refresh_cookie);
+}
+
+pub(crate) fn two_factor_challenge_cookie(challenge: String) -> Cookie<'static> {
+    let mut cookie = Cookie::new(TWO_FACTOR_CHALLENGE_COOKIE, challenge);
+    cookie.set_http_only(true);
+    cookie.set_secure(true);
+    cookie.set_same_site(tower_cookies::cookie::SameSite::Strict);
+    cookie.set_path("/");
+    cookie
+}
+
+/// CSRF token 必須讓前端的 JavaScript 讀得到，才能放進請求標頭，因此不設定 HttpOnly。
+pub(crate) fn csrf_cookie(csrf_token: String) -> Cookie<'static> {
+    let mut cookie = Cookie::new(CSRF_COOKIE, csrf_token);
+    cookie.set_secure(true);
+    cookie.set_same_site(tower_cookies::cookie::SameSite::Strict);
+    cookie.set_path("/");
+    cookie
+}
+
+pub(crate) fn removal_cookie(name: &'static str) -> Cookie<'static> {
+    let mut removal_cookie = Cookie::new(name, "");
+    let expiration_time = (Utc::now() - Duration::days(1)).timestamp();
+    removal_cookie.set_expires(OffsetDateTime::from_unix_timestamp(expiration_time).unwrap());
+    removal_cookie.set_http_only(true);
+    removal_cookie.set_secure(true);
+    removal_cookie.set_same_site(tower_cookies::cookie::SameSite::Strict);
+    removal_cookie.set_path("/");
+    removal_cookie
+}
@@ -0,0 +1,975 @@
+use crate::config::CONFIG;
+use crate::db::entities::{
+    announcements, attendance_records, attendance_students, guardian_sessions, guardians,
+    member_family_relations, members, student_exams, student_infos, students,
+};
+use crate::models::{
+    guardian_to_view, student_info_to_view, AddGuardianRequest, AnnouncementAudience,
+    AnnouncementView, AppResponse, ChangePasswordRequest, GuardianAttendanceQuery,
+    GuardianAttendanceView, GuardianChildView, GuardianMeResponse, GuardianView, LoginEventType,
+    LoginRequest, ResetGuardianPasswordRequest, StudentInfoView,
+};
+use crate::services::auth_service::{csrf_cookie, removal_cookie};
+use crate::services::login_event_service::record_guardian_login_event;
+use crate::services::member_service::{find_member_by_id, get_members_name_hashmap};
+use crate::services::password_service::ensure_password_strength;
+use crate::util::{
+    clear_ip_failures, create_guardian_token, decode_guardian_token, generate_opaque_token,
+    hash_password, ip_retry_after, password_needs_rehash, record_ip_failure, verify_password,
+    Claims, ClientInfo, GuardianClaims,
+};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, JoinType,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, TransactionTrait,
+};
+use tower_cookies::cookie::time::OffsetDateTime;
+use tower_cookies::{Cookie, Cookies};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+pub const GUARDIAN_TOKEN_COOKIE: &str = "guardian_token";
+
+#[utoipa::path(
+    get,
+    path = "/guardians",
+    tag = "guardians",
+    responses(
+        (status = 200, body = AppResponse<Vec<GuardianView>>, description = "成功"),
+    ),
+)]
+pub async fn get_guardians(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<AppResponse<Vec<GuardianView>>>, (StatusCode, Json<AppResponse>)> {
+    let guardians = guardians::Entity::find()
+        .filter(guardians::Column::DeletedAt.is_null())
+        .order_by_asc(guardians::Column::Username)
+        .all(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_guardians failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let member_ids: Vec<Uuid> = guardians
+        .iter()
+        .map(|guardian| guardian.member_id)
+        .collect();
+
+    let member_name_map = get_members_name_hashmap(&db, member_ids).await?;
+
+    let result = guardians
+        .into_iter()
+        .map(|guardian| {
+            let name = member_name_map
+                .get(&guardian.member_id)
+                .cloned()
+                .unwrap_or_else(|| "未知姓名".to_string());
+            guardian_to_view(guardian, name)
+        })
+        .collect();
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/guardians",
+    tag = "guardians",
+    request_body = AddGuardianRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn add_guardian(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<AddGuardianRequest>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if let Err(err) = payload.validate() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("參數不正確。錯誤參數：{}", err),
+        ));
+    }
+
+    ensure_password_strength(&payload.password, &[&payload.username])?;
+
+    if find_member_by_id(&db, payload.member_id).await?.is_none() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "找不到對應的成員",
+        ));
+    }
+
+    // 刪除過的家長帳號仍佔用主鍵與帳號名稱，因此不篩選 deleted_at
+    let existing = guardians::Entity::find()
+        .filter(
+            guardians::Column::MemberId
+                .eq(payload.member_id)
+                .or(guardians::Column::Username.eq(&payload.username)),
+        )
+        .one(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "add_guardian failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    if let Some(existing) = existing {
+        let message = if existing.member_id == payload.member_id {
+            "此成員已有家長帳號"
+        } else {
+            "此帳號名稱已被使用"
+        };
+        return Err(AppResponse::error(StatusCode::BAD_REQUEST, message));
+    }
+
+    let password_hash = hash_password(&payload.password).map_err(|e| {
+        error!(error = %e, "add_guardian failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let now = Utc::now().naive_utc();
+    let new_guardian = guardians::ActiveModel {
+        member_id: Set(payload.member_id),
+        username: Set(payload.username),
+        password: Set(password_hash),
+        // 由管理者設定的初始密碼，家長第一次登入後必須自行變更
+        must_change_password: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+
+    new_guardian.insert(&db).await.map_err(|e| {
+        error!(error = %e, "add_guardian failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    info!(actor = %claims.sub, guardian_id = %payload.member_id, "guardian account created");
+
+    Ok(AppResponse::success("建立成功"))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/guardians/{id}",
+    tag = "guardians",
+    params(("id" = Uuid, Path, description = "家長的成員 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn delete_guardian(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Path(guardian_id): Path<Uuid>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let guardian = find_guardian_by_id(&db, guardian_id)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::NOT_FOUND, "找不到對應的家長帳號"))?;
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "delete_guardian failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let mut guardian: guardians::ActiveModel = guardian.into();
+    guardian.updated_at = Set(Utc::now().naive_utc());
+    guardian.deleted_at = Set(Some(Utc::now().naive_utc()));
+
+    guardian.update(&txn).await.map_err(|e| {
+        error!(error = %e, "delete_guardian failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "刪除失敗")
+    })?;
+
+    revoke_guardian_sessions(&txn, guardian_id).await?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "delete_guardian failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    info!(actor = %claims.sub, guardian_id = %guardian_id, "guardian account deleted");
+
+    Ok(AppResponse::success("刪除成功"))
+}
+
+/// 家長忘記密碼時由管理者重新設定，家長下次登入後必須再自行變更。
+#[utoipa::path(
+    put,
+    path = "/guardians/{id}/password",
+    tag = "guardians",
+    params(("id" = Uuid, Path, description = "家長的成員 id")),
+    request_body = ResetGuardianPasswordRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn reset_guardian_password(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Path(guardian_id): Path<Uuid>,
+    Json(payload): Json<ResetGuardianPasswordRequest>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if let Err(err) = payload.validate() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("參數不正確。錯誤參數：{}", err),
+        ));
+    }
+
+    let guardian = find_guardian_by_id(&db, guardian_id)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::NOT_FOUND, "找不到對應的家長帳號"))?;
+
+    ensure_password_strength(&payload.password, &[&guardian.username])?;
+
+    let password_hash = hash_password(&payload.password).map_err(|e| {
+        error!(error = %e, "reset_guardian_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "reset_guardian_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let mut guardian: guardians::ActiveModel = guardian.into();
+    guardian.password = Set(password_hash);
+    guardian.must_change_password = Set(true);
+    // 管理者重設密碼後同時解除登入鎖定
+    guardian.failed_login_attempts = Set(0);
+    guardian.locked_until = Set(None);
+    guardian.updated_at = Set(Utc::now().naive_utc());
+
+    guardian.update(&txn).await.map_err(|e| {
+        error!(error = %e, "reset_guardian_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
+    })?;
+
+    revoke_guardian_sessions(&txn, guardian_id).await?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "reset_guardian_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    info!(actor = %claims.sub, guardian_id = %guardian_id, "guardian password reset");
+
+    Ok(AppResponse::success("密碼已重設"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/guardian/login",
+    tag = "guardian-portal",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+        (status = 400, body = AppResponse, description = "帳號或密碼錯誤"),
+        (status = 429, body = AppResponse, description = "失敗次數過多或帳號已鎖定，暫時無法登入"),
+    ),
+)]
+pub async fn guardian_login_handler(
+    cookies: Cookies,
+    client: ClientInfo,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<LoginRequest>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if let Some(retry_after) = client.ip_address.as_deref().and_then(ip_retry_after) {
+        record_guardian_login_event(
+            &db,
+            None,
+            Some(payload.username),
+            LoginEventType::Failure,
+            &client,
+        )
+        .await;
+
+        return Err(AppResponse::error(
+            StatusCode::TOO_MANY_REQUESTS,
+            format!(
+                "登入失敗次數過多，請於 {} 分鐘後再試",
+                retry_after.as_secs().div_ceil(60)
+            ),
+        ));
+    }
+
+    let guardian = guardians::Entity::find()
+        .filter(guardians::Column::Username.eq(&payload.username))
+        .filter(guardians::Column::DeletedAt.is_null())
+        .one(&db)
+        .await
+        .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常"))?;
+
+    let guardian_id = guardian.as_ref().map(|guardian| guardian.member_id);
+    let mut event_type = LoginEventType::Failure;
+
+    if let Some(guardian) = guardian {
+        check_guardian_account_lock(&db, &client, &guardian).await?;
+
+        let is_valid = verify_password(&payload.password, &guardian.password).map_err(|e| {
+            error!(error = %e, "guardian_login_handler failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?;
+
+        if is_valid {
+            return complete_guardian_login(&db, &cookies, client, guardian, &payload.password)
+                .await;
+        }
+
+        event_type = record_failed_guardian_login(&db, &guardian).await?;
+    }
+
+    if let Some(ip) = client.ip_address.as_deref() {
+        record_ip_failure(ip, &guardian_throttle_account(&payload.username));
+    }
+
+    record_guardian_login_event(
+        &db,
+        guardian_id,
+        Some(payload.username),
+        event_type,
+        &client,
+    )
+    .await;
+
+    Err(AppResponse::error(
+        StatusCode::BAD_REQUEST,
+        "使用者名稱或密碼錯誤",
+    ))
+}
+
+async fn complete_guardian_login(
+    db: &DatabaseConnection,
+    cookies: &Cookies,
+    client: ClientInfo,
+    guardian: guardians::Model,
+    password: &str,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if let Some(ip) = client.ip_address.as_deref() {
+        clear_ip_failures(ip, &guardian_throttle_account(&guardian.username));
+    }
+
+    if password_needs_rehash(&guardian.password) {
+        rehash_guardian_password(db, guardian.member_id, password).await;
+    }
+
+    let now = Utc::now().naive_utc();
+    let new_session = guardian_sessions::ActiveModel {
+        guardian_id: Set(guardian.member_id),
+        ip_address: Set(client.ip_address.clone()),
+        user_agent: Set(client.user_agent.clone()),
+        created_at: Set(now),
+        expires_at: Set(now + CONFIG.auth.guardian_session_lifetime()),
+        ..Default::default()
+    };
+
+    let session = new_session.insert(db).await.map_err(|e| {
+        error!(error = %e, "guardian_login_handler failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    guardians::Entity::update_many()
+        .col_expr(guardians::Column::FailedLoginAttempts, Expr::value(0))
+        .col_expr(
+            guardians::Column::LockedUntil,
+            Expr::value(None::<NaiveDateTime>),
+        )
+        .col_expr(guardians::Column::LastLoginAt, Expr::value(now))
+        .filter(guardians::Column::MemberId.eq(guardian.member_id))
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "guardian_login_handler failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?;
+
+    issue_guardian_token(cookies, &guardian, session.id)?;
+
+    record_guardian_login_event(
+        db,
+        Some(guardian.member_id),
+        Some(guardian.username),
+        LoginEventType::Success,
+        &client,
+    )
+    .await;
+
+    Ok(AppResponse::success("登入成功"))
+}
+
+async fn check_guardian_account_lock(
+    db: &DatabaseConnection,
+    client: &ClientInfo,
+    guardian: &guardians::Model,
+) -> Result<(), (StatusCode, Json<AppResponse>)> {
+    let now = Utc::now().naive_utc();
+    let Some(locked_until) = guardian.locked_until.filter(|until| *until > now) else {
+        return Ok(());
+    };
+
+    record_guardian_login_event(
+        db,
+        Some(guardian.member_id),
+        Some(guardian.username.clone()),
+        LoginEventType::Lockout,
+        client,
+    )
+    .await;
+
+    Err(AppResponse::error(
+        StatusCode::TOO_MANY_REQUESTS,
+        format!(
+            "帳號已暫時鎖定，請於 {} 分鐘後再試",
+            ((locked_until - now).num_seconds() as u64).div_ceil(60)
+        ),
+    ))
+}
+
+/// 與教職員相同，累計失敗次數並在達到門檻後鎖定帳號，回傳這次嘗試應記錄的事件類型。
+async fn record_failed_guardian_login(
+    db: &DatabaseConnection,
+    guardian: &guardians::Model,
+) -> Result<LoginEventType, (StatusCode, Json<AppResponse>)> {
+    let updated = guardians::Entity::update_many()
+        .col_expr(
+            guardians::Column::FailedLoginAttempts,
+            Expr::col(guardians::Column::FailedLoginAttempts).add(1),
+        )
+        .filter(guardians::Column::MemberId.eq(guardian.member_id))
+        .exec_with_returning(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "record_failed_guardian_login failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?;
+
+    let Some(updated) = updated.into_iter().next() else {
+        return Ok(LoginEventType::Failure);
+    };
+
+    let failed_attempts = updated.failed_login_attempts.max(0) as u32;
+    if let Some(duration) = CONFIG.auth.login_throttle.lockout_duration(failed_attempts) {
+        let locked_until = Utc::now().naive_utc() + duration;
+
+        guardians::Entity::update_many()
+            .col_expr(guardians::Column::LockedUntil, Expr::value(locked_until))
+            .filter(guardians::Column::MemberId.eq(guardian.member_id))
+            .exec(db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "record_failed_guardian_login failed");
+                AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+            })?;
+
+        warn!(
+            username = %updated.username,
+            failed_attempts,
+            locked_until = %locked_until,
+            "guardian account locked after failed logins"
+        );
+
+        return Ok(LoginEventType::Lockout);
+    }
+
+    Ok(LoginEventType::Failure)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/guardian/logout",
+    tag = "guardian-portal",
+    security(("csrf_token" = [])),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn guardian_logout_handler(
+    cookies: Cookies,
+    client: ClientInfo,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if let Some(cookie) = cookies.get(GUARDIAN_TOKEN_COOKIE) {
+        if let Ok(token_data) = decode_guardian_token(cookie.value()) {
+            let revoked = guardian_sessions::Entity::update_many()
+                .col_expr(
+                    guardian_sessions::Column::RevokedAt,
+                    Expr::value(Utc::now().naive_utc()),
+                )
+                .filter(guardian_sessions::Column::Id.eq(token_data.claims.jti))
+                .filter(guardian_sessions::Column::RevokedAt.is_null())
+                .exec(&db)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "guardian_logout_handler failed");
+                    AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+                })?;
+
+            if revoked.rows_affected > 0 {
+                record_guardian_login_event(
+                    &db,
+                    Some(token_data.claims.sub),
+                    None,
+                    LoginEventType::Logout,
+                    &client,
+                )
+                .await;
+            }
+        }
+
+        cookies.add(removal_cookie(GUARDIAN_TOKEN_COOKIE));
+    }
+
+    Ok(AppResponse::success("登出成功"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me",
+    tag = "guardian-portal",
+    responses(
+        (status = 200, body = AppResponse<GuardianMeResponse>, description = "成功"),
+    ),
+)]
+pub async fn guardian_me_handler(
+    Extension(claims): Extension<GuardianClaims>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<AppResponse<GuardianMeResponse>>, (StatusCode, Json<AppResponse>)> {
+    let guardian = find_guardian_by_id(&db, claims.sub)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::UNAUTHORIZED, "請檢查登入是否成功"))?;
+
+    let name = find_member_by_id(&db, guardian.member_id)
+        .await?
+        .map(|member| member.name)
+        .unwrap_or_else(|| "未提供姓名".to_string());
+
+    let children = find_children(&db, guardian.member_id).await?;
+    let child_ids: Vec<Uuid> = children.iter().map(|child| child.person_id).collect();
+    let member_name_map = get_members_name_hashmap(&db, child_ids).await?;
+
+    let children = children
+        .into_iter()
+        .map(|child| GuardianChildView {
+            student_id: child.person_id,
+            name: member_name_map
+                .get(&child.person_id)
+                .cloned()
+                .unwrap_or_else(|| "未知姓名".to_string()),
+            relation_type: child.relation_type,
+        })
+        .collect();
+
+    Ok(AppResponse::success_with_data(GuardianMeResponse {
+        id: guardian.member_id,
+        username: guardian.username,
+        name,
+        must_change_password: guardian.must_change_password,
+        children,
+        exp: claims.exp,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/me/password",
+    tag = "guardian-portal",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn change_guardian_password(
+    cookies: Cookies,
+    Extension(claims): Extension<GuardianClaims>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if let Err(err) = payload.validate() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("參數不正確。錯誤參數：{}", err),
+        ));
+    }
+
+    let guardian = find_guardian_by_id(&db, claims.sub)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::UNAUTHORIZED, "請檢查登入是否成功"))?;
+
+    let is_valid = verify_password(&payload.current_password, &guardian.password).map_err(|e| {
+        error!(error = %e, "change_guardian_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    if !is_valid {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "目前的密碼不正確",
+        ));
+    }
+
+    ensure_password_strength(&payload.new_password, &[&guardian.username])?;
+
+    let password_hash = hash_password(&payload.new_password).map_err(|e| {
+        error!(error = %e, "change_guardian_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "change_guardian_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let mut guardian: guardians::ActiveModel = guardian.into();
+    guardian.password = Set(password_hash);
+    guardian.must_change_password = Set(false);
+    guardian.updated_at = Set(Utc::now().naive_utc());
+
+    let guardian = guardian.update(&txn).await.map_err(|e| {
+        error!(error = %e, "change_guardian_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
+    })?;
+
+    // 其他裝置上的登入階段一併登出，只保留目前這個
+    guardian_sessions::Entity::update_many()
+        .col_expr(
+            guardian_sessions::Column::RevokedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(guardian_sessions::Column::GuardianId.eq(claims.sub))
+        .filter(guardian_sessions::Column::Id.ne(claims.jti))
+        .filter(guardian_sessions::Column::RevokedAt.is_null())
+        .exec(&txn)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "change_guardian_password failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "change_guardian_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    // 重新發出 token，解除「必須變更密碼」的限制
+    issue_guardian_token(&cookies, &guardian, claims.jti)?;
+
+    Ok(AppResponse::success("密碼已更新"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/children/{student_id}/attendance",
+    tag = "guardian-portal",
+    params(("student_id" = Uuid, Path, description = "子女的成員 id"), GuardianAttendanceQuery),
+    responses(
+        (status = 200, body = AppResponse<Vec<GuardianAttendanceView>>, description = "成功"),
+    ),
+)]
+pub async fn get_child_attendance(
+    Extension(claims): Extension<GuardianClaims>,
+    State(db): State<DatabaseConnection>,
+    Path(student_id): Path<Uuid>,
+    Query(query): Query<GuardianAttendanceQuery>,
+) -> Result<Json<AppResponse<Vec<GuardianAttendanceView>>>, (StatusCode, Json<AppResponse>)> {
+    ensure_child_of_guardian(&db, claims.sub, student_id).await?;
+
+    let mut select = attendance_students::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            attendance_students::Relation::AttendanceRecords.def(),
+        )
+        .filter(attendance_students::Column::StudentId.eq(student_id));
+    if let Some(from) = query.from {
+        select = select.filter(attendance_records::Column::Date.gte(from));
+    }
+    if let Some(to) = query.to {
+        select = select.filter(attendance_records::Column::Date.lte(to));
+    }
+
+    let records = select
+        .select_only()
+        .column(attendance_records::Column::Date)
+        .column(attendance_students::Column::AttendanceStatus)
+        .column(attendance_students::Column::Note)
+        .order_by_desc(attendance_records::Column::Date)
+        .into_tuple::<(chrono::NaiveDate, bool, Option<String>)>()
+        .all(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_child_attendance failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let result = records
+        .into_iter()
+        .map(|(date, attendance_status, note)| GuardianAttendanceView {
+            date,
+            attendance_status,
+            note,
+        })
+        .collect();
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/children/{student_id}/exams",
+    tag = "guardian-portal",
+    params(("student_id" = Uuid, Path, description = "子女的成員 id")),
+    responses(
+        (status = 200, body = AppResponse<Vec<StudentInfoView>>, description = "成功"),
+    ),
+)]
+pub async fn get_child_exams(
+    Extension(claims): Extension<GuardianClaims>,
+    State(db): State<DatabaseConnection>,
+    Path(student_id): Path<Uuid>,
+) -> Result<Json<AppResponse<Vec<StudentInfoView>>>, (StatusCode, Json<AppResponse>)> {
+    ensure_child_of_guardian(&db, claims.sub, student_id).await?;
+
+    let infos_with_exams = student_infos::Entity::find()
+        .filter(student_infos::Column::StudentId.eq(student_id))
+        .order_by_desc(student_infos::Column::AcademicYear)
+        .find_with_related(student_exams::Entity)
+        .all(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_child_exams failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let name = find_member_by_id(&db, student_id)
+        .await?
+        .map(|member| member.name)
+        .unwrap_or_else(|| "未知姓名".to_string());
+
+    let result = infos_with_exams
+        .into_iter()
+        .map(|(info, exams)| student_info_to_view(info, exams, name.clone()))
+        .collect();
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/announcements",
+    tag = "guardian-portal",
+    responses(
+        (status = 200, body = AppResponse<Vec<AnnouncementView>>, description = "成功"),
+    ),
+)]
+pub async fn get_guardian_announcements(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<AppResponse<Vec<AnnouncementView>>>, (StatusCode, Json<AppResponse>)> {
+    let announcements = announcements::Entity::find()
+        .filter(announcements::Column::DeletedAt.is_null())
+        .filter(announcements::Column::Audience.is_in([
+            AnnouncementAudience::Guardians.as_str(),
+            AnnouncementAudience::Everyone.as_str(),
+        ]))
+        .order_by_desc(announcements::Column::UpdatedAt)
+        .all(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_guardian_announcements failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?;
+
+    let publisher_ids: Vec<Uuid> = announcements
+        .iter()
+        .map(|announcement| announcement.publisher_id)
+        .collect();
+
+    let member_name_map = get_members_name_hashmap(&db, publisher_ids).await?;
+
+    let result = announcements
+        .into_iter()
+        .map(|announcement| AnnouncementView {
+            id: announcement.id,
+            name: member_name_map
+                .get(&announcement.publisher_id)
+                .cloned()
+                .unwrap_or_else(|| "未知姓名".to_string()),
+            title: announcement.title,
+            content: announcement.content,
+            audience: AnnouncementAudience::from_db(&announcement.audience),
+            updated_at: Utc.from_utc_datetime(&announcement.updated_at).into(),
+        })
+        .collect();
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+/// 取得尚未撤銷且未過期、且帳號仍有效的家長登入階段。
+pub(crate) async fn find_active_guardian_session<C>(
+    db: &C,
+    session_id: Uuid,
+) -> Result<Option<guardian_sessions::Model>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    guardian_sessions::Entity::find_by_id(session_id)
+        .inner_join(guardians::Entity)
+        .filter(guardian_sessions::Column::RevokedAt.is_null())
+        .filter(guardian_sessions::Column::ExpiresAt.gt(Utc::now().naive_utc()))
+        .filter(guardians::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "find_active_guardian_session failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })
+}
+
+async fn find_guardian_by_id<C>(
+    db: &C,
+    guardian_id: Uuid,
+) -> Result<Option<guardians::Model>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    guardians::Entity::find_by_id(guardian_id)
+        .filter(guardians::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "find_guardian_by_id failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })
+}
+
+/// 家長的子女為 `member_family_relations` 中以家長為親屬、且仍在學的學生。
+async fn find_children<C>(
+    db: &C,
+    guardian_id: Uuid,
+) -> Result<Vec<member_family_relations::Model>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    member_family_relations::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            member_family_relations::Relation::Members2.def(),
+        )
+        .join(JoinType::InnerJoin, members::Relation::Students.def())
+        .filter(member_family_relations::Column::RelativeId.eq(guardian_id))
+        .filter(students::Column::DeletedAt.is_null())
+        .all(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "find_children failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })
+}
+
+/// 不是自己子女的學生一律視為不存在，避免家長藉由狀態碼猜測其他學生的 id。
+async fn ensure_child_of_guardian<C>(
+    db: &C,
+    guardian_id: Uuid,
+    student_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let is_child = find_children(db, guardian_id)
+        .await?
+        .iter()
+        .any(|child| child.person_id == student_id);
+
+    if !is_child {
+        return Err(AppResponse::error(
+            StatusCode::NOT_FOUND,
+            "找不到對應的學生",
+        ));
+    }
+
+    Ok(())
+}
+
+async fn revoke_guardian_sessions<C>(
+    db: &C,
+    guardian_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    guardian_sessions::Entity::update_many()
+        .col_expr(
+            guardian_sessions::Column::RevokedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(guardian_sessions::Column::GuardianId.eq(guardian_id))
+        .filter(guardian_sessions::Column::RevokedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "revoke_guardian_sessions failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(())
+}
+
+/// 以目前的雜湊設定重新保存密碼，失敗時只記錄錯誤，不影響這次登入。
+async fn rehash_guardian_password(db: &DatabaseConnection, guardian_id: Uuid, password: &str) {
+    let password_hash = match hash_password(password) {
+        Ok(password_hash) => password_hash,
+        Err(e) => {
+            error!(error = %e, "rehash_guardian_password failed");
+            return;
+        }
+    };
+
+    if let Err(e) = guardians::Entity::update_many()
+        .col_expr(guardians::Column::Password, Expr::value(password_hash))
+        .filter(guardians::Column::MemberId.eq(guardian_id))
+        .exec(db)
+        .await
+    {
+        error!(error = %e, "rehash_guardian_password failed");
+    }
+}
+
+/// 家長的 token 與登入階段同時到期，不另外發 refresh token。
+fn issue_guardian_token(
+    cookies: &Cookies,
+    guardian: &guardians::Model,
+    session_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)> {
+    let token = create_guardian_token(
+        guardian.member_id,
+        session_id,
+        guardian.must_change_password,
+    )
+    .map_err(|status_code| AppResponse::error(status_code, "伺服器發生異常"))?;
+
+    let expiration_time = (Utc::now() + CONFIG.auth.guardian_session_lifetime()).timestamp();
+    let mut cookie = Cookie::new(GUARDIAN_TOKEN_COOKIE, token);
+    cookie.set_expires(OffsetDateTime::from_unix_timestamp(expiration_time).unwrap());
+    cookie.set_http_only(true);
+    cookie.set_secure(true);
+    cookie.set_same_site(tower_cookies::cookie::SameSite::Strict);
+    cookie.set_path("/");
+
+    cookies.add(cookie);
+    cookies.add(csrf_cookie(generate_opaque_token()));
+
+    Ok(())
+}
+
+/// 家長與教職員的帳號名稱可能相同，在登入限制中分開計算。
+fn guardian_throttle_account(username: &str) -> String {
+    format!("guardian:{}", username)
+}
@@ -0,0 +1,149 @@
+use crate::db::migration::migration_status;
+use crate::models::{
+    AppResponse, CheckError, CheckStatus, DatabaseCheck, MigrationsCheck, ReadinessChecks,
+    ReadinessView,
+};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use sea_orm::DatabaseConnection;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+use tracing::warn;
+
+/// 每一項檢查的時限，避免資料庫卡住時探測請求也一起卡住
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 存活檢查，只要程式還能處理請求就回應成功，不檢查外部服務。
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn health_handler() -> Json<AppResponse> {
+    AppResponse::success("OK")
+}
+
+/// 就緒檢查，資料庫無法連線或 migration 與程式版本不符時回應 503。
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, body = AppResponse<ReadinessView>, description = "成功"),
+        (status = 503, body = AppResponse<ReadinessView>, description = "資料庫無法連線或 migration 與程式版本不符"),
+    ),
+)]
+pub async fn readiness_handler(
+    State(db): State<DatabaseConnection>,
+) -> (StatusCode, Json<AppResponse<ReadinessView>>) {
+    let database = check_database(&db).await;
+    let migrations = match database.status {
+        CheckStatus::Ok => check_migrations(&db).await,
+        CheckStatus::Error => failed_migrations_check(CheckError::DatabaseUnavailable),
+    };
+
+    let is_ready = database.status == CheckStatus::Ok && migrations.status == CheckStatus::Ok;
+    let (status_code, status, message) = match is_ready {
+        true => (StatusCode::OK, CheckStatus::Ok, "OK"),
+        false => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            CheckStatus::Error,
+            "服務尚未就緒",
+        ),
+    };
+
+    if !is_ready {
+        warn!(?database, ?migrations, "readiness check failed");
+    }
+
+    (
+        status_code,
+        Json(AppResponse {
+            message: message.to_string(),
+            data: Some(ReadinessView {
+                status,
+                checks: ReadinessChecks {
+                    database,
+                    migrations,
+                },
+            }),
+        }),
+    )
+}
+
+async fn check_database(db: &DatabaseConnection) -> DatabaseCheck {
+    let start = Instant::now();
+    let result = match timeout(CHECK_TIMEOUT, db.ping()).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => {
+            warn!(error = %e, "check_database failed");
+            Err(CheckError::QueryFailed)
+        }
+        Err(_) => {
+            warn!("check_database timed out");
+            Err(CheckError::Timeout)
+        }
+    };
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(()) => DatabaseCheck {
+            status: CheckStatus::Ok,
+            latency_ms,
+            error: None,
+        },
+        Err(e) => DatabaseCheck {
+            status: CheckStatus::Error,
+            latency_ms,
+            error: Some(e),
+        },
+    }
+}
+
+async fn check_migrations(db: &DatabaseConnection) -> MigrationsCheck {
+    let result = match timeout(CHECK_TIMEOUT, migration_status(db)).await {
+        Ok(Ok(status)) => Ok(status),
+        Ok(Err(e)) => {
+            warn!(error = %e, "check_migrations failed");
+            Err(CheckError::QueryFailed)
+        }
+        Err(_) => {
+            warn!("check_migrations timed out");
+            Err(CheckError::Timeout)
+        }
+    };
+
+    match result {
+        Ok(status) => MigrationsCheck {
+            status: match status.is_current() {
+                true => CheckStatus::Ok,
+                false => CheckStatus::Error,
+            },
+            applied: status.applied,
+            expected: status.expected,
+            pending: status.pending,
+            modified: status.modified,
+            unknown: status.unknown,
+            dirty: status.dirty,
+            error: None,
+        },
+        Err(e) => failed_migrations_check(e),
+    }
+}
+
+fn failed_migrations_check(error: CheckError) -> MigrationsCheck {
+    MigrationsCheck {
+        status: CheckStatus::Error,
+        applied: 0,
+        expected: 0,
+        pending: Vec::new(),
+        modified: Vec::new(),
+        unknown: Vec::new(),
+        dirty: None,
+        error: Some(error),
+    }
+}
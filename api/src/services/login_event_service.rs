@@ -0,0 +1,141 @@
+use crate::db::entities::{guardian_login_events, login_events};
+use crate::models::{
+    AppResponse, LoginEventType, LoginEventView, LoginHistoryQuery, Permission, PermissionSet,
+};
+use crate::util::{record_login, Claims, ClientInfo};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::Utc;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+use tracing::error;
+use uuid::Uuid;
+
+const DEFAULT_HISTORY_LIMIT: u64 = 100;
+const MAX_HISTORY_LIMIT: u64 = 500;
+
+#[utoipa::path(
+    get,
+    path = "/teachers/{id}/login-history",
+    tag = "teachers",
+    security(("auth_token" = [])),
+    params(("id" = Uuid, Path, description = "教職員的成員 id"), LoginHistoryQuery),
+    responses(
+        (status = 200, body = AppResponse<Vec<LoginEventView>>, description = "成功"),
+    ),
+)]
+pub async fn get_login_history(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+    Query(query): Query<LoginHistoryQuery>,
+) -> Result<Json<AppResponse<Vec<LoginEventView>>>, (StatusCode, Json<AppResponse>)> {
+    check_permission(claims.sub, teacher_id, &permissions)?;
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_HISTORY_LIMIT)
+        .min(MAX_HISTORY_LIMIT);
+
+    let events = login_events::Entity::find()
+        .filter(login_events::Column::TeacherId.eq(teacher_id))
+        .order_by_desc(login_events::Column::CreatedAt)
+        .limit(limit)
+        .all(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_login_history failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let result = events
+        .into_iter()
+        .map(LoginEventView::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "無效的登入事件類型"))?;
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+/// 寫入登入稽核紀錄。寫入失敗只記錄錯誤，不影響登入流程本身。
+pub(crate) async fn record_login_event<C>(
+    db: &C,
+    teacher_id: Option<Uuid>,
+    username: Option<String>,
+    event_type: LoginEventType,
+    client: &ClientInfo,
+) where
+    C: ConnectionTrait,
+{
+    record_login_metric("teacher", event_type);
+
+    let new_event = login_events::ActiveModel {
+        teacher_id: Set(teacher_id),
+        username: Set(username),
+        event_type: Set(event_type.into()),
+        ip_address: Set(client.ip_address.clone()),
+        user_agent: Set(client.user_agent.clone()),
+        created_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    if let Err(e) = new_event.insert(db).await {
+        error!(error = %e, "record_login_event failed");
+    }
+}
+
+/// 寫入家長的登入稽核紀錄，與 `record_login_event` 相同，寫入失敗不影響登入流程。
+pub(crate) async fn record_guardian_login_event<C>(
+    db: &C,
+    guardian_id: Option<Uuid>,
+    username: Option<String>,
+    event_type: LoginEventType,
+    client: &ClientInfo,
+) where
+    C: ConnectionTrait,
+{
+    record_login_metric("guardian", event_type);
+
+    let new_event = guardian_login_events::ActiveModel {
+        guardian_id: Set(guardian_id),
+        username: Set(username),
+        event_type: Set(event_type.into()),
+        ip_address: Set(client.ip_address.clone()),
+        user_agent: Set(client.user_agent.clone()),
+        created_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    if let Err(e) = new_event.insert(db).await {
+        error!(error = %e, "record_guardian_login_event failed");
+    }
+}
+
+fn record_login_metric(account: &'static str, event_type: LoginEventType) {
+    match event_type {
+        LoginEventType::Success => record_login(account, "success"),
+        LoginEventType::Failure => record_login(account, "failure"),
+        LoginEventType::Lockout => record_login(account, "lockout"),
+        LoginEventType::Logout => {}
+    }
+}
+
+fn check_permission(
+    source_id: Uuid,
+    target_id: Uuid,
+    permissions: &PermissionSet,
+) -> Result<(), (StatusCode, Json<AppResponse>)> {
+    if permissions.contains(Permission::TeachersManage) || source_id.eq(&target_id) {
+        return Ok(());
+    }
+
+    Err(AppResponse::error(
+        StatusCode::FORBIDDEN,
+        "只有本人或是教職員管理者能查看登入紀錄",
+    ))
+}
@@ -1,23 +1,35 @@
-use crate::db::entities::members;
-use crate::models::{AppResponse, MemberDto, MemberView, UpsertMemberRequest};
+use crate::db::entities::{guardians, member_family_relations, members};
+use crate::models::{
+    AppResponse, FamilyRelationView, MemberDto, MemberView, UpsertFamilyRelationRequest,
+    UpsertMemberRequest,
+};
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::Json;
 use chrono::Utc;
-use log::error;
+use sea_orm::sea_query::OnConflict;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use tracing::error;
 use uuid::Uuid;
 use validator::Validate;
 
+#[utoipa::path(
+    get,
+    path = "/members",
+    tag = "members",
+    responses(
+        (status = 200, body = AppResponse<Vec<MemberView>>, description = "成功"),
+    ),
+)]
 pub async fn get_members(
     State(db): State<DatabaseConnection>,
 ) -> Result<Json<AppResponse<Vec<MemberView>>>, (StatusCode, Json<AppResponse>)> {
     let members = members::Entity::find().all(&db).await.map_err(|e| {
-        error!("{}", e);
+        error!(error = %e, "get_members failed");
         AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
     })?;
 
@@ -32,6 +44,15 @@ pub async fn get_members(
     Ok(AppResponse::success_with_data(result))
 }
 
+#[utoipa::path(
+    post,
+    path = "/members",
+    tag = "members",
+    request_body = UpsertMemberRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn add_member(
     State(db): State<DatabaseConnection>,
     Json(payload): Json<UpsertMemberRequest>,
@@ -57,7 +78,7 @@ pub async fn add_member(
         name: Set(payload.member_dto.name),
         gender: Set(payload.member_dto.gender),
         id_number: Set(payload.member_dto.id_number),
-        birth_date: Set(payload.member_dto.birth_date.map(|d| d.naive_utc().into())),
+        birth_date: Set(payload.member_dto.birth_date.map(|d| d.naive_utc())),
         home_phone_number: Set(payload.member_dto.home_phone_number),
         mobile_phone_number: Set(payload.member_dto.mobile_phone_number),
         address: Set(payload.member_dto.address),
@@ -70,13 +91,23 @@ pub async fn add_member(
     };
 
     new_member.insert(&db).await.map_err(|e| {
-        error!("{}", e);
+        error!(error = %e, "add_member failed");
         AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
     })?;
 
     Ok(AppResponse::success("建立成功"))
 }
 
+#[utoipa::path(
+    put,
+    path = "/members/{id}",
+    tag = "members",
+    params(("id" = Uuid, Path, description = "成員 id")),
+    request_body = UpsertMemberRequest,
+    responses(
+        (status = 200, body = AppResponse<MemberView>, description = "成功"),
+    ),
+)]
 pub async fn update_member(
     State(db): State<DatabaseConnection>,
     Path(member_id): Path<Uuid>,
@@ -98,6 +129,162 @@ pub async fn update_member(
     Ok(AppResponse::success_with_data(member_view))
 }
 
+#[utoipa::path(
+    get,
+    path = "/members/{id}/relatives",
+    tag = "members",
+    params(("id" = Uuid, Path, description = "成員 id")),
+    responses(
+        (status = 200, body = AppResponse<Vec<FamilyRelationView>>, description = "成功"),
+    ),
+)]
+pub async fn get_family_relations(
+    State(db): State<DatabaseConnection>,
+    Path(member_id): Path<Uuid>,
+) -> Result<Json<AppResponse<Vec<FamilyRelationView>>>, (StatusCode, Json<AppResponse>)> {
+    let relations = member_family_relations::Entity::find()
+        .filter(member_family_relations::Column::PersonId.eq(member_id))
+        .all(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_family_relations failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let relative_ids: Vec<Uuid> = relations
+        .iter()
+        .map(|relation| relation.relative_id)
+        .collect();
+
+    let member_name_map = get_members_name_hashmap(&db, relative_ids.clone()).await?;
+
+    let guardian_ids: HashSet<Uuid> = guardians::Entity::find()
+        .filter(guardians::Column::MemberId.is_in(relative_ids))
+        .filter(guardians::Column::DeletedAt.is_null())
+        .all(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_family_relations failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?
+        .into_iter()
+        .map(|guardian| guardian.member_id)
+        .collect();
+
+    let result = relations
+        .into_iter()
+        .map(|relation| FamilyRelationView {
+            relative_id: relation.relative_id,
+            name: member_name_map
+                .get(&relation.relative_id)
+                .cloned()
+                .unwrap_or_else(|| "未知姓名".to_string()),
+            relation_type: relation.relation_type,
+            has_guardian_account: guardian_ids.contains(&relation.relative_id),
+        })
+        .collect();
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+/// 設定成員的親屬，家長帳號透過這份關係找到自己的子女。
+#[utoipa::path(
+    put,
+    path = "/members/{id}/relatives/{relative_id}",
+    tag = "members",
+    params(("id" = Uuid, Path, description = "成員 id"), ("relative_id" = Uuid, Path, description = "親屬的成員 id")),
+    request_body = UpsertFamilyRelationRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn upsert_family_relation(
+    State(db): State<DatabaseConnection>,
+    Path((member_id, relative_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<UpsertFamilyRelationRequest>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if let Err(err) = payload.validate() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("參數不正確。錯誤參數：{}", err),
+        ));
+    }
+
+    if member_id == relative_id {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "不能將成員設為自己的親屬",
+        ));
+    }
+
+    if find_member_by_id(&db, member_id).await?.is_none()
+        || find_member_by_id(&db, relative_id).await?.is_none()
+    {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "找不到對應的成員",
+        ));
+    }
+
+    let relation = member_family_relations::ActiveModel {
+        person_id: Set(member_id),
+        relative_id: Set(relative_id),
+        relation_type: Set(payload.relation_type),
+        created_at: Set(Utc::now().naive_utc()),
+    };
+
+    member_family_relations::Entity::insert(relation)
+        .on_conflict(
+            OnConflict::columns([
+                member_family_relations::Column::PersonId,
+                member_family_relations::Column::RelativeId,
+            ])
+            .update_column(member_family_relations::Column::RelationType)
+            .to_owned(),
+        )
+        .exec(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "upsert_family_relation failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?;
+
+    Ok(AppResponse::success("更新成功"))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/members/{id}/relatives/{relative_id}",
+    tag = "members",
+    params(("id" = Uuid, Path, description = "成員 id"), ("relative_id" = Uuid, Path, description = "親屬的成員 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn delete_family_relation(
+    State(db): State<DatabaseConnection>,
+    Path((member_id, relative_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let result = member_family_relations::Entity::delete_many()
+        .filter(member_family_relations::Column::PersonId.eq(member_id))
+        .filter(member_family_relations::Column::RelativeId.eq(relative_id))
+        .exec(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "delete_family_relation failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "刪除失敗")
+        })?;
+
+    if result.rows_affected == 0 {
+        return Err(AppResponse::error(
+            StatusCode::NOT_FOUND,
+            "找不到對應的親屬關係",
+        ));
+    }
+
+    Ok(AppResponse::success("刪除成功"))
+}
+
 pub(crate) async fn find_member_by_id<C>(
     db: &C,
     id: Uuid,
@@ -110,7 +297,7 @@ where
         .one(db)
         .await
         .map_err(|e| {
-            error!("find_member_by_id error:{}", e);
+            error!(error = %e, "find_member_by_id failed");
             AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
         })
 }
@@ -124,7 +311,7 @@ pub(crate) async fn find_member_by_id_number(
         .one(db)
         .await
         .map_err(|e| {
-            error!("find_member_by_id_number error:{}", e);
+            error!(error = %e, "find_member_by_id_number failed");
             AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
         })
 }
@@ -144,7 +331,7 @@ where
             member.name = Set(dto.name);
             member.gender = Set(dto.gender);
             member.id_number = Set(dto.id_number);
-            member.birth_date = Set(dto.birth_date.map(|d| d.naive_utc().into()));
+            member.birth_date = Set(dto.birth_date.map(|d| d.naive_utc()));
             member.home_phone_number = Set(dto.home_phone_number);
             member.mobile_phone_number = Set(dto.mobile_phone_number);
             member.address = Set(dto.address);
@@ -155,7 +342,7 @@ where
             member.updated_at = Set(Utc::now().naive_utc());
 
             let result = member.update(db).await.map_err(|e| {
-                error!("upsert_member_with_context error:{}", e);
+                error!(error = %e, "upsert_member_with_context failed");
                 AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
             })?;
 
@@ -167,7 +354,7 @@ where
                 name: Set(dto.name),
                 gender: Set(dto.gender),
                 id_number: Set(dto.id_number),
-                birth_date: Set(dto.birth_date.map(|d| d.naive_utc().into())),
+                birth_date: Set(dto.birth_date.map(|d| d.naive_utc())),
                 home_phone_number: Set(dto.home_phone_number),
                 mobile_phone_number: Set(dto.mobile_phone_number),
                 address: Set(dto.address),
@@ -179,7 +366,7 @@ where
             };
 
             let new_member_result = new_member.insert(db).await.map_err(|e| {
-                error!("upsert_member_with_context error:{}", e);
+                error!(error = %e, "upsert_member_with_context failed");
                 AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
             })?;
 
@@ -200,7 +387,7 @@ where
         .all(db)
         .await
         .map_err(|e| {
-            error!("get_members_name_hashmap error:{}", e);
+            error!(error = %e, "get_members_name_hashmap failed");
             AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "查詢成員名稱失敗")
         })?;
 
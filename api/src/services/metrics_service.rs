@@ -0,0 +1,69 @@
+use crate::config::CONFIG;
+use crate::db::entities::{attendance_records, attendance_students, students};
+use crate::util::{render_metrics, set_gauge};
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use chrono::Local;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, JoinType, PaginatorTrait, QueryFilter,
+    QuerySelect, RelationTrait,
+};
+use tracing::error;
+
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// 輸出 Prometheus 指標，連線池與業務數量在每次抓取時才計算。
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses(
+        (status = 200, content_type = "text/plain", body = String, description = "Prometheus 格式的指標，需啟用 metrics.enabled"),
+    ),
+)]
+pub async fn metrics_handler(State(db): State<DatabaseConnection>) -> Response {
+    record_pool_gauges(&db);
+    if let Err(e) = record_domain_gauges(&db).await {
+        // 業務指標查詢失敗時仍輸出其他指標，沿用上一次的數值
+        error!(error = %e, "metrics_handler failed");
+    }
+
+    match render_metrics() {
+        Some(body) => ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+fn record_pool_gauges(db: &DatabaseConnection) {
+    let pool = db.get_postgres_connection_pool();
+
+    set_gauge("db_pool_connections", pool.size() as f64);
+    set_gauge("db_pool_idle_connections", pool.num_idle() as f64);
+    set_gauge(
+        "db_pool_max_connections",
+        CONFIG.database.max_connections as f64,
+    );
+}
+
+async fn record_domain_gauges(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let active_students = students::Entity::find()
+        .filter(students::Column::DeletedAt.is_null())
+        .count(db)
+        .await?;
+
+    let present_today = attendance_students::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            attendance_students::Relation::AttendanceRecords.def(),
+        )
+        .filter(attendance_records::Column::Date.eq(Local::now().date_naive()))
+        .filter(attendance_students::Column::AttendanceStatus.eq(true))
+        .count(db)
+        .await?;
+
+    set_gauge("students_active", active_students as f64);
+    set_gauge("attendance_present_today", present_today as f64);
+
+    Ok(())
+}
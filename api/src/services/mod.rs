@@ -1,9 +1,21 @@
 mod announcement_service;
+mod api_token_service;
+mod assignment_service;
 mod attendance_service;
 mod auth_service;
+mod guardian_service;
+mod health_service;
+mod login_event_service;
 mod member_service;
+mod metrics_service;
+mod oidc_service;
+mod password_service;
+mod role_service;
+mod session_service;
+mod setting_service;
+mod student_info_service;
 mod student_service;
 mod teacher_service;
-mod student_info_service;
+mod two_factor_service;
 
-pub mod prelude;
\ No newline at end of file
+pub mod prelude;
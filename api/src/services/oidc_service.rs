@@ -0,0 +1,509 @@
+use crate::config::{OidcConfig, CONFIG};
+use crate::db::entities::teacher_oidc_identities;
+use crate::models::{
+    AppResponse, LoginEventType, OidcCallbackQuery, OidcIdentityView, PermissionSet,
+    UpsertOidcIdentityRequest,
+};
+use crate::services::auth_service::{
+    check_account_lock, complete_login, removal_cookie, two_factor_challenge_cookie,
+};
+use crate::services::login_event_service::record_login_event;
+use crate::services::role_service::ensure_can_manage_teacher;
+use crate::services::teacher_service::find_teacher_by_id;
+use crate::util::{
+    create_oidc_flow_token, create_two_factor_challenge, decode_oidc_flow_token,
+    exchange_oidc_code, generate_opaque_token, oidc_authorization_url, Claims, ClientInfo,
+    OidcIdentity,
+};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{Html, Redirect};
+use axum::{Extension, Json};
+use chrono::Utc;
+use reqwest::Url;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait,
+};
+use tower_cookies::cookie::time::OffsetDateTime;
+use tower_cookies::cookie::SameSite;
+use tower_cookies::{Cookie, Cookies};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+const OIDC_FLOW_COOKIE: &str = "oidc_flow";
+/// 只在登入流程的路由上送出 flow cookie
+const OIDC_FLOW_COOKIE_PATH: &str = "/api/login/oidc";
+
+/// 回呼失敗時帶給前端登入頁的原因
+enum OidcLoginError {
+    /// 使用者在身分提供者取消登入
+    Cancelled,
+    /// state 不符或流程逾時
+    InvalidState,
+    /// 無法與身分提供者完成驗證
+    ProviderError,
+    /// 外部帳號沒有對應的教職員
+    NotLinked,
+    Locked,
+    ServerError,
+}
+
+impl OidcLoginError {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OidcLoginError::Cancelled => "cancelled",
+            OidcLoginError::InvalidState => "invalid_state",
+            OidcLoginError::ProviderError => "provider_error",
+            OidcLoginError::NotLinked => "not_linked",
+            OidcLoginError::Locked => "locked",
+            OidcLoginError::ServerError => "server_error",
+        }
+    }
+}
+
+/// 回呼完成後要導向的結果
+enum OidcLoginOutcome {
+    LoggedIn,
+    TwoFactorRequired,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/login/oidc",
+    tag = "auth",
+    responses(
+        (status = 303, description = "導向身分提供者的登入頁"),
+        (status = 502, body = AppResponse, description = "無法連線到身分提供者"),
+    ),
+)]
+pub async fn oidc_login_handler(
+    cookies: Cookies,
+) -> Result<Redirect, (StatusCode, Json<AppResponse>)> {
+    let config = oidc_config()?;
+
+    let state = generate_opaque_token();
+    let nonce = generate_opaque_token();
+    let code_verifier = generate_opaque_token();
+
+    let authorization_url = oidc_authorization_url(config, &state, &nonce, &code_verifier)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "oidc_login_handler failed");
+            AppResponse::error(StatusCode::BAD_GATEWAY, "無法連線至身分提供者")
+        })?;
+
+    let flow_token = create_oidc_flow_token(state, nonce, code_verifier, config.flow_lifetime())
+        .map_err(|status_code| AppResponse::error(status_code, "伺服器發生異常"))?;
+
+    // 身分提供者導回時屬於跨站的導覽，SameSite=Strict 的 cookie 不會被送出，因此使用 Lax
+    let expiration_time = (Utc::now() + config.flow_lifetime()).timestamp();
+    let mut cookie = Cookie::new(OIDC_FLOW_COOKIE, flow_token);
+    cookie.set_expires(OffsetDateTime::from_unix_timestamp(expiration_time).unwrap());
+    cookie.set_http_only(true);
+    cookie.set_secure(true);
+    cookie.set_same_site(SameSite::Lax);
+    cookie.set_path(OIDC_FLOW_COOKIE_PATH);
+    cookies.add(cookie);
+
+    Ok(Redirect::to(&authorization_url))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/login/oidc/callback",
+    tag = "auth",
+    params(OidcCallbackQuery),
+    responses(
+        (status = 200, content_type = "text/html", body = String, description = "登入完成或失敗後導回前端的頁面"),
+    ),
+)]
+pub async fn oidc_callback_handler(
+    cookies: Cookies,
+    client: ClientInfo,
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<Html<String>, (StatusCode, Json<AppResponse>)> {
+    let config = oidc_config()?;
+
+    let flow_cookie = cookies
+        .get(OIDC_FLOW_COOKIE)
+        .map(|cookie| cookie.value().to_string());
+    let mut removal = removal_cookie(OIDC_FLOW_COOKIE);
+    removal.set_path(OIDC_FLOW_COOKIE_PATH);
+    cookies.add(removal);
+
+    let target = match handle_callback(&db, &cookies, client, config, flow_cookie, query).await {
+        Ok(OidcLoginOutcome::LoggedIn) => config.post_login_redirect.clone(),
+        Ok(OidcLoginOutcome::TwoFactorRequired) => {
+            login_page_with(config, "two_factor", "required")
+        }
+        Err(e) => login_page_with(config, "error", e.as_str()),
+    };
+
+    Ok(redirect_page(&target))
+}
+
+#[utoipa::path(
+    get,
+    path = "/teachers/{id}/oidc-identity",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    responses(
+        (status = 200, body = AppResponse<OidcIdentityView>, description = "成功"),
+    ),
+)]
+pub async fn get_teacher_oidc_identity(
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+) -> Result<Json<AppResponse<OidcIdentityView>>, (StatusCode, Json<AppResponse>)> {
+    let identity = teacher_oidc_identities::Entity::find_by_id(teacher_id)
+        .one(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_teacher_oidc_identity failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?
+        .ok_or_else(|| AppResponse::error(StatusCode::NOT_FOUND, "尚未綁定外部帳號"))?;
+
+    Ok(AppResponse::success_with_data(OidcIdentityView::from(
+        identity,
+    )))
+}
+
+/// 綁定教職員在身分提供者中的帳號，可以只填電子郵件，待第一次登入時再記錄 subject。
+#[utoipa::path(
+    put,
+    path = "/teachers/{id}/oidc-identity",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    request_body = UpsertOidcIdentityRequest,
+    responses(
+        (status = 200, body = AppResponse<OidcIdentityView>, description = "成功"),
+    ),
+)]
+pub async fn upsert_teacher_oidc_identity(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+    Json(payload): Json<UpsertOidcIdentityRequest>,
+) -> Result<Json<AppResponse<OidcIdentityView>>, (StatusCode, Json<AppResponse>)> {
+    if let Err(err) = payload.validate() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("參數不正確。錯誤參數：{}", err),
+        ));
+    }
+
+    if payload.subject.is_none() && payload.email.is_none() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "請至少填寫 subject 或電子郵件",
+        ));
+    }
+
+    let issuer = match &payload.subject {
+        Some(_) => Some(oidc_config()?.issuer.clone()),
+        None => None,
+    };
+    let email = payload.email.map(|email| email.trim().to_lowercase());
+
+    if find_teacher_by_id(&db, teacher_id).await?.is_none() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "找不到對應的教職員",
+        ));
+    }
+    // 綁定外部帳號等同於設定一組新的登入方式
+    ensure_can_manage_teacher(&db, &permissions, teacher_id).await?;
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "upsert_teacher_oidc_identity failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    if let Some(email) = &email {
+        let conflict = teacher_oidc_identities::Entity::find()
+            .filter(teacher_oidc_identities::Column::Email.eq(email))
+            .filter(teacher_oidc_identities::Column::TeacherId.ne(teacher_id))
+            .one(&txn)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "upsert_teacher_oidc_identity failed");
+                AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+            })?;
+
+        if conflict.is_some() {
+            return Err(AppResponse::error(
+                StatusCode::BAD_REQUEST,
+                "此電子郵件已綁定其他教職員",
+            ));
+        }
+    }
+
+    if let (Some(issuer), Some(subject)) = (&issuer, &payload.subject) {
+        let conflict = teacher_oidc_identities::Entity::find()
+            .filter(teacher_oidc_identities::Column::Issuer.eq(issuer))
+            .filter(teacher_oidc_identities::Column::Subject.eq(subject))
+            .filter(teacher_oidc_identities::Column::TeacherId.ne(teacher_id))
+            .one(&txn)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "upsert_teacher_oidc_identity failed");
+                AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+            })?;
+
+        if conflict.is_some() {
+            return Err(AppResponse::error(
+                StatusCode::BAD_REQUEST,
+                "此外部帳號已綁定其他教職員",
+            ));
+        }
+    }
+
+    let existing = teacher_oidc_identities::Entity::find_by_id(teacher_id)
+        .one(&txn)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "upsert_teacher_oidc_identity failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let now = Utc::now().naive_utc();
+    let identity = match existing {
+        Some(identity) => {
+            let mut identity: teacher_oidc_identities::ActiveModel = identity.into();
+            identity.issuer = Set(issuer);
+            identity.subject = Set(payload.subject);
+            identity.email = Set(email);
+            identity.updated_at = Set(now);
+            identity.update(&txn).await
+        }
+        None => {
+            let identity = teacher_oidc_identities::ActiveModel {
+                teacher_id: Set(teacher_id),
+                issuer: Set(issuer),
+                subject: Set(payload.subject),
+                email: Set(email),
+                created_at: Set(now),
+                updated_at: Set(now),
+            };
+            identity.insert(&txn).await
+        }
+    }
+    .map_err(|e| {
+        error!(error = %e, "upsert_teacher_oidc_identity failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
+    })?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "upsert_teacher_oidc_identity failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    info!(actor = %claims.sub, teacher_id = %teacher_id, "oidc identity linked");
+
+    Ok(AppResponse::success_with_data(OidcIdentityView::from(
+        identity,
+    )))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/teachers/{id}/oidc-identity",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn delete_teacher_oidc_identity(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let result = teacher_oidc_identities::Entity::delete_by_id(teacher_id)
+        .exec(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "delete_teacher_oidc_identity failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "刪除失敗")
+        })?;
+
+    if result.rows_affected == 0 {
+        return Err(AppResponse::error(
+            StatusCode::NOT_FOUND,
+            "尚未綁定外部帳號",
+        ));
+    }
+
+    info!(actor = %claims.sub, teacher_id = %teacher_id, "oidc identity unlinked");
+
+    Ok(AppResponse::success("已解除綁定"))
+}
+
+async fn handle_callback(
+    db: &DatabaseConnection,
+    cookies: &Cookies,
+    client: ClientInfo,
+    config: &OidcConfig,
+    flow_cookie: Option<String>,
+    query: OidcCallbackQuery,
+) -> Result<OidcLoginOutcome, OidcLoginError> {
+    if let Some(error) = query.error {
+        info!(error = %error, "oidc provider returned an error");
+        return Err(OidcLoginError::Cancelled);
+    }
+
+    let flow = flow_cookie
+        .and_then(|token| decode_oidc_flow_token(&token).ok())
+        .ok_or(OidcLoginError::InvalidState)?
+        .claims;
+
+    let (Some(code), Some(state)) = (query.code, query.state) else {
+        return Err(OidcLoginError::InvalidState);
+    };
+
+    if state != flow.state {
+        return Err(OidcLoginError::InvalidState);
+    }
+
+    let identity = exchange_oidc_code(config, &code, &flow.code_verifier, &flow.nonce)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "handle_callback failed");
+            OidcLoginError::ProviderError
+        })?;
+
+    let Some(teacher_id) = find_linked_teacher(db, config, &identity).await? else {
+        warn!(
+            subject = %identity.sub,
+            email = ?identity.email,
+            "oidc identity is not linked to a teacher"
+        );
+        record_login_event(
+            db,
+            None,
+            Some(identity.email.unwrap_or(identity.sub)),
+            LoginEventType::Failure,
+            &client,
+        )
+        .await;
+        return Err(OidcLoginError::NotLinked);
+    };
+
+    let teacher = find_teacher_by_id(db, teacher_id)
+        .await
+        .map_err(|_| OidcLoginError::ServerError)?
+        .ok_or(OidcLoginError::NotLinked)?;
+
+    check_account_lock(db, &client, &teacher)
+        .await
+        .map_err(|_| OidcLoginError::Locked)?;
+
+    // 啟用兩步驟驗證的教職員，即使透過身分提供者登入仍需輸入驗證碼
+    if teacher.totp_enabled_at.is_some() {
+        let challenge = create_two_factor_challenge(teacher.member_id)
+            .map_err(|_| OidcLoginError::ServerError)?;
+        cookies.add(two_factor_challenge_cookie(challenge));
+        return Ok(OidcLoginOutcome::TwoFactorRequired);
+    }
+
+    complete_login(db, cookies, client, teacher)
+        .await
+        .map(|_| OidcLoginOutcome::LoggedIn)
+        .map_err(|_| OidcLoginError::ServerError)
+}
+
+/// 先以 issuer 與 subject 對應；找不到時，若允許則以已驗證的電子郵件對應尚未綁定 subject 的教職員，
+/// 並記錄 subject 供之後登入使用。
+async fn find_linked_teacher(
+    db: &DatabaseConnection,
+    config: &OidcConfig,
+    identity: &OidcIdentity,
+) -> Result<Option<Uuid>, OidcLoginError> {
+    let linked = teacher_oidc_identities::Entity::find()
+        .filter(teacher_oidc_identities::Column::Issuer.eq(&config.issuer))
+        .filter(teacher_oidc_identities::Column::Subject.eq(&identity.sub))
+        .one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "find_linked_teacher failed");
+            OidcLoginError::ServerError
+        })?;
+
+    if let Some(linked) = linked {
+        return Ok(Some(linked.teacher_id));
+    }
+
+    let email = match &identity.email {
+        Some(email) if config.match_email && identity.email_verified => email.trim().to_lowercase(),
+        _ => return Ok(None),
+    };
+
+    let pending = teacher_oidc_identities::Entity::find()
+        .filter(teacher_oidc_identities::Column::Email.eq(email))
+        .filter(teacher_oidc_identities::Column::Subject.is_null())
+        .one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "find_linked_teacher failed");
+            OidcLoginError::ServerError
+        })?;
+
+    let Some(pending) = pending else {
+        return Ok(None);
+    };
+
+    let teacher_id = pending.teacher_id;
+    let mut pending: teacher_oidc_identities::ActiveModel = pending.into();
+    pending.issuer = Set(Some(config.issuer.clone()));
+    pending.subject = Set(Some(identity.sub.clone()));
+    pending.updated_at = Set(Utc::now().naive_utc());
+
+    pending.update(db).await.map_err(|e| {
+        error!(error = %e, "find_linked_teacher failed");
+        OidcLoginError::ServerError
+    })?;
+
+    info!(subject = %identity.sub, teacher_id = %teacher_id, "oidc subject bound by email");
+
+    Ok(Some(teacher_id))
+}
+
+fn oidc_config() -> Result<&'static OidcConfig, (StatusCode, Json<AppResponse>)> {
+    CONFIG
+        .auth
+        .oidc
+        .as_ref()
+        .ok_or_else(|| AppResponse::error(StatusCode::NOT_FOUND, "尚未啟用 OpenID Connect 登入"))
+}
+
+fn login_page_with(config: &OidcConfig, key: &str, value: &str) -> String {
+    match Url::parse(&config.login_page) {
+        Ok(mut url) => {
+            url.query_pairs_mut().append_pair(key, value);
+            url.into()
+        }
+        Err(_) => config.login_page.clone(),
+    }
+}
+
+/// 以頁面重新導向，而不是 302。
+///
+/// 從身分提供者一路 302 回前端時，瀏覽器會把整個導覽視為跨站，
+/// 前端第一次載入時收不到 SameSite=Strict 的 `auth_token`；由本站頁面發起的導覽則屬於同站。
+fn redirect_page(target: &str) -> Html<String> {
+    let target = target
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+
+    Html(format!(
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><meta http-equiv="refresh" content="0;url={0}"></head><body><a href="{0}">繼續</a></body></html>"#,
+        target
+    ))
+}
@@ -0,0 +1,314 @@
+use crate::config::CONFIG;
+use crate::db::entities::{password_reset_codes, teachers};
+use crate::models::{
+    AppResponse, ChangePasswordRequest, PasswordResetCodeView, PermissionSet,
+    RedeemPasswordResetRequest,
+};
+use crate::services::auth_service::issue_access_token;
+use crate::services::role_service::ensure_can_manage_teacher;
+use crate::services::session_service::{revoke_other_sessions, revoke_sessions_by_teacher};
+use crate::services::teacher_service::find_teacher_by_id;
+use crate::util::{
+    check_password_strength, clear_ip_failures, generate_reset_code, hash_password, hash_token,
+    ip_retry_after, normalize_reset_code, record_ip_failure, verify_password, Claims, ClientInfo,
+};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::{TimeZone, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait,
+};
+use tower_cookies::Cookies;
+use tracing::{error, info};
+use uuid::Uuid;
+use validator::Validate;
+
+#[utoipa::path(
+    put,
+    path = "/me/password",
+    tag = "me",
+    security(("auth_token" = [])),
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn change_password(
+    cookies: Cookies,
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if let Err(err) = payload.validate() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("參數不正確。錯誤參數：{}", err),
+        ));
+    }
+
+    let teacher = find_teacher_by_id(&db, claims.sub)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::UNAUTHORIZED, "請檢查登入是否成功"))?;
+
+    let is_valid = verify_password(&payload.current_password, &teacher.password).map_err(|e| {
+        error!(error = %e, "change_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    if !is_valid {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "目前的密碼不正確",
+        ));
+    }
+
+    ensure_password_strength(&payload.new_password, &[&teacher.username])?;
+
+    let password_hash = hash_password(&payload.new_password).map_err(|e| {
+        error!(error = %e, "change_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "change_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let mut teacher: teachers::ActiveModel = teacher.into();
+    teacher.password = Set(password_hash);
+    teacher.must_change_password = Set(false);
+    teacher.updated_at = Set(Utc::now().naive_utc());
+
+    let teacher = teacher.update(&txn).await.map_err(|e| {
+        error!(error = %e, "change_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
+    })?;
+
+    // 其他裝置上的登入階段可能是密碼外洩的來源，一併登出
+    revoke_other_sessions(&txn, claims.sub, claims.jti).await?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "change_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    // 重新發出 access token，解除「必須變更密碼」的限制
+    issue_access_token(&db, &cookies, &teacher, claims.jti).await?;
+
+    Ok(AppResponse::success("密碼已更新"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/teachers/{id}/password-reset-code",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    responses(
+        (status = 200, body = AppResponse<PasswordResetCodeView>, description = "成功"),
+    ),
+)]
+pub async fn issue_password_reset_code(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+) -> Result<Json<AppResponse<PasswordResetCodeView>>, (StatusCode, Json<AppResponse>)> {
+    if find_teacher_by_id(&db, teacher_id).await?.is_none() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "找不到對應的教職員",
+        ));
+    }
+    ensure_can_manage_teacher(&db, &permissions, teacher_id).await?;
+
+    let code = generate_reset_code();
+    let now = Utc::now().naive_utc();
+    let expires_at = now + CONFIG.auth.password_reset_code_lifetime();
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "issue_password_reset_code failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    // 同一時間只保留最新發出的一組重設碼
+    password_reset_codes::Entity::delete_many()
+        .filter(password_reset_codes::Column::TeacherId.eq(teacher_id))
+        .filter(password_reset_codes::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "issue_password_reset_code failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let new_code = password_reset_codes::ActiveModel {
+        teacher_id: Set(teacher_id),
+        issued_by: Set(claims.sub),
+        code_hash: Set(hash_token(&normalize_reset_code(&code))),
+        created_at: Set(now),
+        expires_at: Set(expires_at),
+        ..Default::default()
+    };
+
+    new_code.insert(&txn).await.map_err(|e| {
+        error!(error = %e, "issue_password_reset_code failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "issue_password_reset_code failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    info!(actor = %claims.sub, teacher_id = %teacher_id, "password reset code issued");
+
+    Ok(AppResponse::success_with_data(PasswordResetCodeView {
+        code,
+        expires_at: Utc.from_utc_datetime(&expires_at).into(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/password-reset",
+    tag = "auth",
+    request_body = RedeemPasswordResetRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+        (status = 429, body = AppResponse, description = "失敗次數過多，暫時無法登入"),
+    ),
+)]
+pub async fn redeem_password_reset_code(
+    client: ClientInfo,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<RedeemPasswordResetRequest>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if let Err(err) = payload.validate() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("參數不正確。錯誤參數：{}", err),
+        ));
+    }
+
+    if let Some(retry_after) = client.ip_address.as_deref().and_then(ip_retry_after) {
+        return Err(AppResponse::error(
+            StatusCode::TOO_MANY_REQUESTS,
+            format!(
+                "嘗試次數過多，請於 {} 分鐘後再試",
+                retry_after.as_secs().div_ceil(60)
+            ),
+        ));
+    }
+
+    let teacher = teachers::Entity::find()
+        .filter(teachers::Column::Username.eq(&payload.username))
+        .filter(teachers::Column::DeletedAt.is_null())
+        .one(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "redeem_password_reset_code failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let reset_code = match &teacher {
+        Some(teacher) => password_reset_codes::Entity::find()
+            .filter(password_reset_codes::Column::TeacherId.eq(teacher.member_id))
+            .filter(
+                password_reset_codes::Column::CodeHash
+                    .eq(hash_token(&normalize_reset_code(&payload.code))),
+            )
+            .filter(password_reset_codes::Column::UsedAt.is_null())
+            .filter(password_reset_codes::Column::ExpiresAt.gt(Utc::now().naive_utc()))
+            .one(&db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "redeem_password_reset_code failed");
+                AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+            })?,
+        None => None,
+    };
+
+    let (Some(teacher), Some(reset_code)) = (teacher, reset_code) else {
+        if let Some(ip) = client.ip_address.as_deref() {
+            record_ip_failure(ip, &payload.username);
+        }
+
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "重設碼無效或已過期",
+        ));
+    };
+
+    ensure_password_strength(&payload.new_password, &[&teacher.username])?;
+
+    let password_hash = hash_password(&payload.new_password).map_err(|e| {
+        error!(error = %e, "redeem_password_reset_code failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "redeem_password_reset_code failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    // 以條件更新標記為已使用，確保同一組重設碼只能兌換一次
+    let result = password_reset_codes::Entity::update_many()
+        .col_expr(
+            password_reset_codes::Column::UsedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(password_reset_codes::Column::Id.eq(reset_code.id))
+        .filter(password_reset_codes::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "redeem_password_reset_code failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    if result.rows_affected == 0 {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "重設碼無效或已過期",
+        ));
+    }
+
+    let teacher_id = teacher.member_id;
+    let username = teacher.username.clone();
+    let mut teacher: teachers::ActiveModel = teacher.into();
+    teacher.password = Set(password_hash);
+    teacher.must_change_password = Set(false);
+    teacher.failed_login_attempts = Set(0);
+    teacher.locked_until = Set(None);
+    teacher.updated_at = Set(Utc::now().naive_utc());
+
+    teacher.update(&txn).await.map_err(|e| {
+        error!(error = %e, "redeem_password_reset_code failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
+    })?;
+
+    revoke_sessions_by_teacher(&txn, teacher_id).await?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "redeem_password_reset_code failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    if let Some(ip) = client.ip_address.as_deref() {
+        clear_ip_failures(ip, &username);
+    }
+
+    Ok(AppResponse::success("密碼已重設，請使用新密碼登入"))
+}
+
+/// 依密碼政策檢查新密碼，不符合時回傳 400。
+pub(crate) fn ensure_password_strength(
+    password: &str,
+    user_inputs: &[&str],
+) -> Result<(), (StatusCode, Json<AppResponse>)> {
+    check_password_strength(password, user_inputs)
+        .map_err(|message| AppResponse::error(StatusCode::BAD_REQUEST, message))
+}
@@ -1,7 +1,19 @@
 pub use super::announcement_service::*;
+pub use super::api_token_service::*;
+pub use super::assignment_service::*;
 pub use super::attendance_service::*;
 pub use super::auth_service::*;
+pub use super::guardian_service::*;
+pub use super::health_service::*;
+pub use super::login_event_service::*;
 pub use super::member_service::*;
+pub use super::metrics_service::*;
+pub use super::oidc_service::*;
+pub use super::password_service::*;
+pub use super::role_service::*;
+pub use super::session_service::*;
+pub use super::setting_service::*;
+pub use super::student_info_service::*;
 pub use super::student_service::*;
 pub use super::teacher_service::*;
-pub use super::student_info_service::*;
\ No newline at end of file
+pub use super::two_factor_service::*;
@@ -0,0 +1,465 @@
+use crate::db::entities::{role_permissions, roles, teachers};
+use crate::models::{
+    role_to_view, AppResponse, AssignRoleRequest, Permission, PermissionSet, PermissionView,
+    RoleView, UpsertRoleRequest, SUPER_ADMIN_ROLE,
+};
+use crate::services::teacher_service::find_teacher_by_id;
+use crate::util::Claims;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::Utc;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, JoinType,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, TransactionTrait,
+};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+#[utoipa::path(
+    get,
+    path = "/permissions",
+    tag = "roles",
+    responses(
+        (status = 200, body = AppResponse<Vec<PermissionView>>, description = "成功"),
+    ),
+)]
+pub async fn get_permissions(
+) -> Result<Json<AppResponse<Vec<PermissionView>>>, (StatusCode, Json<AppResponse>)> {
+    let result = Permission::ALL
+        .into_iter()
+        .map(|permission| PermissionView {
+            name: permission,
+            description: permission.description(),
+        })
+        .collect();
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/roles",
+    tag = "roles",
+    responses(
+        (status = 200, body = AppResponse<Vec<RoleView>>, description = "成功"),
+    ),
+)]
+pub async fn get_roles(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<AppResponse<Vec<RoleView>>>, (StatusCode, Json<AppResponse>)> {
+    let roles_with_permissions = roles::Entity::find()
+        .order_by_asc(roles::Column::CreatedAt)
+        .find_with_related(role_permissions::Entity)
+        .all(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_roles failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let result = roles_with_permissions
+        .into_iter()
+        .map(|(role, permissions)| {
+            let permissions: PermissionSet = permissions
+                .into_iter()
+                .filter_map(|permission| Permission::try_from(permission.permission).ok())
+                .collect();
+            role_to_view(role, permissions.to_vec())
+        })
+        .collect();
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/roles",
+    tag = "roles",
+    request_body = UpsertRoleRequest,
+    responses(
+        (status = 200, body = AppResponse<RoleView>, description = "成功"),
+    ),
+)]
+pub async fn add_role(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<UpsertRoleRequest>,
+) -> Result<Json<AppResponse<RoleView>>, (StatusCode, Json<AppResponse>)> {
+    if let Err(err) = payload.validate() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("參數不正確。錯誤參數：{}", err),
+        ));
+    }
+
+    if find_role_by_name(&db, &payload.name).await?.is_some() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("角色名稱 {} 已被使用", payload.name),
+        ));
+    }
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "add_role failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let new_role = roles::ActiveModel {
+        name: Set(payload.name),
+        description: Set(payload.description),
+        ..Default::default()
+    };
+
+    let role = new_role.insert(&txn).await.map_err(|e| {
+        error!(error = %e, "add_role failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let permissions: PermissionSet = payload.permissions.into_iter().collect();
+    replace_role_permissions(&txn, role.id, &permissions).await?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "add_role failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    info!(actor = %claims.sub, role = %role.name, "role created");
+
+    Ok(AppResponse::success_with_data(role_to_view(
+        role,
+        permissions.to_vec(),
+    )))
+}
+
+#[utoipa::path(
+    put,
+    path = "/roles/{id}",
+    tag = "roles",
+    params(("id" = Uuid, Path, description = "角色 id")),
+    request_body = UpsertRoleRequest,
+    responses(
+        (status = 200, body = AppResponse<RoleView>, description = "成功"),
+    ),
+)]
+pub async fn update_role(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Path(role_id): Path<Uuid>,
+    Json(payload): Json<UpsertRoleRequest>,
+) -> Result<Json<AppResponse<RoleView>>, (StatusCode, Json<AppResponse>)> {
+    if let Err(err) = payload.validate() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("參數不正確。錯誤參數：{}", err),
+        ));
+    }
+
+    let role = find_role_by_id(&db, role_id)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::BAD_REQUEST, "找不到對應的角色"))?;
+
+    if role.name == SUPER_ADMIN_ROLE {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "主管理員角色固定擁有所有權限，無法修改",
+        ));
+    }
+
+    if role.is_system && role.name != payload.name {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "系統內建角色無法更改名稱",
+        ));
+    }
+
+    if role.name != payload.name && find_role_by_name(&db, &payload.name).await?.is_some() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            format!("角色名稱 {} 已被使用", payload.name),
+        ));
+    }
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "update_role failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let mut role: roles::ActiveModel = role.into();
+    role.name = Set(payload.name);
+    role.description = Set(payload.description);
+    role.updated_at = Set(Utc::now().naive_utc());
+
+    let role = role.update(&txn).await.map_err(|e| {
+        error!(error = %e, "update_role failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
+    })?;
+
+    let permissions: PermissionSet = payload.permissions.into_iter().collect();
+    replace_role_permissions(&txn, role.id, &permissions).await?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "update_role failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    info!(actor = %claims.sub, role = %role.name, "role updated");
+
+    Ok(AppResponse::success_with_data(role_to_view(
+        role,
+        permissions.to_vec(),
+    )))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/roles/{id}",
+    tag = "roles",
+    params(("id" = Uuid, Path, description = "角色 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn delete_role(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Path(role_id): Path<Uuid>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let role = find_role_by_id(&db, role_id)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::BAD_REQUEST, "找不到對應的角色"))?;
+
+    if role.is_system {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "系統內建角色無法刪除",
+        ));
+    }
+
+    // 已刪除的教職員仍保有角色的外鍵，因此一併計入
+    let teacher_count = teachers::Entity::find()
+        .filter(teachers::Column::RoleId.eq(role.id))
+        .count(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "delete_role failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    if teacher_count > 0 {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "仍有教職員使用此角色，無法刪除",
+        ));
+    }
+
+    roles::Entity::delete_by_id(role.id)
+        .exec(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "delete_role failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "刪除失敗")
+        })?;
+
+    info!(actor = %claims.sub, role = %role.name, "role deleted");
+
+    Ok(AppResponse::success("刪除成功"))
+}
+
+#[utoipa::path(
+    put,
+    path = "/teachers/{id}/role",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    request_body = AssignRoleRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn assign_teacher_role(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+    Json(payload): Json<AssignRoleRequest>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let teacher = find_teacher_by_id(&db, teacher_id)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::BAD_REQUEST, "找不到對應的教職員"))?;
+
+    let role = find_role_by_id(&db, payload.role_id)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::BAD_REQUEST, "找不到對應的角色"))?;
+
+    let super_admin_role = find_role_by_name(&db, SUPER_ADMIN_ROLE)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常"))?;
+
+    // 避免移除最後一位主管理員後，沒有人能再管理角色
+    if teacher.role_id == super_admin_role.id && role.id != super_admin_role.id {
+        let super_admin_count = teachers::Entity::find()
+            .filter(teachers::Column::RoleId.eq(super_admin_role.id))
+            .filter(teachers::Column::DeletedAt.is_null())
+            .count(&db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "assign_teacher_role failed");
+                AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+            })?;
+
+        if super_admin_count <= 1 {
+            return Err(AppResponse::error(
+                StatusCode::BAD_REQUEST,
+                "至少需要保留一位主管理員",
+            ));
+        }
+    }
+
+    let mut teacher: teachers::ActiveModel = teacher.into();
+    teacher.role_id = Set(role.id);
+    teacher.updated_at = Set(Utc::now().naive_utc());
+
+    teacher.update(&db).await.map_err(|e| {
+        error!(error = %e, "assign_teacher_role failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
+    })?;
+
+    info!(
+        actor = %claims.sub,
+        teacher_id = %teacher_id,
+        role = %role.name,
+        "teacher role assigned"
+    );
+
+    Ok(AppResponse::success("已更新角色"))
+}
+
+/// 依教職員目前的角色載入權限，每個請求都重新讀取，讓角色異動立即生效。
+/// 目標教職員擁有操作者沒有的權限時拒絕，避免藉由重設密碼等操作接管權限較高的帳號。
+pub(crate) async fn ensure_can_manage_teacher<C>(
+    db: &C,
+    actor_permissions: &PermissionSet,
+    teacher_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let teacher_permissions = load_teacher_permissions(db, teacher_id).await?;
+    if !actor_permissions.contains_all(&teacher_permissions) {
+        return Err(AppResponse::error(
+            StatusCode::FORBIDDEN,
+            "無法管理擁有您所沒有權限的教職員",
+        ));
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn load_teacher_permissions<C>(
+    db: &C,
+    teacher_id: Uuid,
+) -> Result<PermissionSet, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let permissions = role_permissions::Entity::find()
+        .join(JoinType::InnerJoin, role_permissions::Relation::Roles.def())
+        .join(JoinType::InnerJoin, roles::Relation::Teachers.def())
+        .filter(teachers::Column::MemberId.eq(teacher_id))
+        .select_only()
+        .column(role_permissions::Column::Permission)
+        .into_tuple::<String>()
+        .all(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "load_teacher_permissions failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(permissions
+        .into_iter()
+        .filter_map(|permission| match Permission::try_from(permission) {
+            Ok(permission) => Some(permission),
+            Err(e) => {
+                warn!(error = %e, "unknown role permission ignored");
+                None
+            }
+        })
+        .collect())
+}
+
+pub(crate) async fn find_role_by_id<C>(
+    db: &C,
+    role_id: Uuid,
+) -> Result<Option<roles::Model>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    roles::Entity::find_by_id(role_id)
+        .one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "find_role_by_id failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })
+}
+
+pub(crate) async fn find_role_by_name<C>(
+    db: &C,
+    name: &str,
+) -> Result<Option<roles::Model>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    roles::Entity::find()
+        .filter(roles::Column::Name.eq(name))
+        .one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "find_role_by_name failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })
+}
+
+/// 將角色的權限整批替換成指定的權限。
+async fn replace_role_permissions<C>(
+    db: &C,
+    role_id: Uuid,
+    permissions: &PermissionSet,
+) -> Result<(), (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    role_permissions::Entity::delete_many()
+        .filter(role_permissions::Column::RoleId.eq(role_id))
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "replace_role_permissions failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let new_permissions = permissions
+        .to_vec()
+        .into_iter()
+        .map(|permission| role_permissions::ActiveModel {
+            role_id: Set(role_id),
+            permission: Set(permission.into()),
+        })
+        .collect::<Vec<_>>();
+
+    if new_permissions.is_empty() {
+        return Ok(());
+    }
+
+    role_permissions::Entity::insert_many(new_permissions)
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "replace_role_permissions failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(())
+}
@@ -0,0 +1,351 @@
+use crate::config::CONFIG;
+use crate::db::entities::{session_refresh_tokens, teacher_sessions};
+use crate::models::{session_to_view, AppResponse, SessionView};
+use crate::util::{generate_opaque_token, hash_token, Claims, ClientInfo};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
+};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+/// 多個分頁同時更新憑證時，舊的 refresh token 在此秒數內重複出現不視為遭竊用。
+const REFRESH_REUSE_GRACE_SECS: i64 = 10;
+
+#[utoipa::path(
+    get,
+    path = "/teachers/{id}/sessions",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    responses(
+        (status = 200, body = AppResponse<Vec<SessionView>>, description = "成功"),
+    ),
+)]
+pub async fn get_teacher_sessions(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+) -> Result<Json<AppResponse<Vec<SessionView>>>, (StatusCode, Json<AppResponse>)> {
+    let sessions = teacher_sessions::Entity::find()
+        .filter(teacher_sessions::Column::TeacherId.eq(teacher_id))
+        .filter(teacher_sessions::Column::RevokedAt.is_null())
+        .filter(teacher_sessions::Column::ExpiresAt.gt(Utc::now().naive_utc()))
+        .order_by_desc(teacher_sessions::Column::CreatedAt)
+        .all(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_teacher_sessions failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let result = sessions
+        .into_iter()
+        .map(|session| session_to_view(session, claims.jti))
+        .collect();
+
+    Ok(AppResponse::success_with_data(result))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/teachers/{id}/sessions/{session_id}",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id"), ("session_id" = Uuid, Path, description = "登入階段 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn revoke_teacher_session(
+    State(db): State<DatabaseConnection>,
+    Path((teacher_id, session_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let session = teacher_sessions::Entity::find_by_id(session_id)
+        .filter(teacher_sessions::Column::TeacherId.eq(teacher_id))
+        .filter(teacher_sessions::Column::RevokedAt.is_null())
+        .one(&db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "revoke_teacher_session failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?
+        .ok_or_else(|| AppResponse::error(StatusCode::NOT_FOUND, "找不到對應的登入階段"))?;
+
+    revoke_session(&db, session.id).await?;
+
+    Ok(AppResponse::success("已撤銷登入階段"))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/teachers/{id}/sessions",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn revoke_teacher_sessions(
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    revoke_sessions_by_teacher(&db, teacher_id).await?;
+
+    Ok(AppResponse::success("已撤銷所有登入階段"))
+}
+
+pub(crate) async fn create_session<C>(
+    db: &C,
+    teacher_id: Uuid,
+    client: ClientInfo,
+) -> Result<teacher_sessions::Model, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let now = Utc::now().naive_utc();
+    let new_session = teacher_sessions::ActiveModel {
+        teacher_id: Set(teacher_id),
+        ip_address: Set(client.ip_address),
+        user_agent: Set(client.user_agent),
+        created_at: Set(now),
+        expires_at: Set(now + CONFIG.auth.refresh_token_lifetime()),
+        ..Default::default()
+    };
+
+    new_session.insert(db).await.map_err(|e| {
+        error!(error = %e, "create_session failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })
+}
+
+/// 為登入階段發出新的 refresh token，並將登入階段的期限往後延長。
+pub(crate) async fn issue_refresh_token<C>(
+    db: &C,
+    session_id: Uuid,
+) -> Result<String, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let now = Utc::now().naive_utc();
+    let expires_at = now + CONFIG.auth.refresh_token_lifetime();
+    let refresh_token = generate_opaque_token();
+
+    let new_refresh_token = session_refresh_tokens::ActiveModel {
+        session_id: Set(session_id),
+        token_hash: Set(hash_token(&refresh_token)),
+        created_at: Set(now),
+        expires_at: Set(expires_at),
+        ..Default::default()
+    };
+
+    new_refresh_token.insert(db).await.map_err(|e| {
+        error!(error = %e, "issue_refresh_token failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    teacher_sessions::Entity::update_many()
+        .col_expr(teacher_sessions::Column::ExpiresAt, Expr::value(expires_at))
+        .filter(teacher_sessions::Column::Id.eq(session_id))
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "issue_refresh_token failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+        })?;
+
+    Ok(refresh_token)
+}
+
+/// 以舊的 refresh token 換發新的 refresh token。
+///
+/// 已使用過的 refresh token 再次出現代表可能遭竊用，會直接撤銷整個登入階段。
+pub(crate) async fn rotate_refresh_token(
+    db: &DatabaseConnection,
+    refresh_token: &str,
+) -> Result<(teacher_sessions::Model, String), (StatusCode, Json<AppResponse>)> {
+    let now = Utc::now().naive_utc();
+
+    let record = session_refresh_tokens::Entity::find()
+        .filter(session_refresh_tokens::Column::TokenHash.eq(hash_token(refresh_token)))
+        .one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "rotate_refresh_token failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?
+        .ok_or_else(|| AppResponse::error(StatusCode::UNAUTHORIZED, "請重新登入"))?;
+
+    if let Some(used_at) = record.used_at {
+        if (now - used_at).num_seconds() <= REFRESH_REUSE_GRACE_SECS {
+            return Err(AppResponse::error(
+                StatusCode::CONFLICT,
+                "登入憑證已更新，請重新發送請求",
+            ));
+        }
+
+        warn!(
+            session_id = %record.session_id,
+            "refresh token reused, revoking session"
+        );
+        revoke_session(db, record.session_id).await?;
+
+        return Err(AppResponse::error(StatusCode::UNAUTHORIZED, "請重新登入"));
+    }
+
+    if record.expires_at <= now {
+        return Err(AppResponse::error(StatusCode::UNAUTHORIZED, "請重新登入"));
+    }
+
+    let session = find_active_session(db, record.session_id)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::UNAUTHORIZED, "請重新登入"))?;
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "rotate_refresh_token failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+    })?;
+
+    // 以條件更新標記為已使用，避免兩個請求同時換發成功
+    let result = session_refresh_tokens::Entity::update_many()
+        .col_expr(session_refresh_tokens::Column::UsedAt, Expr::value(now))
+        .filter(session_refresh_tokens::Column::Id.eq(record.id))
+        .filter(session_refresh_tokens::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "rotate_refresh_token failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    if result.rows_affected == 0 {
+        return Err(AppResponse::error(
+            StatusCode::CONFLICT,
+            "登入憑證已更新，請重新發送請求",
+        ));
+    }
+
+    let new_refresh_token = issue_refresh_token(&txn, session.id).await?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "rotate_refresh_token failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+    })?;
+
+    Ok((session, new_refresh_token))
+}
+
+/// 依 refresh token 找出所屬的登入階段，不論 token 是否已使用或過期，登出時用來撤銷。
+pub(crate) async fn find_session_by_refresh_token(
+    db: &DatabaseConnection,
+    refresh_token: &str,
+) -> Result<Option<teacher_sessions::Model>, (StatusCode, Json<AppResponse>)> {
+    session_refresh_tokens::Entity::find()
+        .filter(session_refresh_tokens::Column::TokenHash.eq(hash_token(refresh_token)))
+        .find_also_related(teacher_sessions::Entity)
+        .one(db)
+        .await
+        .map(|record| record.and_then(|(_, session)| session))
+        .map_err(|e| {
+            error!(error = %e, "find_session_by_refresh_token failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })
+}
+
+/// 取得尚未撤銷且未過期的登入階段。
+pub(crate) async fn find_active_session<C>(
+    db: &C,
+    session_id: Uuid,
+) -> Result<Option<teacher_sessions::Model>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    teacher_sessions::Entity::find_by_id(session_id)
+        .filter(teacher_sessions::Column::RevokedAt.is_null())
+        .filter(teacher_sessions::Column::ExpiresAt.gt(Utc::now().naive_utc()))
+        .one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "find_active_session failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })
+}
+
+pub(crate) async fn revoke_session<C>(
+    db: &C,
+    session_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    teacher_sessions::Entity::update_many()
+        .col_expr(
+            teacher_sessions::Column::RevokedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(teacher_sessions::Column::Id.eq(session_id))
+        .filter(teacher_sessions::Column::RevokedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "revoke_session failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(())
+}
+
+pub(crate) async fn revoke_sessions_by_teacher<C>(
+    db: &C,
+    teacher_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    teacher_sessions::Entity::update_many()
+        .col_expr(
+            teacher_sessions::Column::RevokedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(teacher_sessions::Column::TeacherId.eq(teacher_id))
+        .filter(teacher_sessions::Column::RevokedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "revoke_sessions_by_teacher failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(())
+}
+
+/// 撤銷同一位教職員除了目前這個以外的所有登入階段，例如變更密碼之後。
+pub(crate) async fn revoke_other_sessions<C>(
+    db: &C,
+    teacher_id: Uuid,
+    current_session_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    teacher_sessions::Entity::update_many()
+        .col_expr(
+            teacher_sessions::Column::RevokedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(teacher_sessions::Column::TeacherId.eq(teacher_id))
+        .filter(teacher_sessions::Column::Id.ne(current_session_id))
+        .filter(teacher_sessions::Column::RevokedAt.is_null())
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "revoke_other_sessions failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use crate::db::entities::system_settings;
+use crate::models::{AppResponse, SecuritySettings};
+use crate::util::Claims;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::Utc;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait};
+use tracing::{error, info};
+
+const REQUIRE_TWO_FACTOR_KEY: &str = "require_two_factor";
+const RESTRICT_STUDENT_ACCESS_KEY: &str = "restrict_student_access";
+
+#[utoipa::path(
+    get,
+    path = "/settings/security",
+    tag = "settings",
+    responses(
+        (status = 200, body = AppResponse<SecuritySettings>, description = "成功"),
+    ),
+)]
+pub async fn get_security_settings(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<AppResponse<SecuritySettings>>, (StatusCode, Json<AppResponse>)> {
+    Ok(AppResponse::success_with_data(SecuritySettings {
+        require_two_factor: is_two_factor_required(&db).await?,
+        restrict_student_access: is_student_access_restricted(&db).await?,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/settings/security",
+    tag = "settings",
+    request_body = SecuritySettings,
+    responses(
+        (status = 200, body = AppResponse<SecuritySettings>, description = "成功"),
+    ),
+)]
+pub async fn update_security_settings(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<SecuritySettings>,
+) -> Result<Json<AppResponse<SecuritySettings>>, (StatusCode, Json<AppResponse>)> {
+    set_setting(
+        &db,
+        REQUIRE_TWO_FACTOR_KEY,
+        payload.require_two_factor.to_string(),
+    )
+    .await?;
+    set_setting(
+        &db,
+        RESTRICT_STUDENT_ACCESS_KEY,
+        payload.restrict_student_access.to_string(),
+    )
+    .await?;
+
+    info!(
+        actor = %claims.sub,
+        require_two_factor = payload.require_two_factor,
+        restrict_student_access = payload.restrict_student_access,
+        "security settings updated"
+    );
+
+    Ok(AppResponse::success_with_data(payload))
+}
+
+/// 是否要求所有教職員都必須啟用兩步驟驗證，未設定時視為不要求。
+pub(crate) async fn is_two_factor_required<C>(
+    db: &C,
+) -> Result<bool, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    Ok(get_setting(db, REQUIRE_TWO_FACTOR_KEY)
+        .await?
+        .is_some_and(|value| value == "true"))
+}
+
+/// 是否限制教職員只能存取指派給自己的學生，未設定時視為不限制。
+pub(crate) async fn is_student_access_restricted<C>(
+    db: &C,
+) -> Result<bool, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    Ok(get_setting(db, RESTRICT_STUDENT_ACCESS_KEY)
+        .await?
+        .is_some_and(|value| value == "true"))
+}
+
+async fn get_setting<C>(
+    db: &C,
+    key: &str,
+) -> Result<Option<String>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let setting = system_settings::Entity::find_by_id(key)
+        .one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "get_setting failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(setting.map(|setting| setting.value))
+}
+
+async fn set_setting<C>(
+    db: &C,
+    key: &str,
+    value: String,
+) -> Result<(), (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let setting = system_settings::ActiveModel {
+        key: Set(key.to_string()),
+        value: Set(value),
+        updated_at: Set(Utc::now().naive_utc()),
+    };
+
+    system_settings::Entity::insert(setting)
+        .on_conflict(
+            OnConflict::column(system_settings::Column::Key)
+                .update_columns([
+                    system_settings::Column::Value,
+                    system_settings::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "set_setting failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(())
+}
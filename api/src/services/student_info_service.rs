@@ -1,20 +1,40 @@
 use crate::db::entities::{student_exams, student_infos};
 use crate::models::{
-    AppResponse, StudentExamDto, StudentInfoDto, StudentInfoView, UpsertStudentInfoRequest,
+    student_info_to_view, AppResponse, PermissionSet, StudentInfoDto, StudentInfoView,
+    StudentScope, UpsertStudentInfoRequest,
 };
+use crate::services::assignment_service::{ensure_student_in_scope, find_student_scope};
 use crate::services::member_service::{find_member_by_id, get_members_name_hashmap};
+use crate::util::Claims;
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
-use axum::Json;
+use axum::{Extension, Json};
 use sea_orm::{ActiveModelTrait, QueryFilter, Set, TransactionTrait};
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait};
 use uuid::Uuid;
 use validator::Validate;
 
+#[utoipa::path(
+    get,
+    path = "/student_infos",
+    tag = "student-infos",
+    responses(
+        (status = 200, body = AppResponse<Vec<StudentInfoView>>, description = "成功"),
+    ),
+)]
 pub async fn get_student_infos(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
 ) -> Result<Json<AppResponse<Vec<StudentInfoView>>>, (StatusCode, Json<AppResponse>)> {
-    let infos_with_exams = student_infos::Entity::find()
+    let mut query = student_infos::Entity::find();
+    if let StudentScope::Assigned(student_ids) =
+        find_student_scope(&db, claims.sub, &permissions).await?
+    {
+        query = query.filter(student_infos::Column::StudentId.is_in(student_ids));
+    }
+
+    let infos_with_exams = query
         .find_with_related(student_exams::Entity)
         .all(&db)
         .await
@@ -30,49 +50,36 @@ pub async fn get_student_infos(
     let result: Vec<StudentInfoView> = infos_with_exams
         .into_iter()
         .map(|(info, exams)| {
-            let info_dto = StudentInfoDto {
-                academic_year: info.academic_year,
-                chinese_book: info.chinese_book,
-                english_book: info.english_book,
-                math_book: info.math_book,
-                science_book: info.science_book,
-                social_studies_book: info.social_studies_book,
-                comment: info.comment,
-            };
-
-            let exams_dto = exams
-                .into_iter()
-                .map(|exam| StudentExamDto {
-                    semester: exam.semester,
-                    exam_type: exam.exam_type,
-                    chinese_score: exam.chinese_score,
-                    english_score: exam.english_score,
-                    math_score: exam.math_score,
-                    science_score: exam.science_score,
-                    social_studies_score: exam.social_studies_score,
-                })
-                .collect();
-
-            StudentInfoView {
-                id: info.id,
-                name: member_name_map
-                    .get(&info.student_id)
-                    .cloned()
-                    .unwrap_or_else(|| "未知姓名".to_string()),
-                info_dto,
-                exams_dto,
-            }
+            let name = member_name_map
+                .get(&info.student_id)
+                .cloned()
+                .unwrap_or_else(|| "未知姓名".to_string());
+            student_info_to_view(info, exams, name)
         })
         .collect();
 
     Ok(AppResponse::success_with_data(result))
 }
 
+#[utoipa::path(
+    post,
+    path = "/student_infos",
+    tag = "student-infos",
+    request_body = UpsertStudentInfoRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn add_student_infos(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpsertStudentInfoRequest>,
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let scope = find_student_scope(&db, claims.sub, &permissions).await?;
+    ensure_student_in_scope(&scope, id)?;
+
     if let Err(err) = payload.validate() {
         return Err(AppResponse::error(
             StatusCode::BAD_REQUEST,
@@ -131,11 +138,26 @@ pub async fn add_student_infos(
     Ok(AppResponse::success("新增成功"))
 }
 
+#[utoipa::path(
+    put,
+    path = "/student_infos/{id}",
+    tag = "student-infos",
+    params(("id" = Uuid, Path, description = "學生資料 id")),
+    request_body = UpsertStudentInfoRequest,
+    responses(
+        (status = 200, body = AppResponse<StudentInfoView>, description = "成功"),
+    ),
+)]
 pub async fn update_student_infos(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpsertStudentInfoRequest>,
 ) -> Result<Json<AppResponse<StudentInfoView>>, (StatusCode, Json<AppResponse>)> {
+    let scope = find_student_scope(&db, claims.sub, &permissions).await?;
+    ensure_student_in_scope(&scope, id)?;
+
     if let Err(err) = payload.validate() {
         return Err(AppResponse::error(
             StatusCode::BAD_REQUEST,
@@ -179,7 +201,7 @@ pub async fn update_student_infos(
         .map_err(|e| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     // 查找並更新現有的 student_exams（假設固定為兩筆）
-    for (_, exam_dto) in student_exams_dto.iter().enumerate() {
+    for exam_dto in student_exams_dto.iter() {
         let existing_exam = student_exams::Entity::find()
             .filter(student_exams::Column::StudentInfosId.eq(updated_info.id))
             .filter(student_exams::Column::Semester.eq(exam_dto.semester))
@@ -216,7 +238,6 @@ pub async fn update_student_infos(
                     social_studies_score: Set(exam_dto.social_studies_score),
                     created_at: Set(chrono::Utc::now().naive_utc()),
                     updated_at: Set(chrono::Utc::now().naive_utc()),
-                    ..Default::default()
                 };
 
                 student_exam.insert(&txn).await.map_err(|e| {
@@ -254,10 +275,24 @@ pub async fn update_student_infos(
     }))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/student_infos/{id}",
+    tag = "student-infos",
+    params(("id" = Uuid, Path, description = "學生資料 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn delete_student_infos(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let scope = find_student_scope(&db, claims.sub, &permissions).await?;
+    ensure_student_in_scope(&scope, id)?;
+
     let txn = db
         .begin()
         .await
@@ -1,46 +1,61 @@
 use crate::db::entities::{members, students};
 use crate::models::{
-    student_and_member_to_view, AddStudentRequest, AppResponse, StudentView, UpdateStudentRequest,
+    student_and_member_to_view, AddStudentRequest, AppResponse, Permission, PermissionSet,
+    StudentScope, StudentView, UpdateStudentRequest,
 };
-use crate::services::member_service::upsert_member_with_context;
+use crate::services::assignment_service::{
+    ensure_student_in_scope, find_student_scope, insert_assignment,
+};
+use crate::services::member_service::{find_member_by_id, upsert_member_with_context};
+use crate::util::Claims;
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
-use axum::Json;
+use axum::{Extension, Json};
 use chrono::Utc;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    TransactionTrait,
 };
 use uuid::Uuid;
 use validator::Validate;
 
+#[utoipa::path(
+    get,
+    path = "/students",
+    tag = "students",
+    responses(
+        (status = 200, body = AppResponse<Vec<StudentView>>, description = "成功"),
+    ),
+)]
 pub async fn get_students(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
 ) -> Result<Json<AppResponse<Vec<StudentView>>>, (StatusCode, Json<AppResponse>)> {
-    let students_with_members = students::Entity::find()
-        .filter(students::Column::DeletedAt.is_null())
-        .find_with_related(members::Entity)
-        .all(&db)
-        .await
-        .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常"))?;
-
-    let mut result = vec![];
-    for (student, mut members) in students_with_members {
-        if let Some(member) = members.pop() {
-            let student_view = student_and_member_to_view(student, member);
-            result.push(student_view);
-        } else {
-            return Err(AppResponse::error(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Student without member",
-            ));
-        }
-    }
+    let scope = find_student_scope(&db, claims.sub, &permissions).await?;
+    let result = find_student_views(
+        &db,
+        &scope,
+        permissions.contains(Permission::StudentsReadPii),
+    )
+    .await?;
 
     Ok(AppResponse::success_with_data(result))
 }
 
+#[utoipa::path(
+    post,
+    path = "/students",
+    tag = "students",
+    request_body = AddStudentRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn add_student(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Json(payload): Json<AddStudentRequest>,
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
@@ -51,7 +66,8 @@ pub async fn add_student(
         ));
     }
 
-    let member_id = payload.member_id.unwrap_or_else(|| Uuid::nil());
+    let member_id = payload.member_id.unwrap_or_else(Uuid::nil);
+    let scope = find_student_scope(&db, claims.sub, &permissions).await?;
 
     if find_student_by_id(&db, member_id).await?.is_some() {
         return Err(AppResponse::error(
@@ -89,6 +105,11 @@ pub async fn add_student(
         .await
         .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常"))?;
 
+    // 受指派限制的教職員新增的學生自動指派給自己，否則新增後就無法再存取
+    if scope.is_restricted() {
+        insert_assignment(&txn, claims.sub, member.id).await?;
+    }
+
     txn.commit()
         .await
         .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常"))?;
@@ -96,10 +117,22 @@ pub async fn add_student(
     Ok(AppResponse::success("新增成功"))
 }
 
+#[utoipa::path(
+    put,
+    path = "/students/{id}",
+    tag = "students",
+    params(("id" = Uuid, Path, description = "學生的成員 id")),
+    request_body = UpdateStudentRequest,
+    responses(
+        (status = 200, body = AppResponse<StudentView>, description = "成功"),
+    ),
+)]
 pub async fn update_student(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateStudentRequest>,
+    Json(mut payload): Json<UpdateStudentRequest>,
 ) -> Result<Json<AppResponse<StudentView>>, (StatusCode, Json<AppResponse>)> {
     if let Err(err) = payload.validate() {
         return Err(AppResponse::error(
@@ -108,6 +141,20 @@ pub async fn update_student(
         ));
     }
 
+    let scope = find_student_scope(&db, claims.sub, &permissions).await?;
+    ensure_student_in_scope(&scope, id)?;
+
+    // 看不到個資的人送回來的欄位必定是空的，保留原本的資料
+    let can_read_pii = permissions.contains(Permission::StudentsReadPii);
+    if !can_read_pii {
+        if let Some(member) = find_member_by_id(&db, id).await? {
+            payload.member_dto.keep_pii(&member);
+        }
+        if let Some(student) = find_student_by_id(&db, id).await? {
+            payload.student_dto.keep_pii(&student);
+        }
+    }
+
     let txn = db
         .begin()
         .await
@@ -150,15 +197,32 @@ pub async fn update_student(
         .await
         .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常"))?;
 
-    let student_view = student_and_member_to_view(student, member);
+    let mut student_view = student_and_member_to_view(student, member);
+    if !can_read_pii {
+        student_view.redact_pii();
+    }
 
     Ok(AppResponse::success_with_data(student_view))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/students/{id}",
+    tag = "students",
+    params(("id" = Uuid, Path, description = "學生的成員 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn delete_student(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let scope = find_student_scope(&db, claims.sub, &permissions).await?;
+    ensure_student_in_scope(&scope, id)?;
+
     if let Some(student) = find_student_by_id(&db, id).await? {
         let mut student: students::ActiveModel = student.into();
         student.updated_at = Set(Utc::now().naive_utc());
@@ -178,10 +242,52 @@ pub async fn delete_student(
     }
 }
 
-async fn find_student_by_id(
-    db: &DatabaseConnection,
+/// 列出範圍內的學生，沒有個資權限時一併清除個人資料。
+pub(crate) async fn find_student_views<C>(
+    db: &C,
+    scope: &StudentScope,
+    can_read_pii: bool,
+) -> Result<Vec<StudentView>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let mut query = students::Entity::find().filter(students::Column::DeletedAt.is_null());
+    if let StudentScope::Assigned(student_ids) = scope {
+        query = query.filter(students::Column::MemberId.is_in(student_ids.iter().copied()));
+    }
+
+    let students_with_members = query
+        .find_with_related(members::Entity)
+        .all(db)
+        .await
+        .map_err(|_| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常"))?;
+
+    let mut result = vec![];
+    for (student, mut members) in students_with_members {
+        if let Some(member) = members.pop() {
+            let mut student_view = student_and_member_to_view(student, member);
+            if !can_read_pii {
+                student_view.redact_pii();
+            }
+            result.push(student_view);
+        } else {
+            return Err(AppResponse::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Student without member",
+            ));
+        }
+    }
+
+    Ok(result)
+}
+
+pub(crate) async fn find_student_by_id<C>(
+    db: &C,
     id: Uuid,
-) -> Result<Option<students::Model>, (StatusCode, Json<AppResponse>)> {
+) -> Result<Option<students::Model>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
     students::Entity::find()
         .filter(students::Column::MemberId.eq(id))
         .filter(students::Column::DeletedAt.is_null())
@@ -1,23 +1,31 @@
 use crate::db::entities::{members, teachers};
 use crate::models::{
-    teacher_and_member_to_view, AddTeacherRequest, AppResponse, EmploymentType, MemberDto,
-    RoleType, TeacherView, UpdateTeacherRequest,
+    teacher_and_member_to_view, AddTeacherRequest, AppResponse, EmploymentType, Permission,
+    PermissionSet, TeacherView, UpdateTeacherRequest, DEFAULT_ROLE,
 };
 use crate::services::prelude::*;
-use crate::util::Claims;
+use crate::util::{hash_password, Claims};
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::{Extension, Json};
-use bcrypt::{hash, DEFAULT_COST};
 use chrono::Utc;
-use log::error;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    TransactionTrait,
 };
+use tracing::error;
 use uuid::Uuid;
 use validator::Validate;
 
+#[utoipa::path(
+    get,
+    path = "/teachers",
+    tag = "teachers",
+    responses(
+        (status = 200, body = AppResponse<Vec<TeacherView>>, description = "成功"),
+    ),
+)]
 pub async fn get_teachers(
     State(db): State<DatabaseConnection>,
 ) -> Result<Json<AppResponse<Vec<TeacherView>>>, (StatusCode, Json<AppResponse>)> {
@@ -27,7 +35,7 @@ pub async fn get_teachers(
         .all(&db)
         .await
         .map_err(|e| {
-            error!("{}", e);
+            error!(error = %e, "get_teachers failed");
             AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
         })?;
 
@@ -47,8 +55,16 @@ pub async fn get_teachers(
     Ok(AppResponse::success_with_data(result))
 }
 
+#[utoipa::path(
+    post,
+    path = "/teachers",
+    tag = "teachers",
+    request_body = AddTeacherRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn add_teacher(
-    Extension(claims): Extension<Claims>,
     State(db): State<DatabaseConnection>,
     Json(payload): Json<AddTeacherRequest>,
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
@@ -59,9 +75,12 @@ pub async fn add_teacher(
         ));
     }
 
-    check_permission(claims.role)?;
+    ensure_password_strength(
+        &payload.password,
+        &[&payload.username, &payload.member_dto.name],
+    )?;
 
-    let member_id = payload.member_id.unwrap_or_else(|| Uuid::nil());
+    let member_id = payload.member_id.unwrap_or_else(Uuid::nil);
 
     if find_teacher_by_id(&db, member_id).await?.is_some() {
         return Err(AppResponse::error(
@@ -80,6 +99,10 @@ pub async fn add_teacher(
         ));
     }
 
+    let default_role = find_role_by_name(&db, DEFAULT_ROLE)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "找不到預設角色"))?;
+
     let txn = db
         .begin()
         .await
@@ -87,8 +110,8 @@ pub async fn add_teacher(
 
     let member = upsert_member_with_context(&txn, member_id, payload.member_dto).await?;
 
-    let password_hash = hash(&payload.password, DEFAULT_COST).map_err(|e| {
-        error!("{}", e);
+    let password_hash = hash_password(&payload.password).map_err(|e| {
+        error!(error = %e, "add_teacher failed");
         AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
     })?;
 
@@ -96,28 +119,42 @@ pub async fn add_teacher(
         member_id: Set(member.id),
         username: Set(payload.username),
         password: Set(password_hash),
-        role_type: Set(RoleType::Admin.into()),
+        role_id: Set(default_role.id),
         employment_type: Set(EmploymentType::FullTime.into()),
         responsibility: Set(payload.responsibility),
         background: Set(payload.background),
+        // 初始密碼由管理員設定，首次登入後需由本人變更
+        must_change_password: Set(true),
         ..Default::default()
     };
 
     new_teacher.insert(&txn).await.map_err(|e| {
-        error!("{}", e);
+        error!(error = %e, "add_teacher failed");
         AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
     })?;
 
     txn.commit().await.map_err(|e| {
-        error!("{}", e);
+        error!(error = %e, "add_teacher failed");
         AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
     })?;
 
     Ok(AppResponse::success("建立成功"))
 }
 
+#[utoipa::path(
+    put,
+    path = "/teachers/{id}",
+    tag = "teachers",
+    security(("auth_token" = [])),
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    request_body = UpdateTeacherRequest,
+    responses(
+        (status = 200, body = AppResponse<TeacherView>, description = "成功"),
+    ),
+)]
 pub async fn update_teacher(
     Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
     State(db): State<DatabaseConnection>,
     Path(teacher_id): Path<Uuid>,
     Json(payload): Json<UpdateTeacherRequest>,
@@ -129,30 +166,48 @@ pub async fn update_teacher(
         ));
     }
 
-    if claims.sub != teacher_id || claims.role != RoleType::SuperAdmin {
+    if claims.sub != teacher_id && !permissions.contains(Permission::TeachersManage) {
         return Err(AppResponse::error(
             StatusCode::FORBIDDEN,
             "非本人或是系統管理員，無法修改。",
         ));
     }
 
+    // 本人變更密碼需要驗證目前的密碼，避免被盜用的登入階段直接改掉密碼
+    if claims.sub == teacher_id && payload.password.is_some() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "請使用變更密碼功能修改自己的密碼",
+        ));
+    }
+
+    if payload.password.is_some() {
+        ensure_can_manage_teacher(&db, &permissions, teacher_id).await?;
+    }
+
     let txn = db.begin().await.map_err(|e| {
-        error!("{}", e);
+        error!(error = %e, "update_teacher failed");
         AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
     })?;
 
     let member = upsert_member_with_context(&txn, teacher_id, payload.member_dto).await?;
 
-    let teacher = match find_teacher_by_id(&db, teacher_id).await? {
+    let teacher = match find_teacher_by_id(&txn, teacher_id).await? {
         Some(teacher) => {
+            if let Some(password) = &payload.password {
+                ensure_password_strength(password, &[&teacher.username])?;
+            }
+
             let mut teacher: teachers::ActiveModel = teacher.into();
 
-            if let Some(password) = payload.password {
-                let password_hash = hash(password, DEFAULT_COST).map_err(|e| {
-                    error!("{}", e);
+            if let Some(password) = &payload.password {
+                let password_hash = hash_password(password).map_err(|e| {
+                    error!(error = %e, "update_teacher failed");
                     AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
                 })?;
                 teacher.password = Set(password_hash);
+                // 密碼由管理員設定，下次登入後需由本人變更
+                teacher.must_change_password = Set(true);
             }
 
             teacher.employment_type = Set(payload.employment_type.into());
@@ -160,8 +215,8 @@ pub async fn update_teacher(
             teacher.background = Set(payload.background);
             teacher.updated_at = Set(Utc::now().naive_utc());
 
-            let teacher: teachers::Model = teacher.update(&db).await.map_err(|e| {
-                error!("{}", e);
+            let teacher: teachers::Model = teacher.update(&txn).await.map_err(|e| {
+                error!(error = %e, "update_teacher failed");
                 AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
             })?;
 
@@ -175,35 +230,45 @@ pub async fn update_teacher(
         }
     };
 
-    let teacher_view = TeacherView {
-        member_id: member.id.clone(),
-        username: teacher.username,
-        employment_type: teacher.employment_type.into(),
-        responsibility: teacher.responsibility,
-        background: teacher.background,
-        member_dto: MemberDto::from(member),
-    };
+    if payload.password.is_some() {
+        revoke_sessions_by_teacher(&txn, teacher_id).await?;
+    }
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "update_teacher failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let teacher_view = teacher_and_member_to_view(teacher, member);
 
     Ok(AppResponse::success_with_data(teacher_view))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/teachers/{id}",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
 pub async fn delete_teacher(
-    Extension(claims): Extension<Claims>,
     State(db): State<DatabaseConnection>,
     Path(teacher_id): Path<Uuid>,
 ) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
-    check_permission(claims.role)?;
-
     if let Some(teacher) = find_teacher_by_id(&db, teacher_id).await? {
         let mut teacher: teachers::ActiveModel = teacher.into();
         teacher.updated_at = Set(Utc::now().naive_utc());
         teacher.deleted_at = Set(Some(Utc::now().naive_utc()));
 
         teacher.update(&db).await.map_err(|e| {
-            error!("{}", e);
+            error!(error = %e, "delete_teacher failed");
             AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "刪除失敗")
         })?;
 
+        revoke_sessions_by_teacher(&db, teacher_id).await?;
+
         Ok(AppResponse::success("刪除成功"))
     } else {
         Err(AppResponse::error(
@@ -213,17 +278,53 @@ pub async fn delete_teacher(
     }
 }
 
-async fn find_teacher_by_id(
-    db: &DatabaseConnection,
+#[utoipa::path(
+    post,
+    path = "/teachers/{id}/unlock",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn unlock_teacher(
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if let Some(teacher) = find_teacher_by_id(&db, teacher_id).await? {
+        let mut teacher: teachers::ActiveModel = teacher.into();
+        teacher.failed_login_attempts = Set(0);
+        teacher.locked_until = Set(None);
+        teacher.updated_at = Set(Utc::now().naive_utc());
+
+        teacher.update(&db).await.map_err(|e| {
+            error!(error = %e, "unlock_teacher failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "解除鎖定失敗")
+        })?;
+
+        Ok(AppResponse::success("已解除鎖定"))
+    } else {
+        Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "找不到對應的教職員",
+        ))
+    }
+}
+
+pub(crate) async fn find_teacher_by_id<C>(
+    db: &C,
     id: Uuid,
-) -> Result<Option<teachers::Model>, (StatusCode, Json<AppResponse>)> {
+) -> Result<Option<teachers::Model>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
     teachers::Entity::find()
         .filter(teachers::Column::MemberId.eq(id))
         .filter(teachers::Column::DeletedAt.is_null())
         .one(db)
         .await
         .map_err(|e| {
-            error!("find_teacher_by_id error:{}", e);
+            error!(error = %e, "find_teacher_by_id failed");
             AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
         })
 }
@@ -238,18 +339,7 @@ async fn find_teacher_by_username(
         .one(db)
         .await
         .map_err(|e| {
-            error!("find_teacher_by_username error:{}", e);
+            error!(error = %e, "find_teacher_by_username failed");
             AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
         })
 }
-
-fn check_permission(role_type: RoleType) -> Result<(), (StatusCode, Json<AppResponse>)> {
-    if role_type.ne(&RoleType::SuperAdmin) {
-        return Err(AppResponse::error(
-            StatusCode::FORBIDDEN,
-            "主管理員才可使用",
-        ));
-    }
-
-    Ok(())
-}
@@ -0,0 +1,464 @@
+use crate::db::entities::{teachers, two_factor_recovery_codes};
+use crate::models::{
+    AppResponse, ConfirmPasswordRequest, EnableTwoFactorRequest, PermissionSet, RecoveryCodesView,
+    TwoFactorSetupView,
+};
+use crate::services::auth_service::issue_access_token;
+use crate::services::role_service::ensure_can_manage_teacher;
+use crate::services::setting_service::is_two_factor_required;
+use crate::services::teacher_service::find_teacher_by_id;
+use crate::util::{
+    generate_reset_code, generate_totp_secret, hash_token, normalize_reset_code, totp_otpauth_uri,
+    verify_password, verify_totp_code, Claims,
+};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    QueryFilter, TransactionTrait, UpdateMany,
+};
+use tower_cookies::Cookies;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+/// 復原碼正規化後的長度，用來和 6 位數的 TOTP 驗證碼區分
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+#[utoipa::path(
+    post,
+    path = "/me/two-factor/setup",
+    tag = "me",
+    security(("auth_token" = [])),
+    responses(
+        (status = 200, body = AppResponse<TwoFactorSetupView>, description = "成功"),
+    ),
+)]
+pub async fn setup_two_factor(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<AppResponse<TwoFactorSetupView>>, (StatusCode, Json<AppResponse>)> {
+    let teacher = find_current_teacher(&db, claims.sub).await?;
+
+    if teacher.totp_enabled_at.is_some() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "已啟用兩步驟驗證",
+        ));
+    }
+
+    let secret = generate_totp_secret();
+    let otpauth_uri = totp_otpauth_uri(&secret, &teacher.username)
+        .ok_or_else(|| AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常"))?;
+
+    // 尚未啟用前重新設定會直接覆蓋舊的金鑰
+    let mut teacher: teachers::ActiveModel = teacher.into();
+    teacher.totp_secret = Set(Some(secret.clone()));
+    teacher.totp_last_used_step = Set(None);
+    teacher.updated_at = Set(Utc::now().naive_utc());
+
+    teacher.update(&db).await.map_err(|e| {
+        error!(error = %e, "setup_two_factor failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
+    })?;
+
+    Ok(AppResponse::success_with_data(TwoFactorSetupView {
+        secret,
+        otpauth_uri,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/me/two-factor/enable",
+    tag = "me",
+    security(("auth_token" = [])),
+    request_body = EnableTwoFactorRequest,
+    responses(
+        (status = 200, body = AppResponse<RecoveryCodesView>, description = "成功"),
+    ),
+)]
+pub async fn enable_two_factor(
+    cookies: Cookies,
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<EnableTwoFactorRequest>,
+) -> Result<Json<AppResponse<RecoveryCodesView>>, (StatusCode, Json<AppResponse>)> {
+    let teacher = find_current_teacher(&db, claims.sub).await?;
+
+    if teacher.totp_enabled_at.is_some() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "已啟用兩步驟驗證",
+        ));
+    }
+
+    let Some(secret) = teacher.totp_secret.as_deref() else {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "請先產生兩步驟驗證金鑰",
+        ));
+    };
+
+    let Some(step) = verify_totp_code(secret, &payload.code, teacher.totp_last_used_step) else {
+        return Err(AppResponse::error(StatusCode::BAD_REQUEST, "驗證碼錯誤"));
+    };
+
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "enable_two_factor failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    let mut teacher: teachers::ActiveModel = teacher.into();
+    teacher.totp_enabled_at = Set(Some(Utc::now().naive_utc()));
+    teacher.totp_last_used_step = Set(Some(step));
+    teacher.updated_at = Set(Utc::now().naive_utc());
+
+    let teacher = teacher.update(&txn).await.map_err(|e| {
+        error!(error = %e, "enable_two_factor failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
+    })?;
+
+    let recovery_codes = replace_recovery_codes(&txn, teacher.member_id).await?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "enable_two_factor failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    // 重新發出 access token，解除「必須啟用兩步驟驗證」的限制
+    issue_access_token(&db, &cookies, &teacher, claims.jti).await?;
+
+    info!(teacher_id = %teacher.member_id, "two-factor enabled");
+
+    Ok(AppResponse::success_with_data(RecoveryCodesView {
+        recovery_codes,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/me/two-factor",
+    tag = "me",
+    security(("auth_token" = [])),
+    request_body = ConfirmPasswordRequest,
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn disable_two_factor(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<ConfirmPasswordRequest>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    let teacher = find_current_teacher(&db, claims.sub).await?;
+    verify_current_password(&teacher, &payload.current_password)?;
+
+    if teacher.totp_enabled_at.is_none() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "尚未啟用兩步驟驗證",
+        ));
+    }
+
+    if is_two_factor_required(&db).await? {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "系統要求所有教職員啟用兩步驟驗證，無法停用",
+        ));
+    }
+
+    clear_two_factor(&db, teacher.member_id).await?;
+
+    info!(teacher_id = %teacher.member_id, "two-factor disabled");
+
+    Ok(AppResponse::success("已停用兩步驟驗證"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/me/two-factor/recovery-codes",
+    tag = "me",
+    security(("auth_token" = [])),
+    request_body = ConfirmPasswordRequest,
+    responses(
+        (status = 200, body = AppResponse<RecoveryCodesView>, description = "成功"),
+    ),
+)]
+pub async fn regenerate_recovery_codes(
+    Extension(claims): Extension<Claims>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<ConfirmPasswordRequest>,
+) -> Result<Json<AppResponse<RecoveryCodesView>>, (StatusCode, Json<AppResponse>)> {
+    let teacher = find_current_teacher(&db, claims.sub).await?;
+    verify_current_password(&teacher, &payload.current_password)?;
+
+    if teacher.totp_enabled_at.is_none() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "尚未啟用兩步驟驗證",
+        ));
+    }
+
+    let recovery_codes = replace_recovery_codes(&db, teacher.member_id).await?;
+
+    Ok(AppResponse::success_with_data(RecoveryCodesView {
+        recovery_codes,
+    }))
+}
+
+/// 教職員遺失驗證裝置時，由管理者清除其兩步驟驗證設定。
+#[utoipa::path(
+    delete,
+    path = "/teachers/{id}/two-factor",
+    tag = "teachers",
+    params(("id" = Uuid, Path, description = "教職員的成員 id")),
+    responses(
+        (status = 200, body = AppResponse, description = "成功"),
+    ),
+)]
+pub async fn reset_teacher_two_factor(
+    Extension(claims): Extension<Claims>,
+    Extension(permissions): Extension<PermissionSet>,
+    State(db): State<DatabaseConnection>,
+    Path(teacher_id): Path<Uuid>,
+) -> Result<Json<AppResponse>, (StatusCode, Json<AppResponse>)> {
+    if find_teacher_by_id(&db, teacher_id).await?.is_none() {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "找不到對應的教職員",
+        ));
+    }
+    ensure_can_manage_teacher(&db, &permissions, teacher_id).await?;
+
+    clear_two_factor(&db, teacher_id).await?;
+
+    info!(actor = %claims.sub, teacher_id = %teacher_id, "two-factor reset");
+
+    Ok(AppResponse::success("已重設兩步驟驗證"))
+}
+
+/// 驗證登入第二步輸入的 TOTP 驗證碼或復原碼，通過後該驗證碼即不可再使用。
+pub(crate) async fn verify_two_factor_code<C>(
+    db: &C,
+    teacher: &teachers::Model,
+    code: &str,
+) -> Result<bool, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let (Some(secret), Some(_)) = (teacher.totp_secret.as_deref(), teacher.totp_enabled_at) else {
+        return Ok(false);
+    };
+
+    let normalized = normalize_reset_code(code);
+    if normalized.len() == RECOVERY_CODE_LENGTH {
+        return redeem_recovery_code(db, teacher.member_id, &normalized).await;
+    }
+
+    let Some(step) = verify_totp_code(secret, code, teacher.totp_last_used_step) else {
+        return Ok(false);
+    };
+
+    // 以條件更新記錄使用過的時間區間，避免同一組驗證碼被同時使用兩次
+    let result = teachers::Entity::update_many()
+        .col_expr(teachers::Column::TotpLastUsedStep, Expr::value(step))
+        .filter(teachers::Column::MemberId.eq(teacher.member_id))
+        .filter(
+            Condition::any()
+                .add(teachers::Column::TotpLastUsedStep.is_null())
+                .add(teachers::Column::TotpLastUsedStep.lt(step)),
+        )
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "verify_two_factor_code failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(result.rows_affected > 0)
+}
+
+async fn redeem_recovery_code<C>(
+    db: &C,
+    teacher_id: Uuid,
+    normalized_code: &str,
+) -> Result<bool, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    let result = redeem_recovery_code_query(teacher_id, normalized_code)
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "redeem_recovery_code failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    if result.rows_affected == 0 {
+        return Ok(false);
+    }
+
+    warn!(teacher_id = %teacher_id, "recovery code used");
+
+    Ok(true)
+}
+
+/// 以條件更新標記復原碼已使用，已使用過的復原碼不會符合條件，每組只能兌換一次。
+fn redeem_recovery_code_query(
+    teacher_id: Uuid,
+    normalized_code: &str,
+) -> UpdateMany<two_factor_recovery_codes::Entity> {
+    two_factor_recovery_codes::Entity::update_many()
+        .col_expr(
+            two_factor_recovery_codes::Column::UsedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(two_factor_recovery_codes::Column::TeacherId.eq(teacher_id))
+        .filter(two_factor_recovery_codes::Column::CodeHash.eq(hash_token(normalized_code)))
+        .filter(two_factor_recovery_codes::Column::UsedAt.is_null())
+}
+
+/// 作廢所有舊的復原碼並產生一組新的，回傳的明碼只會顯示這一次。
+async fn replace_recovery_codes<C>(
+    db: &C,
+    teacher_id: Uuid,
+) -> Result<Vec<String>, (StatusCode, Json<AppResponse>)>
+where
+    C: ConnectionTrait,
+{
+    two_factor_recovery_codes::Entity::delete_many()
+        .filter(two_factor_recovery_codes::Column::TeacherId.eq(teacher_id))
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "replace_recovery_codes failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    let now = Utc::now().naive_utc();
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_reset_code())
+        .collect();
+
+    let new_codes = recovery_codes
+        .iter()
+        .map(|code| two_factor_recovery_codes::ActiveModel {
+            teacher_id: Set(teacher_id),
+            code_hash: Set(hash_token(&normalize_reset_code(code))),
+            created_at: Set(now),
+            ..Default::default()
+        });
+
+    two_factor_recovery_codes::Entity::insert_many(new_codes)
+        .exec(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "replace_recovery_codes failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    Ok(recovery_codes)
+}
+
+async fn clear_two_factor(
+    db: &DatabaseConnection,
+    teacher_id: Uuid,
+) -> Result<(), (StatusCode, Json<AppResponse>)> {
+    let txn = db.begin().await.map_err(|e| {
+        error!(error = %e, "clear_two_factor failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    teachers::Entity::update_many()
+        .col_expr(teachers::Column::TotpSecret, Expr::value(None::<String>))
+        .col_expr(
+            teachers::Column::TotpEnabledAt,
+            Expr::value(None::<NaiveDateTime>),
+        )
+        .col_expr(teachers::Column::TotpLastUsedStep, Expr::value(None::<i64>))
+        .col_expr(
+            teachers::Column::UpdatedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(teachers::Column::MemberId.eq(teacher_id))
+        .exec(&txn)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "clear_two_factor failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "更新失敗")
+        })?;
+
+    two_factor_recovery_codes::Entity::delete_many()
+        .filter(two_factor_recovery_codes::Column::TeacherId.eq(teacher_id))
+        .exec(&txn)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "clear_two_factor failed");
+            AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "資料庫異常")
+        })?;
+
+    txn.commit().await.map_err(|e| {
+        error!(error = %e, "clear_two_factor failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })
+}
+
+async fn find_current_teacher(
+    db: &DatabaseConnection,
+    teacher_id: Uuid,
+) -> Result<teachers::Model, (StatusCode, Json<AppResponse>)> {
+    find_teacher_by_id(db, teacher_id)
+        .await?
+        .ok_or_else(|| AppResponse::error(StatusCode::UNAUTHORIZED, "請檢查登入是否成功"))
+}
+
+fn verify_current_password(
+    teacher: &teachers::Model,
+    password: &str,
+) -> Result<(), (StatusCode, Json<AppResponse>)> {
+    let is_valid = verify_password(password, &teacher.password).map_err(|e| {
+        error!(error = %e, "verify_current_password failed");
+        AppResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "伺服器發生異常")
+    })?;
+
+    if !is_valid {
+        return Err(AppResponse::error(
+            StatusCode::BAD_REQUEST,
+            "目前的密碼不正確",
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, QueryTrait};
+
+    #[test]
+    fn recovery_codes_can_only_be_redeemed_once() {
+        let teacher_id = Uuid::now_v7();
+        let code = generate_reset_code();
+        let normalized = normalize_reset_code(&code.to_lowercase());
+
+        let statement =
+            redeem_recovery_code_query(teacher_id, &normalized).build(DatabaseBackend::Postgres);
+
+        // 兌換時同時標記為已使用，且只會更新尚未使用過、雜湊相符的復原碼
+        assert!(statement
+            .sql
+            .starts_with(r#"UPDATE "two_factor_recovery_codes" SET "used_at" = $1"#));
+        assert!(statement
+            .sql
+            .ends_with(r#"AND "two_factor_recovery_codes"."used_at" IS NULL"#));
+        let values = format!("{:?}", statement.values);
+        assert!(values.contains(&teacher_id.to_string()));
+        assert!(values.contains(&hash_token(&normalize_reset_code(&code))));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::config::CONFIG;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::{header, HeaderMap};
+use ipnet::IpNet;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::LazyLock;
+
+static TRUSTED_PROXIES: LazyLock<Vec<IpNet>> =
+    LazyLock::new(|| CONFIG.server.trusted_proxy_networks());
+
+/// 發出請求的用戶端資訊。
+///
+/// 只有連線來自 `server.trusted_proxies` 時才採用 `X-Forwarded-For`，
+/// 否則任何人都能自行帶入這個標頭，偽造登入限制與稽核紀錄中的 IP。
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+
+        let ip_address = peer
+            .map(|peer| client_ip(peer, &parts.headers, &TRUSTED_PROXIES))
+            .map(|ip| ip.to_string());
+
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        Ok(ClientInfo {
+            ip_address,
+            user_agent,
+        })
+    }
+}
+
+/// 從最後一個轉送的位址往前找，第一個不是信任代理的位址就是用戶端。
+///
+/// 最左邊的值由用戶端自行填寫，只有右側由信任的代理附加的部分可以採信；
+/// 遇到無法解析的值時停止，改用最後一個確認過的位址。
+fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpNet]) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted_proxies.iter().any(|net| net.contains(ip));
+
+    if !is_trusted(&peer) {
+        return peer;
+    }
+
+    let forwarded_for: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+
+    let mut client = peer;
+    for hop in forwarded_for.into_iter().rev() {
+        let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !is_trusted(&ip) {
+            break;
+        }
+    }
+
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn proxies() -> Vec<IpNet> {
+        vec!["10.0.0.0/8".parse().unwrap()]
+    }
+
+    fn headers(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("x-forwarded-for", HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn ignores_forwarded_for_from_untrusted_peer() {
+        let headers = headers(&["198.51.100.7"]);
+        assert_eq!(
+            client_ip(ip("203.0.113.5"), &headers, &proxies()),
+            ip("203.0.113.5")
+        );
+    }
+
+    #[test]
+    fn takes_right_most_untrusted_hop() {
+        // 用戶端自行帶入 1.2.3.4，代理再附加實際連線的位址
+        let headers = headers(&["1.2.3.4, 198.51.100.7, 10.0.0.2"]);
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), &headers, &proxies()),
+            ip("198.51.100.7")
+        );
+    }
+
+    #[test]
+    fn joins_multiple_forwarded_for_headers() {
+        let headers = headers(&["1.2.3.4", "198.51.100.7"]);
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), &headers, &proxies()),
+            ip("198.51.100.7")
+        );
+    }
+
+    #[test]
+    fn stops_at_unparsable_hop() {
+        let headers = headers(&["198.51.100.7, garbage, 10.0.0.2"]);
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), &headers, &proxies()),
+            ip("10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_peer_without_header() {
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), &HeaderMap::new(), &proxies()),
+            ip("10.0.0.1")
+        );
+    }
+}
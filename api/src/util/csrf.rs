@@ -0,0 +1,87 @@
+use crate::config::CONFIG;
+
+/// 存放 CSRF token 的 cookie，前端讀取後放進 `X-CSRF-Token` 標頭送回
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// 比對 cookie 與標頭中的 CSRF token，以固定時間比較避免從回應時間推測內容。
+pub fn csrf_token_matches(cookie_token: &str, header_token: &str) -> bool {
+    if cookie_token.is_empty() || cookie_token.len() != header_token.len() {
+        return false;
+    }
+
+    cookie_token
+        .bytes()
+        .zip(header_token.bytes())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+/// 請求的來源是否為允許的前端網址，`Referer` 只比對其中的 origin 部分。
+pub fn is_allowed_origin(origin_or_referer: &str) -> bool {
+    request_origin(origin_or_referer).is_some_and(|origin| CONFIG.server.cors.allows_origin(origin))
+}
+
+/// 取出 `Origin` 或 `Referer` 中 scheme 與 host 的部分。
+fn request_origin(origin_or_referer: &str) -> Option<&str> {
+    let (scheme, rest) = origin_or_referer.split_once("://")?;
+    let host_len = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+
+    Some(&origin_or_referer[..scheme.len() + "://".len() + host_len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CorsConfig;
+
+    #[test]
+    fn csrf_token_must_match_exactly() {
+        assert!(csrf_token_matches("abc123", "abc123"));
+        assert!(!csrf_token_matches("abc123", "abc124"));
+        assert!(!csrf_token_matches("abc123", "abc12"));
+        assert!(!csrf_token_matches("abc123", "abc1234"));
+        assert!(!csrf_token_matches("abc123", ""));
+        // cookie 不存在時不可以用空的標頭通過
+        assert!(!csrf_token_matches("", ""));
+    }
+
+    #[test]
+    fn extracts_origin_from_referer() {
+        assert_eq!(
+            request_origin("https://app.example.org"),
+            Some("https://app.example.org")
+        );
+        assert_eq!(
+            request_origin("https://app.example.org:8443/login?next=/"),
+            Some("https://app.example.org:8443")
+        );
+        assert_eq!(
+            request_origin("https://app.example.org?x=1"),
+            Some("https://app.example.org")
+        );
+        assert_eq!(
+            request_origin("https://app.example.org#top"),
+            Some("https://app.example.org")
+        );
+        assert_eq!(request_origin("app.example.org/login"), None);
+        assert_eq!(request_origin("null"), None);
+    }
+
+    #[test]
+    fn referer_path_cannot_spoof_allowed_origin() {
+        let cors = CorsConfig {
+            allowed_origins: vec!["https://app.example.org".to_string()],
+            allowed_methods: Vec::new(),
+            allowed_headers: Vec::new(),
+        };
+        let allowed =
+            |value| request_origin(value).is_some_and(|origin| cors.allows_origin(origin));
+
+        assert!(allowed("https://app.example.org/teachers"));
+        assert!(!allowed("https://evil.example.net/https://app.example.org"));
+        assert!(!allowed("https://evil.example.net?https://app.example.org"));
+        assert!(!allowed("https://app.example.org.evil.example.net/"));
+        assert!(!allowed("http://app.example.org/"));
+    }
+}
@@ -1,43 +1,558 @@
-use crate::config::CONFIG;
-use crate::models::RoleType;
+use crate::config::{AuthConfig, JwtAlgorithm, JwtKeyConfig, CONFIG};
 use axum::http::StatusCode;
-use jsonwebtoken::{encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
+use jsonwebtoken::{
+    decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation,
+};
 use sea_orm::sqlx::types::chrono::Utc;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::LazyLock;
 use std::time::Duration;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: Uuid,
-    pub role: RoleType,
+    pub jti: Uuid,
+    /// 為 true 時只能存取變更密碼相關的路由
+    #[serde(default)]
+    pub must_change_password: bool,
+    /// 為 true 時只能存取啟用兩步驟驗證相關的路由
+    #[serde(default)]
+    pub must_enroll_two_factor: bool,
     pub exp: i64,
 }
 
-pub fn create_token(id: Uuid, role: RoleType) -> Result<String, StatusCode> {
+/// 密碼驗證通過、等待輸入兩步驟驗證碼時使用的短期憑證。
+///
+/// 帶有 `aud`，因此無法被 `decode_token` 當成一般的 access token 使用。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorChallengeClaims {
+    pub sub: Uuid,
+    pub aud: String,
+    pub exp: i64,
+}
+
+/// 家長入口使用的 access token，`jti` 為 `guardian_sessions` 的 id。
+///
+/// 帶有 `aud`，因此無法被 `decode_token` 當成教職員的 access token 使用。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardianClaims {
+    pub sub: Uuid,
+    pub jti: Uuid,
+    pub aud: String,
+    /// 為 true 時只能存取變更密碼相關的路由
+    #[serde(default)]
+    pub must_change_password: bool,
+    pub exp: i64,
+}
+
+/// 跳轉到身分提供者之前，保存 OpenID Connect 流程所需的隨機值，放在短期的 cookie 中。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcFlowClaims {
+    pub state: String,
+    pub nonce: String,
+    /// PKCE 的 code verifier，換發 token 時才送給身分提供者
+    pub code_verifier: String,
+    pub aud: String,
+    pub exp: i64,
+}
+
+const TWO_FACTOR_CHALLENGE_AUDIENCE: &str = "two_factor_challenge";
+const GUARDIAN_AUDIENCE: &str = "guardian";
+const OIDC_FLOW_AUDIENCE: &str = "oidc_flow";
+/// 只設定 `jwt_secret` 時使用的 `kid`
+const DEFAULT_KID: &str = "default";
+
+static JWT_KEYS: LazyLock<JwtKeys> = LazyLock::new(|| {
+    load_jwt_keys(&CONFIG.auth).unwrap_or_else(|e| panic!("Failed to load JWT keys: {}", e))
+});
+
+struct JwtKeys {
+    current_kid: String,
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    decoding_keys: HashMap<String, (Algorithm, DecodingKey)>,
+    /// 驗證沒有 `kid` 的舊 token，只在開啟 `auth.jwt.accept_legacy_tokens` 時設定
+    legacy_key: Option<DecodingKey>,
+}
+
+impl JwtKeys {
+    /// 以目前的金鑰簽章，並在標頭中帶上 `kid`。
+    fn encode<T: Serialize>(&self, claims: &T) -> Result<String, StatusCode> {
+        let mut header = Header::new(self.algorithm);
+        header.kid = Some(self.current_kid.clone());
+
+        encode(&header, claims, &self.encoding_key).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// 依標頭中的 `kid` 選擇驗證金鑰，並只接受該金鑰對應的演算法。
+    fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        mut validation: Validation,
+    ) -> Result<TokenData<T>, StatusCode> {
+        let header = decode_header(token).map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+        let (algorithm, decoding_key) = match header.kid {
+            Some(kid) => self
+                .decoding_keys
+                .get(&kid)
+                .map(|(algorithm, key)| (*algorithm, key)),
+            None => self.legacy_key.as_ref().map(|key| (Algorithm::HS256, key)),
+        }
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+        validation.algorithms = vec![algorithm];
+
+        jsonwebtoken::decode::<T>(token, decoding_key, &validation)
+            .map_err(|_| StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// 啟動時先載入金鑰，設定錯誤時不必等到第一次登入才發現。
+pub fn init_jwt_keys() {
+    LazyLock::force(&JWT_KEYS);
+}
+
+/// 檢查設定中的金鑰是否都能載入，不會影響目前使用的金鑰。
+pub fn check_jwt_keys(config: &AuthConfig) -> Result<(), String> {
+    load_jwt_keys(config).map(|_| ())
+}
+
+pub fn create_token(
+    id: Uuid,
+    session_id: Uuid,
+    must_change_password: bool,
+    must_enroll_two_factor: bool,
+) -> Result<String, StatusCode> {
     let claims = Claims {
         sub: id,
-        role,
-        exp: (Utc::now() + Duration::from_secs(24 * 60 * 60)).timestamp(),
+        jti: session_id,
+        must_change_password,
+        must_enroll_two_factor,
+        exp: (Utc::now() + CONFIG.auth.access_token_lifetime()).timestamp(),
     };
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(CONFIG.auth.jwt_secret.as_bytes()),
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(token)
+    encode_claims(&claims)
 }
 
 pub fn decode_token(token: &str) -> Result<TokenData<Claims>, StatusCode> {
-    let token_data = jsonwebtoken::decode::<Claims>(
-        &token,
-        &DecodingKey::from_secret(CONFIG.auth.jwt_secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|_| StatusCode::UNAUTHORIZED)?;
-
-    Ok(token_data)
+    decode_claims(token, Validation::default())
+}
+
+/// 只驗證簽章、不檢查是否過期，登出時用來找出要撤銷的登入階段。
+pub fn decode_token_ignoring_expiry(token: &str) -> Result<TokenData<Claims>, StatusCode> {
+    decode_claims(token, ignore_expiry_validation())
+}
+
+fn ignore_expiry_validation() -> Validation {
+    let mut validation = Validation::default();
+    validation.validate_exp = false;
+    validation
+}
+
+pub fn create_two_factor_challenge(id: Uuid) -> Result<String, StatusCode> {
+    let claims = TwoFactorChallengeClaims {
+        sub: id,
+        aud: TWO_FACTOR_CHALLENGE_AUDIENCE.to_string(),
+        exp: (Utc::now() + CONFIG.auth.two_factor.challenge_lifetime()).timestamp(),
+    };
+
+    encode_claims(&claims)
+}
+
+pub fn decode_two_factor_challenge(
+    token: &str,
+) -> Result<TokenData<TwoFactorChallengeClaims>, StatusCode> {
+    let mut validation = Validation::default();
+    validation.set_audience(&[TWO_FACTOR_CHALLENGE_AUDIENCE]);
+    validation.set_required_spec_claims(&["exp", "aud"]);
+
+    decode_claims(token, validation)
+}
+
+pub fn create_guardian_token(
+    id: Uuid,
+    session_id: Uuid,
+    must_change_password: bool,
+) -> Result<String, StatusCode> {
+    let claims = GuardianClaims {
+        sub: id,
+        jti: session_id,
+        aud: GUARDIAN_AUDIENCE.to_string(),
+        must_change_password,
+        exp: (Utc::now() + CONFIG.auth.guardian_session_lifetime()).timestamp(),
+    };
+
+    encode_claims(&claims)
+}
+
+pub fn decode_guardian_token(token: &str) -> Result<TokenData<GuardianClaims>, StatusCode> {
+    let mut validation = Validation::default();
+    validation.set_audience(&[GUARDIAN_AUDIENCE]);
+    validation.set_required_spec_claims(&["exp", "aud"]);
+
+    decode_claims(token, validation)
+}
+
+pub fn create_oidc_flow_token(
+    state: String,
+    nonce: String,
+    code_verifier: String,
+    lifetime: Duration,
+) -> Result<String, StatusCode> {
+    let claims = OidcFlowClaims {
+        state,
+        nonce,
+        code_verifier,
+        aud: OIDC_FLOW_AUDIENCE.to_string(),
+        exp: (Utc::now() + lifetime).timestamp(),
+    };
+
+    encode_claims(&claims)
+}
+
+pub fn decode_oidc_flow_token(token: &str) -> Result<TokenData<OidcFlowClaims>, StatusCode> {
+    let mut validation = Validation::default();
+    validation.set_audience(&[OIDC_FLOW_AUDIENCE]);
+    validation.set_required_spec_claims(&["exp", "aud"]);
+
+    decode_claims(token, validation)
+}
+
+fn encode_claims<T: Serialize>(claims: &T) -> Result<String, StatusCode> {
+    JWT_KEYS.encode(claims)
+}
+
+fn decode_claims<T: DeserializeOwned>(
+    token: &str,
+    validation: Validation,
+) -> Result<TokenData<T>, StatusCode> {
+    JWT_KEYS.decode(token, validation)
+}
+
+fn load_jwt_keys(config: &AuthConfig) -> Result<JwtKeys, String> {
+    let legacy_secret = config
+        .jwt_secret
+        .as_deref()
+        .filter(|secret| !secret.is_empty());
+
+    // 沒有設定金鑰組時，沿用 jwt_secret 作為唯一的 HS256 金鑰
+    let (current_kid, key_configs) = if config.jwt.keys.is_empty() {
+        let secret = legacy_secret.ok_or("必須設定 auth.jwt_secret 或 auth.jwt.keys")?;
+        let key_config = JwtKeyConfig {
+            kid: DEFAULT_KID.to_string(),
+            algorithm: JwtAlgorithm::HS256,
+            secret: Some(secret.to_string()),
+            private_key_file: None,
+            public_key_file: None,
+        };
+        (DEFAULT_KID.to_string(), vec![key_config])
+    } else {
+        let current_kid = match (&config.jwt.current_kid, config.jwt.keys.as_slice()) {
+            (Some(kid), _) => kid.clone(),
+            (None, [key_config]) => key_config.kid.clone(),
+            (None, _) => return Err("有多把金鑰時必須設定 auth.jwt.current_kid".to_string()),
+        };
+        (current_kid, config.jwt.keys.clone())
+    };
+
+    let mut decoding_keys = HashMap::new();
+    let mut current_key = None;
+    for key_config in &key_configs {
+        let algorithm = to_algorithm(key_config.algorithm);
+        let (encoding_key, decoding_key) = load_key_pair(key_config)?;
+
+        if decoding_keys
+            .insert(key_config.kid.clone(), (algorithm, decoding_key))
+            .is_some()
+        {
+            return Err(format!("重複的 kid：{}", key_config.kid));
+        }
+
+        if key_config.kid == current_kid {
+            let encoding_key =
+                encoding_key.ok_or_else(|| format!("目前的金鑰 {} 缺少私鑰", key_config.kid))?;
+            current_key = Some((algorithm, encoding_key));
+        }
+    }
+
+    let (algorithm, encoding_key) =
+        current_key.ok_or_else(|| format!("找不到 current_kid 對應的金鑰：{}", current_kid))?;
+
+    Ok(JwtKeys {
+        current_kid,
+        algorithm,
+        encoding_key,
+        decoding_keys,
+        legacy_key: legacy_secret
+            .filter(|_| config.jwt.accept_legacy_tokens)
+            .map(|secret| DecodingKey::from_secret(secret.as_bytes())),
+    })
+}
+
+/// 讀取單一金鑰，私鑰是選填的，只用來驗證的舊金鑰不需要。
+fn load_key_pair(key_config: &JwtKeyConfig) -> Result<(Option<EncodingKey>, DecodingKey), String> {
+    let kid = &key_config.kid;
+
+    let (encoding_key, decoding_key) = match key_config.algorithm {
+        JwtAlgorithm::HS256 => {
+            let secret = key_config
+                .secret
+                .as_deref()
+                .filter(|secret| !secret.is_empty())
+                .ok_or_else(|| format!("HS256 金鑰 {} 缺少 secret", kid))?;
+
+            return Ok((
+                Some(EncodingKey::from_secret(secret.as_bytes())),
+                DecodingKey::from_secret(secret.as_bytes()),
+            ));
+        }
+        JwtAlgorithm::RS256 => {
+            let (private_pem, public_pem) = read_key_files(key_config)?;
+            (
+                private_pem
+                    .map(|pem| EncodingKey::from_rsa_pem(&pem))
+                    .transpose(),
+                DecodingKey::from_rsa_pem(&public_pem),
+            )
+        }
+        JwtAlgorithm::EdDSA => {
+            let (private_pem, public_pem) = read_key_files(key_config)?;
+            (
+                private_pem
+                    .map(|pem| EncodingKey::from_ed_pem(&pem))
+                    .transpose(),
+                DecodingKey::from_ed_pem(&public_pem),
+            )
+        }
+    };
+
+    let encoding_key = encoding_key.map_err(|e| format!("金鑰 {} 的私鑰格式錯誤：{}", kid, e))?;
+    let decoding_key = decoding_key.map_err(|e| format!("金鑰 {} 的公鑰格式錯誤：{}", kid, e))?;
+
+    Ok((encoding_key, decoding_key))
+}
+
+/// 讀取 PEM 格式的私鑰（選填）與公鑰。
+fn read_key_files(key_config: &JwtKeyConfig) -> Result<(Option<Vec<u8>>, Vec<u8>), String> {
+    let kid = &key_config.kid;
+    let read_pem = |path: &str| {
+        fs::read(path).map_err(|e| format!("無法讀取金鑰 {} 的檔案 {}：{}", kid, path, e))
+    };
+
+    let public_key_file = key_config
+        .public_key_file
+        .as_deref()
+        .ok_or_else(|| format!("金鑰 {} 缺少 public_key_file", kid))?;
+
+    let private_pem = key_config
+        .private_key_file
+        .as_deref()
+        .map(read_pem)
+        .transpose()?;
+
+    Ok((private_pem, read_pem(public_key_file)?))
+}
+
+fn to_algorithm(algorithm: JwtAlgorithm) -> Algorithm {
+    match algorithm {
+        JwtAlgorithm::HS256 => Algorithm::HS256,
+        JwtAlgorithm::RS256 => Algorithm::RS256,
+        JwtAlgorithm::EdDSA => Algorithm::EdDSA,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JwtConfig;
+
+    fn auth_config(jwt_secret: Option<&str>, jwt: JwtConfig) -> AuthConfig {
+        let mut config: AuthConfig = serde_yml::from_str(
+            "default_name: 管理員\ndefault_username: admin\ndefault_password: password",
+        )
+        .unwrap();
+        config.jwt_secret = jwt_secret.map(str::to_string);
+        config.jwt = jwt;
+        config
+    }
+
+    fn hs256_key(kid: &str, secret: &str) -> JwtKeyConfig {
+        JwtKeyConfig {
+            kid: kid.to_string(),
+            algorithm: JwtAlgorithm::HS256,
+            secret: Some(secret.to_string()),
+            private_key_file: None,
+            public_key_file: None,
+        }
+    }
+
+    fn claims() -> Claims {
+        Claims {
+            sub: Uuid::now_v7(),
+            jti: Uuid::now_v7(),
+            must_change_password: false,
+            must_enroll_two_factor: false,
+            exp: (Utc::now() + Duration::from_secs(60)).timestamp(),
+        }
+    }
+
+    fn sign(header: Header, secret: &str) -> String {
+        encode(
+            &header,
+            &claims(),
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    fn rotation_config() -> AuthConfig {
+        auth_config(
+            None,
+            JwtConfig {
+                current_kid: Some("new".to_string()),
+                keys: vec![
+                    hs256_key("new", "new-secret"),
+                    hs256_key("old", "old-secret"),
+                ],
+                accept_legacy_tokens: false,
+            },
+        )
+    }
+
+    #[test]
+    fn jwt_secret_alone_signs_with_default_kid() {
+        let keys = load_jwt_keys(&auth_config(Some("secret"), JwtConfig::default())).unwrap();
+        let token = keys.encode(&claims()).unwrap();
+
+        assert_eq!(
+            decode_header(&token).unwrap().kid.as_deref(),
+            Some(DEFAULT_KID)
+        );
+        assert!(keys.decode::<Claims>(&token, Validation::default()).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_key_sets() {
+        let missing = auth_config(None, JwtConfig::default());
+        assert!(load_jwt_keys(&missing).is_err());
+
+        let mut no_current_kid = rotation_config();
+        no_current_kid.jwt.current_kid = None;
+        assert!(load_jwt_keys(&no_current_kid).is_err());
+
+        let mut unknown_current_kid = rotation_config();
+        unknown_current_kid.jwt.current_kid = Some("unknown".to_string());
+        assert!(load_jwt_keys(&unknown_current_kid).is_err());
+
+        let mut duplicate_kid = rotation_config();
+        duplicate_kid
+            .jwt
+            .keys
+            .push(hs256_key("old", "another-secret"));
+        assert!(load_jwt_keys(&duplicate_kid).is_err());
+
+        let mut missing_secret = rotation_config();
+        missing_secret.jwt.keys[1].secret = None;
+        assert!(load_jwt_keys(&missing_secret).is_err());
+
+        let mut missing_public_key = rotation_config();
+        missing_public_key.jwt.keys[1].algorithm = JwtAlgorithm::EdDSA;
+        assert!(load_jwt_keys(&missing_public_key).is_err());
+    }
+
+    #[test]
+    fn verifies_each_kid_with_its_own_key() {
+        let keys = load_jwt_keys(&rotation_config()).unwrap();
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("old".to_string());
+        assert!(keys
+            .decode::<Claims>(&sign(header.clone(), "old-secret"), Validation::default())
+            .is_ok());
+        // 以其他金鑰簽章卻宣稱是 old 的 token
+        assert!(keys
+            .decode::<Claims>(&sign(header, "new-secret"), Validation::default())
+            .is_err());
+
+        let mut unknown = Header::new(Algorithm::HS256);
+        unknown.kid = Some("unknown".to_string());
+        assert!(keys
+            .decode::<Claims>(&sign(unknown, "old-secret"), Validation::default())
+            .is_err());
+    }
+
+    #[test]
+    fn pins_the_algorithm_of_each_kid() {
+        let keys = load_jwt_keys(&rotation_config()).unwrap();
+
+        // 密鑰正確，但演算法與 old 設定的 HS256 不同
+        let mut header = Header::new(Algorithm::HS512);
+        header.kid = Some("old".to_string());
+        let token = sign(header, "old-secret");
+
+        let mut validation = Validation::default();
+        validation.algorithms = vec![Algorithm::HS512];
+        assert_eq!(
+            keys.decode::<Claims>(&token, validation).unwrap_err(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[test]
+    fn logout_accepts_expired_tokens_with_valid_signature() {
+        let keys = load_jwt_keys(&rotation_config()).unwrap();
+        let expired = Claims {
+            exp: (Utc::now() - Duration::from_secs(60 * 60)).timestamp(),
+            ..claims()
+        };
+        let token = keys.encode(&expired).unwrap();
+
+        assert!(keys
+            .decode::<Claims>(&token, Validation::default())
+            .is_err());
+        let token_data = keys
+            .decode::<Claims>(&token, ignore_expiry_validation())
+            .unwrap();
+        assert_eq!(token_data.claims.jti, expired.jti);
+
+        // 簽章錯誤的 token 即使不檢查期限也不接受
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("new".to_string());
+        let forged = encode(
+            &header,
+            &expired,
+            &EncodingKey::from_secret("old-secret".as_bytes()),
+        )
+        .unwrap();
+        assert!(keys
+            .decode::<Claims>(&forged, ignore_expiry_validation())
+            .is_err());
+    }
+
+    #[test]
+    fn accepts_tokens_without_kid_only_when_enabled() {
+        let token = sign(Header::new(Algorithm::HS256), "legacy-secret");
+
+        let mut config = rotation_config();
+        config.jwt_secret = Some("legacy-secret".to_string());
+        let keys = load_jwt_keys(&config).unwrap();
+        assert!(keys
+            .decode::<Claims>(&token, Validation::default())
+            .is_err());
+
+        config.jwt.accept_legacy_tokens = true;
+        let keys = load_jwt_keys(&config).unwrap();
+        assert!(keys.decode::<Claims>(&token, Validation::default()).is_ok());
+
+        // 沒有 kid 時也只接受 HS256
+        let token = sign(Header::new(Algorithm::HS512), "legacy-secret");
+        assert!(keys
+            .decode::<Claims>(&token, Validation::default())
+            .is_err());
+    }
 }
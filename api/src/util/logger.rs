@@ -0,0 +1,91 @@
+use crate::config::{LogFileConfig, LogFormat, LogRotation, LoggerConfig};
+use std::fs;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// 日誌輸出到終端機的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleOutput {
+    Stdout,
+    /// 管理指令的結果輸出到標準輸出，日誌改寫到標準錯誤以免混在一起
+    Stderr,
+}
+
+/// 依設定初始化日誌，同時輸出到終端機與（若有設定）輪替的日誌檔。
+///
+/// 有輸出到檔案時會回傳 `WorkerGuard`，需保留到程式結束，否則尚未寫入的日誌會遺失。
+pub fn init_logger(
+    config: &LoggerConfig,
+    console: ConsoleOutput,
+) -> Result<Option<WorkerGuard>, String> {
+    let filter = EnvFilter::try_new(&config.level)
+        .map_err(|e| format!("Invalid logger.level `{}`: {}", config.level, e))?;
+
+    let mut layers = vec![match console {
+        ConsoleOutput::Stdout => fmt_layer(config.format, std::io::stdout, true),
+        ConsoleOutput::Stderr => fmt_layer(config.format, std::io::stderr, true),
+    }];
+
+    let guard = match &config.file {
+        Some(file) => {
+            let (writer, guard) = tracing_appender::non_blocking(file_appender(file)?);
+            layers.push(fmt_layer(config.format, writer, false));
+            Some(guard)
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(layers)
+        .with(filter)
+        .try_init()
+        .map_err(|e| format!("Failed to initialize logger: {}", e))?;
+
+    Ok(guard)
+}
+
+fn file_appender(config: &LogFileConfig) -> Result<RollingFileAppender, String> {
+    let rotation = match config.rotation {
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+
+    // 目錄不存在時 tracing-appender 清理舊檔會失敗，先自行建立
+    fs::create_dir_all(&config.directory)
+        .map_err(|e| format!("Failed to create log directory {}: {}", config.directory, e))?;
+
+    let mut builder = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(&config.prefix);
+    if let Some(max_files) = config.max_files {
+        builder = builder.max_log_files(max_files);
+    }
+
+    builder
+        .build(&config.directory)
+        .map_err(|e| format!("Failed to open log directory {}: {}", config.directory, e))
+}
+
+fn fmt_layer<W>(format: LogFormat, writer: W, ansi: bool) -> BoxedLayer
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+
+    match format {
+        LogFormat::Console => layer.boxed(),
+        LogFormat::Json => layer
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+    }
+}
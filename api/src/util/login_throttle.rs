@@ -0,0 +1,220 @@
+use crate::config::CONFIG;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// 最多同時追蹤的 IP 數量，已滿且沒有過期的紀錄時不再追蹤新的 IP，
+/// 帳號本身的鎖定仍然有效
+const MAX_TRACKED_IPS: usize = 10_000;
+/// 清除過期紀錄的最短間隔，避免紀錄已滿時每次失敗都要掃過整張表
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+static IP_FAILURES: LazyLock<Mutex<IpFailureTable>> =
+    LazyLock::new(|| Mutex::new(IpFailureTable::new(MAX_TRACKED_IPS)));
+
+fn ip_window() -> Duration {
+    Duration::from_secs(CONFIG.auth.login_throttle.ip_window_seconds)
+}
+
+/// 回傳該 IP 還需要等待多久才能再次嘗試登入，未被限制時回傳 `None`。
+pub fn ip_retry_after(ip: &str) -> Option<Duration> {
+    IP_FAILURES.lock().unwrap().retry_after(
+        ip,
+        ip_window(),
+        CONFIG.auth.login_throttle.ip_max_failed_attempts,
+        Instant::now(),
+    )
+}
+
+/// 記錄該 IP 對某個帳號的一次失敗，`account` 為嘗試登入的帳號名稱。
+pub fn record_ip_failure(ip: &str, account: &str) {
+    IP_FAILURES
+        .lock()
+        .unwrap()
+        .record(ip, account, ip_window(), Instant::now());
+}
+
+/// 登入成功後只清除該帳號的失敗次數，對其他帳號的嘗試仍然計入 IP 的限制。
+pub fn clear_ip_failures(ip: &str, account: &str) {
+    IP_FAILURES.lock().unwrap().clear(ip, account);
+}
+
+struct IpFailures {
+    window_start: Instant,
+    /// 各帳號的失敗次數，合計達到上限後整個 IP 都會被限制
+    accounts: HashMap<String, u32>,
+}
+
+impl IpFailures {
+    fn count(&self) -> u32 {
+        self.accounts.values().sum()
+    }
+}
+
+struct IpFailureTable {
+    capacity: usize,
+    entries: HashMap<String, IpFailures>,
+    last_pruned: Option<Instant>,
+}
+
+impl IpFailureTable {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            last_pruned: None,
+        }
+    }
+
+    fn retry_after(
+        &self,
+        ip: &str,
+        window: Duration,
+        max_failed_attempts: u32,
+        now: Instant,
+    ) -> Option<Duration> {
+        let record = self.entries.get(ip)?;
+
+        let elapsed = now.duration_since(record.window_start);
+        if elapsed >= window || record.count() < max_failed_attempts {
+            return None;
+        }
+
+        Some(window - elapsed)
+    }
+
+    fn record(&mut self, ip: &str, account: &str, window: Duration, now: Instant) {
+        if !self.entries.contains_key(ip) && self.entries.len() >= self.capacity {
+            self.prune(window, now);
+            if self.entries.len() >= self.capacity {
+                return;
+            }
+        }
+
+        let record = self.entries.entry(ip.to_string()).or_insert(IpFailures {
+            window_start: now,
+            accounts: HashMap::new(),
+        });
+
+        if now.duration_since(record.window_start) >= window {
+            record.accounts.clear();
+            record.window_start = now;
+        }
+
+        *record.accounts.entry(account.to_string()).or_default() += 1;
+    }
+
+    fn clear(&mut self, ip: &str, account: &str) {
+        let Some(record) = self.entries.get_mut(ip) else {
+            return;
+        };
+
+        record.accounts.remove(account);
+        if record.accounts.is_empty() {
+            self.entries.remove(ip);
+        }
+    }
+
+    fn prune(&mut self, window: Duration, now: Instant) {
+        if self
+            .last_pruned
+            .is_some_and(|last_pruned| now.duration_since(last_pruned) < PRUNE_INTERVAL)
+        {
+            return;
+        }
+
+        self.entries
+            .retain(|_, record| now.duration_since(record.window_start) < window);
+        self.last_pruned = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(15 * 60);
+
+    #[test]
+    fn limits_ip_after_max_failures() {
+        let mut table = IpFailureTable::new(10);
+        let now = Instant::now();
+        for _ in 0..3 {
+            table.record("192.0.2.1", "alice", WINDOW, now);
+        }
+
+        assert!(table.retry_after("192.0.2.1", WINDOW, 3, now).is_some());
+        assert!(table.retry_after("192.0.2.1", WINDOW, 4, now).is_none());
+        assert!(table.retry_after("192.0.2.2", WINDOW, 3, now).is_none());
+    }
+
+    #[test]
+    fn successful_login_only_clears_that_account() {
+        let mut table = IpFailureTable::new(10);
+        let now = Instant::now();
+        table.record("192.0.2.1", "alice", WINDOW, now);
+        table.record("192.0.2.1", "bob", WINDOW, now);
+        table.record("192.0.2.1", "bob", WINDOW, now);
+
+        table.clear("192.0.2.1", "mallory");
+        assert!(table.retry_after("192.0.2.1", WINDOW, 3, now).is_some());
+
+        table.clear("192.0.2.1", "alice");
+        assert!(table.retry_after("192.0.2.1", WINDOW, 3, now).is_none());
+        assert!(table.retry_after("192.0.2.1", WINDOW, 2, now).is_some());
+    }
+
+    #[test]
+    fn stops_tracking_new_ips_when_full() {
+        let mut table = IpFailureTable::new(2);
+        let now = Instant::now();
+        table.record("192.0.2.1", "alice", WINDOW, now);
+        table.record("192.0.2.2", "alice", WINDOW, now);
+        table.record("192.0.2.3", "alice", WINDOW, now);
+
+        assert_eq!(table.entries.len(), 2);
+        assert!(!table.entries.contains_key("192.0.2.3"));
+
+        // 已追蹤的 IP 仍然繼續計算
+        table.record("192.0.2.1", "alice", WINDOW, now);
+        assert!(table.retry_after("192.0.2.1", WINDOW, 2, now).is_some());
+    }
+
+    #[test]
+    fn prunes_expired_ips_when_full() {
+        let mut table = IpFailureTable::new(2);
+        let start = Instant::now();
+        table.record("192.0.2.1", "alice", WINDOW, start);
+        table.record("192.0.2.2", "alice", WINDOW, start);
+
+        let later = start + WINDOW;
+        table.record("192.0.2.3", "alice", WINDOW, later);
+
+        assert_eq!(table.entries.len(), 1);
+        assert!(table.entries.contains_key("192.0.2.3"));
+    }
+
+    #[test]
+    fn prunes_at_most_once_per_interval() {
+        let mut table = IpFailureTable::new(1);
+        let start = Instant::now();
+        table.record("192.0.2.1", "alice", WINDOW, start);
+
+        // 第一次清除時還沒有過期的紀錄
+        table.record(
+            "192.0.2.2",
+            "alice",
+            WINDOW,
+            start + WINDOW - Duration::from_secs(1),
+        );
+        assert!(!table.entries.contains_key("192.0.2.2"));
+
+        // 紀錄已過期，但距離上次清除還不到間隔，不會再掃一次
+        let expired = start + WINDOW;
+        table.record("192.0.2.3", "alice", WINDOW, expired);
+        assert!(!table.entries.contains_key("192.0.2.3"));
+
+        table.record("192.0.2.3", "alice", WINDOW, expired + PRUNE_INTERVAL);
+        assert!(table.entries.contains_key("192.0.2.3"));
+    }
+}
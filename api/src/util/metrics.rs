@@ -0,0 +1,59 @@
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+const LOGINS_TOTAL: &str = "logins_total";
+
+const HTTP_DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+static METRICS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// 安裝 Prometheus recorder，未呼叫時下面記錄指標的函式都不會有作用。
+pub fn init_metrics() -> Result<(), String> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()),
+            HTTP_DURATION_BUCKETS,
+        )
+        .map_err(|e| format!("Failed to configure metrics: {}", e))?
+        .install_recorder()
+        .map_err(|e| format!("Failed to install metrics recorder: {}", e))?;
+
+    METRICS_HANDLE
+        .set(handle)
+        .map_err(|_| "Metrics have already been initialized".to_string())
+}
+
+/// 以 Prometheus 文字格式輸出目前的指標。
+pub fn render_metrics() -> Option<String> {
+    METRICS_HANDLE.get().map(|handle| {
+        handle.run_upkeep();
+        handle.render()
+    })
+}
+
+/// `route` 使用路由的樣板（例如 `/api/students/{id}`），避免每個 id 都產生一組指標。
+pub fn record_http_request(method: &str, route: &str, status: u16, duration: Duration) {
+    let labels = [
+        ("method", method.to_string()),
+        ("route", route.to_string()),
+        ("status", status.to_string()),
+    ];
+
+    counter!(HTTP_REQUESTS_TOTAL, &labels).increment(1);
+    histogram!(HTTP_REQUEST_DURATION_SECONDS, &labels).record(duration.as_secs_f64());
+}
+
+/// `account` 為 `teacher` 或 `guardian`，`result` 為 `success`、`failure` 或 `lockout`。
+pub fn record_login(account: &'static str, result: &'static str) {
+    counter!(LOGINS_TOTAL, "account" => account, "result" => result).increment(1);
+}
+
+pub fn set_gauge(name: &'static str, value: f64) {
+    gauge!(name).set(value);
+}
@@ -1,3 +1,21 @@
+mod client;
+mod csrf;
 mod jwt;
+mod logger;
+mod login_throttle;
+mod metrics;
+mod oidc;
+mod password;
+mod token;
+mod totp;
 
-pub use jwt::*;
\ No newline at end of file
+pub use client::*;
+pub use csrf::*;
+pub use jwt::*;
+pub use logger::*;
+pub use login_throttle::*;
+pub use metrics::*;
+pub use oidc::*;
+pub use password::*;
+pub use token::*;
+pub use totp::*;
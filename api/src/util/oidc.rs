@@ -0,0 +1,210 @@
+use crate::config::OidcConfig;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
+
+/// 身分提供者只能以非對稱金鑰簽署 ID token，避免以 client secret 偽造的 HS256 token
+const ALLOWED_ID_TOKEN_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to build HTTP client")
+});
+
+/// discovery 文件在程式執行期間不會變動，依 issuer 在第一次使用時取得後快取
+static PROVIDER_METADATA: LazyLock<RwLock<HashMap<String, Arc<ProviderMetadata>>>> =
+    LazyLock::new(Default::default);
+
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// 從 ID token 中取出、用來對應教職員的欄位。
+#[derive(Debug, Deserialize)]
+pub struct OidcIdentity {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    nonce: Option<String>,
+}
+
+/// 以 S256 方法由 code verifier 產生 PKCE 的 code challenge。
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// 組出跳轉到身分提供者的授權網址。
+pub async fn oidc_authorization_url(
+    config: &OidcConfig,
+    state: &str,
+    nonce: &str,
+    code_verifier: &str,
+) -> Result<String, String> {
+    let metadata = provider_metadata(config).await?;
+
+    let mut url = Url::parse(&metadata.authorization_endpoint)
+        .map_err(|e| format!("Invalid authorization endpoint: {}", e))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &config.client_id)
+        .append_pair("redirect_uri", &config.redirect_uri)
+        .append_pair("scope", &config.scopes.join(" "))
+        .append_pair("state", state)
+        .append_pair("nonce", nonce)
+        .append_pair("code_challenge", &pkce_challenge(code_verifier))
+        .append_pair("code_challenge_method", "S256");
+
+    Ok(url.into())
+}
+
+/// 以授權碼換發 token，並驗證 ID token 的簽章、issuer、audience 與 nonce。
+pub async fn exchange_oidc_code(
+    config: &OidcConfig,
+    code: &str,
+    code_verifier: &str,
+    nonce: &str,
+) -> Result<OidcIdentity, String> {
+    let metadata = provider_metadata(config).await?;
+
+    let response = HTTP_CLIENT
+        .post(&metadata.token_endpoint)
+        .basic_auth(&config.client_id, Some(&config.client_secret))
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.redirect_uri.as_str()),
+            ("code_verifier", code_verifier),
+        ])
+        .send()
+        .await
+        .map_err(|e| format!("Token request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Token endpoint returned {}: {}", status, body));
+    }
+
+    let token_response: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid token response: {}", e))?;
+
+    let identity = verify_id_token(config, &metadata, &token_response.id_token).await?;
+
+    if identity.nonce.as_deref() != Some(nonce) {
+        return Err("ID token nonce mismatch".to_string());
+    }
+
+    Ok(identity)
+}
+
+async fn verify_id_token(
+    config: &OidcConfig,
+    metadata: &ProviderMetadata,
+    id_token: &str,
+) -> Result<OidcIdentity, String> {
+    let header = decode_header(id_token).map_err(|e| format!("Invalid ID token: {}", e))?;
+    if !ALLOWED_ID_TOKEN_ALGORITHMS.contains(&header.alg) {
+        return Err(format!("Unsupported ID token algorithm: {:?}", header.alg));
+    }
+
+    // 每次登入都重新取得 JWKS，身分提供者輪替金鑰後不需要重新啟動
+    let jwks: JwkSet = HTTP_CLIENT
+        .get(&metadata.jwks_uri)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("JWKS request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid JWKS: {}", e))?;
+
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .ok_or_else(|| "No matching key in JWKS".to_string())?;
+
+    let decoding_key = DecodingKey::from_jwk(jwk).map_err(|e| format!("Invalid JWK: {}", e))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[&metadata.issuer]);
+    validation.set_audience(&[&config.client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    decode::<OidcIdentity>(id_token, &decoding_key, &validation)
+        .map(|token_data| token_data.claims)
+        .map_err(|e| format!("ID token validation failed: {}", e))
+}
+
+async fn provider_metadata(config: &OidcConfig) -> Result<Arc<ProviderMetadata>, String> {
+    if let Some(metadata) = PROVIDER_METADATA.read().unwrap().get(&config.issuer) {
+        return Ok(metadata.clone());
+    }
+
+    // 同時有多個請求時可能會重複取得，結果相同，不影響正確性
+    let metadata = Arc::new(fetch_provider_metadata(config).await?);
+    PROVIDER_METADATA
+        .write()
+        .unwrap()
+        .insert(config.issuer.clone(), metadata.clone());
+
+    Ok(metadata)
+}
+
+async fn fetch_provider_metadata(config: &OidcConfig) -> Result<ProviderMetadata, String> {
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        config.issuer.trim_end_matches('/')
+    );
+
+    let metadata: ProviderMetadata = HTTP_CLIENT
+        .get(&url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Discovery request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid discovery document: {}", e))?;
+
+    if metadata.issuer != config.issuer {
+        return Err(format!(
+            "Issuer mismatch: expected {}, got {}",
+            config.issuer, metadata.issuer
+        ));
+    }
+
+    Ok(metadata)
+}
@@ -0,0 +1,303 @@
+use crate::config::{PasswordAlgorithm, PasswordHashingConfig, PasswordPolicyConfig, CONFIG};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+
+/// 常見的弱密碼，比對時忽略大小寫
+const COMMON_PASSWORDS: &[&str] = &[
+    "password",
+    "password1",
+    "password123",
+    "passw0rd",
+    "p@ssw0rd",
+    "p@ssword",
+    "123456",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "1qaz2wsx",
+    "1q2w3e4r",
+    "qwerty",
+    "qwerty123",
+    "qwertyuiop",
+    "asdfghjkl",
+    "abc123",
+    "abcd1234",
+    "aa123456",
+    "iloveyou",
+    "welcome",
+    "welcome1",
+    "welcome123",
+    "admin",
+    "admin123",
+    "administrator",
+    "letmein",
+    "changeme",
+    "trustno1",
+    "football",
+    "baseball",
+    "sunshine",
+    "princess",
+    "dragon",
+    "monkey",
+    "jesus",
+    "jesuslovesme",
+    "god",
+    "godisgood",
+    "church",
+    "church123",
+];
+
+/// 依設定的演算法與參數雜湊密碼。
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let config = &CONFIG.auth.password_hashing;
+
+    match config.algorithm {
+        PasswordAlgorithm::Argon2id => {
+            let salt = SaltString::generate(&mut OsRng);
+            argon2_hasher(config)?
+                .hash_password(password.as_bytes(), &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|e| format!("Failed to hash password: {}", e))
+        }
+        PasswordAlgorithm::Bcrypt => bcrypt::hash(password, config.bcrypt_cost)
+            .map_err(|e| format!("Failed to hash password: {}", e)),
+    }
+}
+
+/// 依雜湊值的格式選擇演算法驗證密碼，同時支援 argon2 與舊的 bcrypt 雜湊。
+pub fn verify_password(password: &str, password_hash: &str) -> Result<bool, String> {
+    if password_hash.starts_with("$argon2") {
+        let parsed = PasswordHash::new(password_hash)
+            .map_err(|e| format!("Invalid password hash: {}", e))?;
+
+        // 驗證時使用雜湊值中記錄的參數，而不是目前的設定
+        return Ok(Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok());
+    }
+
+    bcrypt::verify(password, password_hash).map_err(|e| format!("Invalid password hash: {}", e))
+}
+
+/// 雜湊值的演算法或參數與目前設定不同時，登入成功後應重新雜湊。
+pub fn password_needs_rehash(password_hash: &str) -> bool {
+    needs_rehash(&CONFIG.auth.password_hashing, password_hash)
+}
+
+fn needs_rehash(config: &PasswordHashingConfig, password_hash: &str) -> bool {
+    match config.algorithm {
+        PasswordAlgorithm::Argon2id => {
+            let Ok(parsed) = PasswordHash::new(password_hash) else {
+                return true;
+            };
+            let Ok(params) = Params::try_from(&parsed) else {
+                return true;
+            };
+
+            parsed.algorithm != Algorithm::Argon2id.ident()
+                || parsed.version != Some(Version::V0x13.into())
+                || params.m_cost() != config.argon2_memory_kib
+                || params.t_cost() != config.argon2_iterations
+                || params.p_cost() != config.argon2_parallelism
+        }
+        // bcrypt 雜湊的格式為 `$2b$<cost>$...`
+        PasswordAlgorithm::Bcrypt => password_hash
+            .split('$')
+            .nth(2)
+            .and_then(|cost| cost.parse::<u32>().ok())
+            .is_none_or(|cost| cost != config.bcrypt_cost),
+    }
+}
+
+/// 依密碼政策檢查密碼強度，`user_inputs` 為帳號名稱等不應出現在密碼中的字串。
+pub fn check_password_strength(password: &str, user_inputs: &[&str]) -> Result<(), String> {
+    check_strength(&CONFIG.auth.password_policy, password, user_inputs)
+}
+
+fn check_strength(
+    policy: &PasswordPolicyConfig,
+    password: &str,
+    user_inputs: &[&str],
+) -> Result<(), String> {
+    let length = password.chars().count();
+    if length < policy.min_length {
+        return Err(format!("密碼至少需要{}個字元", policy.min_length));
+    }
+    if length > policy.max_length {
+        return Err(format!("密碼不能超過{}個字元", policy.max_length));
+    }
+
+    let character_classes = [
+        password.chars().any(|c| c.is_lowercase()),
+        password.chars().any(|c| c.is_uppercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .into_iter()
+    .filter(|has_class| *has_class)
+    .count();
+    if character_classes < policy.min_character_classes {
+        return Err(format!(
+            "密碼需包含小寫字母、大寫字母、數字與符號中的至少{}種",
+            policy.min_character_classes
+        ));
+    }
+
+    let lowercase = password.to_lowercase();
+    if policy.reject_common
+        && COMMON_PASSWORDS.iter().any(|common| {
+            // 常見密碼後面加上數字或符號湊長度，仍視為弱密碼
+            lowercase
+                .strip_prefix(common)
+                .is_some_and(|rest| rest.chars().all(|c| !c.is_alphabetic()))
+        })
+    {
+        return Err("密碼過於常見，請換一個".to_string());
+    }
+
+    if policy.reject_username
+        && user_inputs
+            .iter()
+            .map(|input| input.trim().to_lowercase())
+            .any(|input| input.chars().count() >= 3 && lowercase.contains(&input))
+    {
+        return Err("密碼不能包含帳號名稱".to_string());
+    }
+
+    Ok(())
+}
+
+fn argon2_hasher(config: &PasswordHashingConfig) -> Result<Argon2<'static>, String> {
+    let params = Params::new(
+        config.argon2_memory_kib,
+        config.argon2_iterations,
+        config.argon2_parallelism,
+        None,
+    )
+    .map_err(|e| format!("Invalid argon2 parameters: {}", e))?;
+
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 測試用的小參數，避免每次雜湊都要花上數十毫秒
+    fn hashing_config(algorithm: PasswordAlgorithm) -> PasswordHashingConfig {
+        PasswordHashingConfig {
+            algorithm,
+            argon2_memory_kib: 64,
+            argon2_iterations: 1,
+            argon2_parallelism: 1,
+            bcrypt_cost: 4,
+        }
+    }
+
+    fn argon2_hash(config: &PasswordHashingConfig) -> String {
+        argon2_hasher(config)
+            .unwrap()
+            .hash_password(b"Blue-Harbor-71", &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn accepts_strong_password() {
+        let policy = PasswordPolicyConfig::default();
+        assert!(check_strength(&policy, "Blue-Harbor-71", &["teacher1"]).is_ok());
+        // 長度以字元計算，不是位元組
+        assert!(check_strength(&policy, "主日學Blue-71", &[]).is_ok());
+    }
+
+    #[test]
+    fn rejects_weak_passwords() {
+        let policy = PasswordPolicyConfig::default();
+
+        assert!(check_strength(&policy, "Short-1", &[]).is_err());
+        assert!(check_strength(&policy, &"Aa1-".repeat(33), &[]).is_err());
+        assert!(check_strength(&policy, "lowercaseonly", &[]).is_err());
+        assert!(check_strength(&policy, "Password123!", &[]).is_err());
+        assert!(check_strength(&policy, "Welcome1!!!", &[]).is_err());
+        assert!(check_strength(&policy, "Teacher1-Harbor", &["teacher1"]).is_err());
+        assert!(check_strength(&policy, "Harbor-TEACHER1", &[" teacher1 "]).is_err());
+    }
+
+    #[test]
+    fn policy_switches_are_respected() {
+        let policy = PasswordPolicyConfig {
+            min_character_classes: 1,
+            reject_common: false,
+            reject_username: false,
+            ..PasswordPolicyConfig::default()
+        };
+
+        assert!(check_strength(&policy, "password123", &[]).is_ok());
+        assert!(check_strength(&policy, "teacher1longer", &["teacher1"]).is_ok());
+        // 太短的帳號名稱不比對，避免誤判
+        let policy = PasswordPolicyConfig::default();
+        assert!(check_strength(&policy, "Blue-Harbor-71", &["ha"]).is_ok());
+    }
+
+    #[test]
+    fn argon2_hash_with_current_params_is_kept() {
+        let config = hashing_config(PasswordAlgorithm::Argon2id);
+        assert!(!needs_rehash(&config, &argon2_hash(&config)));
+    }
+
+    #[test]
+    fn rehashes_when_params_or_algorithm_change() {
+        let config = hashing_config(PasswordAlgorithm::Argon2id);
+        let hash = argon2_hash(&config);
+
+        for changed in [
+            PasswordHashingConfig {
+                argon2_memory_kib: 128,
+                ..hashing_config(PasswordAlgorithm::Argon2id)
+            },
+            PasswordHashingConfig {
+                argon2_iterations: 2,
+                ..hashing_config(PasswordAlgorithm::Argon2id)
+            },
+            PasswordHashingConfig {
+                argon2_parallelism: 2,
+                ..hashing_config(PasswordAlgorithm::Argon2id)
+            },
+            hashing_config(PasswordAlgorithm::Bcrypt),
+        ] {
+            assert!(needs_rehash(&changed, &hash));
+        }
+
+        let bcrypt_hash = bcrypt::hash("Blue-Harbor-71", 4).unwrap();
+        assert!(needs_rehash(&config, &bcrypt_hash));
+        assert!(needs_rehash(&config, "not a hash"));
+    }
+
+    #[test]
+    fn bcrypt_rehashes_only_when_cost_changes() {
+        let config = hashing_config(PasswordAlgorithm::Bcrypt);
+        let hash = bcrypt::hash("Blue-Harbor-71", 4).unwrap();
+
+        assert!(!needs_rehash(&config, &hash));
+        assert!(needs_rehash(
+            &PasswordHashingConfig {
+                bcrypt_cost: 5,
+                ..config.clone()
+            },
+            &hash
+        ));
+        assert!(needs_rehash(&config, "not a hash"));
+    }
+
+    #[test]
+    fn verifies_argon2_and_bcrypt_hashes() {
+        let argon2 = argon2_hash(&hashing_config(PasswordAlgorithm::Argon2id));
+        assert!(verify_password("Blue-Harbor-71", &argon2).unwrap());
+        assert!(!verify_password("Blue-Harbor-72", &argon2).unwrap());
+
+        let bcrypt = bcrypt::hash("Blue-Harbor-71", 4).unwrap();
+        assert!(verify_password("Blue-Harbor-71", &bcrypt).unwrap());
+        assert!(!verify_password("Blue-Harbor-72", &bcrypt).unwrap());
+    }
+}
@@ -0,0 +1,64 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::{Rng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// 產生隨機的不透明 token，用於 refresh token 等只需比對雜湊值的憑證。
+pub fn generate_opaque_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// 排除容易混淆的 0/O、1/I 等字元，方便口頭或手寫轉交
+const RESET_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const RESET_CODE_LENGTH: usize = 10;
+
+/// 產生一次性的密碼重設碼或兩步驟驗證復原碼，格式為 `XXXXX-XXXXX`。
+pub fn generate_reset_code() -> String {
+    let mut rng = rand::rng();
+    let chars: String = (0..RESET_CODE_LENGTH)
+        .map(|_| RESET_CODE_ALPHABET[rng.random_range(0..RESET_CODE_ALPHABET.len())] as char)
+        .collect();
+
+    format!(
+        "{}-{}",
+        &chars[..RESET_CODE_LENGTH / 2],
+        &chars[RESET_CODE_LENGTH / 2..]
+    )
+}
+
+/// 排除容易混淆的字元，並混合大小寫字母與數字
+const TEMPORARY_PASSWORD_ALPHABET: &[u8] =
+    b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnpqrstuvwxyz23456789";
+
+/// 產生管理指令使用的臨時密碼，至少 `length` 個字元，每 4 個字元以連字號分隔方便抄寫。
+pub fn generate_temporary_password(length: usize) -> String {
+    let mut rng = rand::rng();
+    let groups: Vec<String> = (0..length.div_ceil(4))
+        .map(|_| {
+            (0..4)
+                .map(|_| {
+                    TEMPORARY_PASSWORD_ALPHABET
+                        [rng.random_range(0..TEMPORARY_PASSWORD_ALPHABET.len())]
+                        as char
+                })
+                .collect()
+        })
+        .collect();
+
+    groups.join("-")
+}
+
+/// 將使用者輸入的重設碼正規化，忽略大小寫、空白與連字號。
+pub fn normalize_reset_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// 資料庫只保存 token 的 SHA-256 雜湊值，避免資料外洩時可直接被使用。
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
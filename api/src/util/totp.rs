@@ -0,0 +1,133 @@
+use crate::config::CONFIG;
+use chrono::Utc;
+use rand::RngCore;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP_SECONDS: u64 = 30;
+/// 允許前後各一個時間區間的時鐘誤差
+const TOTP_SKEW_STEPS: u64 = 1;
+
+/// 產生 160 位元的 TOTP 金鑰，以 Base32 編碼保存與顯示。
+pub fn generate_totp_secret() -> String {
+    let mut bytes = vec![0u8; 20];
+    rand::rng().fill_bytes(&mut bytes);
+    Secret::Raw(bytes).to_encoded().to_string()
+}
+
+fn build_totp(secret: &str, issuer: Option<&str>, account_name: &str) -> Option<TOTP> {
+    let secret = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+
+    // otpauth URI 以冒號分隔發行者與帳號，名稱中不能出現冒號
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        0,
+        TOTP_STEP_SECONDS,
+        secret,
+        issuer.map(|issuer| issuer.replace(':', " ")),
+        account_name.replace(':', " "),
+    )
+    .ok()
+}
+
+/// 產生驗證器 App 掃描 QR code 用的 `otpauth://` URI。
+pub fn totp_otpauth_uri(secret: &str, account_name: &str) -> Option<String> {
+    build_totp(secret, Some(&CONFIG.auth.two_factor.issuer), account_name)
+        .map(|totp| totp.get_url())
+}
+
+/// 驗證 TOTP 驗證碼，成功時回傳驗證碼所屬的時間區間。
+///
+/// 早於或等於 `last_used_step` 的區間一律視為無效，避免同一組驗證碼被重複使用。
+pub fn verify_totp_code(secret: &str, code: &str, last_used_step: Option<i64>) -> Option<i64> {
+    verify_totp_code_at(
+        secret,
+        code,
+        last_used_step,
+        Utc::now().timestamp().max(0) as u64,
+    )
+}
+
+fn verify_totp_code_at(
+    secret: &str,
+    code: &str,
+    last_used_step: Option<i64>,
+    timestamp: u64,
+) -> Option<i64> {
+    let totp = build_totp(secret, None, "")?;
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    let current_step = timestamp / TOTP_STEP_SECONDS;
+
+    (current_step.saturating_sub(TOTP_SKEW_STEPS)..=current_step + TOTP_SKEW_STEPS)
+        .map(|step| step as i64)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| totp.check(&code, *step as u64 * TOTP_STEP_SECONDS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 附錄 B 的金鑰 `12345678901234567890`（SHA1），以 Base32 編碼
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn matches_rfc_6238_test_vectors() {
+        // RFC 的驗證碼為 8 位數，6 位數的驗證碼是其末 6 位
+        let vectors = [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+            (20000000000, "353130"),
+        ];
+
+        for (timestamp, code) in vectors {
+            assert_eq!(
+                verify_totp_code_at(RFC_SECRET, code, None, timestamp),
+                Some((timestamp / TOTP_STEP_SECONDS) as i64),
+                "T = {}",
+                timestamp
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_one_step_of_clock_skew() {
+        // T = 59 的驗證碼屬於第 1 個區間
+        assert_eq!(verify_totp_code_at(RFC_SECRET, "287082", None, 29), Some(1));
+        assert_eq!(verify_totp_code_at(RFC_SECRET, "287082", None, 89), Some(1));
+        assert_eq!(verify_totp_code_at(RFC_SECRET, "287082", None, 90), None);
+        assert_eq!(
+            verify_totp_code_at(RFC_SECRET, "287 082", None, 59),
+            Some(1)
+        );
+        assert_eq!(verify_totp_code_at(RFC_SECRET, "287083", None, 59), None);
+    }
+
+    #[test]
+    fn rejects_codes_from_used_steps() {
+        let step = verify_totp_code_at(RFC_SECRET, "287082", None, 59).unwrap();
+
+        // 同一區間的驗證碼不能再使用，較早區間的驗證碼也一樣
+        assert_eq!(
+            verify_totp_code_at(RFC_SECRET, "287082", Some(step), 59),
+            None
+        );
+        assert_eq!(
+            verify_totp_code_at(RFC_SECRET, "287082", Some(step), 89),
+            None
+        );
+        assert_eq!(
+            verify_totp_code_at(RFC_SECRET, "287082", Some(step - 1), 59),
+            Some(step)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_secrets() {
+        assert_eq!(verify_totp_code_at("not base32!", "287082", None, 59), None);
+    }
+}
@@ -1,6 +1,6 @@
 'use client';
 
-import { useState } from 'react';
+import { useEffect, useState } from 'react';
 import { useRouter, useSearchParams } from 'next/navigation';
 import { toast } from "sonner";
 import { Button } from "@/components/ui/button";
@@ -9,19 +9,50 @@ import { Label } from "@/components/ui/label";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { authService } from '@/lib/api/client-auth';
 
+const OIDC_ENABLED = process.env.NEXT_PUBLIC_OIDC_ENABLED === 'true';
+
+const OIDC_ERROR_MESSAGES: Record<string, string> = {
+  cancelled: "已取消登入",
+  invalid_state: "登入逾時，請重新登入",
+  provider_error: "無法向身分提供者完成驗證",
+  not_linked: "此帳號尚未綁定教職員，請聯絡管理員",
+  locked: "帳號已暫時鎖定，請稍後再試",
+  server_error: "伺服器發生異常",
+};
+
 export default function LoginForm() {
   const [username, setUsername] = useState('');
   const [password, setPassword] = useState('');
-  const [isLoading, setIsLoading] = useState(false);
+  const [twoFactorCode, setTwoFactorCode] = useState('');
   const router = useRouter();
   const searchParams = useSearchParams();
+  // 透過身分提供者登入後，若帳號啟用兩步驟驗證，後端會導回登入頁並帶上 two_factor 參數
+  const [isTwoFactorStep, setIsTwoFactorStep] = useState(searchParams.get('two_factor') === 'required');
+  const [isLoading, setIsLoading] = useState(false);
+
+  useEffect(() => {
+    const error = searchParams.get('error');
+    if (error) {
+      toast.error("登入失敗", {
+        description: OIDC_ERROR_MESSAGES[error] ?? "請重新登入",
+      });
+    }
+  }, [searchParams]);
 
   const handleLogin = async (e: React.FormEvent) => {
     e.preventDefault();
     setIsLoading(true);
 
     try {
-      await authService.login(username, password);
+      const result = isTwoFactorStep
+        ? await authService.verifyTwoFactor(twoFactorCode)
+        : await authService.login(username, password);
+
+      if (result.two_factor_required) {
+        setIsTwoFactorStep(true);
+        return;
+      }
+
       toast.success("登入成功", {
         description: "正在為您導向頁面...",
       });
@@ -49,31 +80,49 @@ export default function LoginForm() {
 
       <CardContent>
         <form onSubmit={handleLogin} className="space-y-6">
-          <div className="space-y-2">
-            <Label htmlFor="username">使用者名稱</Label>
-            <Input
-              id="username"
-              type="text"
-              value={username}
-              onChange={(e) => setUsername(e.target.value)}
-              placeholder="請輸入使用者名稱"
-              required
-              disabled={isLoading}
-            />
-          </div>
-
-          <div className="space-y-2">
-            <Label htmlFor="password">密碼</Label>
-            <Input
-              id="password"
-              type="password"
-              value={password}
-              onChange={(e) => setPassword(e.target.value)}
-              placeholder="請輸入密碼"
-              required
-              disabled={isLoading}
-            />
-          </div>
+          {isTwoFactorStep ? (
+            <div className="space-y-2">
+              <Label htmlFor="two-factor-code">驗證碼</Label>
+              <Input
+                id="two-factor-code"
+                type="text"
+                autoComplete="one-time-code"
+                value={twoFactorCode}
+                onChange={(e) => setTwoFactorCode(e.target.value)}
+                placeholder="請輸入驗證器 App 的 6 位數驗證碼或復原碼"
+                required
+                disabled={isLoading}
+              />
+            </div>
+          ) : (
+            <>
+              <div className="space-y-2">
+                <Label htmlFor="username">使用者名稱</Label>
+                <Input
+                  id="username"
+                  type="text"
+                  value={username}
+                  onChange={(e) => setUsername(e.target.value)}
+                  placeholder="請輸入使用者名稱"
+                  required
+                  disabled={isLoading}
+                />
+              </div>
+
+              <div className="space-y-2">
+                <Label htmlFor="password">密碼</Label>
+                <Input
+                  id="password"
+                  type="password"
+                  value={password}
+                  onChange={(e) => setPassword(e.target.value)}
+                  placeholder="請輸入密碼"
+                  required
+                  disabled={isLoading}
+                />
+              </div>
+            </>
+          )}
 
           <Button
             type="submit"
@@ -82,6 +131,35 @@ export default function LoginForm() {
           >
             {isLoading ? '登入中...' : '登入'}
           </Button>
+
+          {!isTwoFactorStep && OIDC_ENABLED && (
+            <Button
+              type="button"
+              variant="outline"
+              className="w-full"
+              disabled={isLoading}
+              onClick={() => {
+                window.location.href = `${process.env.NEXT_PUBLIC_API_URL}/api/login/oidc`;
+              }}
+            >
+              使用教會帳號登入
+            </Button>
+          )}
+
+          {isTwoFactorStep && (
+            <Button
+              type="button"
+              variant="ghost"
+              className="w-full"
+              disabled={isLoading}
+              onClick={() => {
+                setIsTwoFactorStep(false);
+                setTwoFactorCode('');
+              }}
+            >
+              返回重新輸入密碼
+            </Button>
+          )}
         </form>
       </CardContent>
     </Card>
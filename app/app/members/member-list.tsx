@@ -5,7 +5,7 @@ import { GenericDataTable } from '@/components/generic_table/generic-data-table'
 import { useCrud } from '@/hooks/use-crud';
 import { Member, memberColumns, memberSchema, MemberSchemaShape, MemberUpsertRequest } from '@/types/member';
 import { createFormStore } from '@/stores/form-store';
-import { Me } from '@/types/me';
+import { hasPermission, Me } from '@/types/me';
 import { API_PATH } from '@/lib/api/common';
 
 export function MemberList({ me }: { me: Me | null }) {
@@ -36,15 +36,11 @@ export function MemberList({ me }: { me: Me | null }) {
   const permissionConfig = {
     canEdit: (member: Member) => {
       if (!me) return false;
-      if (me.role === 'super_admin') return true;
-      return me.role === 'admin' && me.id === member.id;
+      return hasPermission(me, 'members.write') || me.id === member.id;
     },
     canDelete: (member: Member) => {
       if (!me) return false;
-      return (
-        (me.role === 'admin' && me.id === member.id) ||
-        (me.role === 'super_admin' && me.id !== member.id)
-      );
+      return hasPermission(me, 'members.write') && me.id !== member.id;
     }
   };
 
@@ -5,7 +5,7 @@ import { GenericDataTable } from '@/components/generic_table/generic-data-table'
 import { Student, studentColumns, studentSchema, StudentSchemaShape, StudentUpsertReq } from '@/types/student';
 import { useCrud } from '@/hooks/use-crud';
 import { createFormStore } from '@/stores/form-store';
-import { Me } from '@/types/me';
+import { hasPermission, Me } from '@/types/me';
 import { API_PATH } from '@/lib/api/common';
 
 export function StudentList({ me }: { me: Me | null }) {
@@ -35,12 +35,10 @@ export function StudentList({ me }: { me: Me | null }) {
 
   const permissionConfig = {
     canEdit: useCallback(() => {
-      if (!me) return false;
-      return me.role === 'super_admin' || me.role === 'admin';
+      return hasPermission(me, 'students.write');
     }, [me]),
     canDelete: useCallback(() => {
-      if (!me) return false;
-      return me.role === 'super_admin' || me.role === 'admin';
+      return hasPermission(me, 'students.write');
     }, [me])
   };
 
@@ -5,7 +5,7 @@ import { Teacher, teacherColumns, teacherSchema, TeacherSchemaShape, TeacherUpse
 import { GenericDataTable } from '@/components/generic_table/generic-data-table';
 import { useCrud } from '@/hooks/use-crud';
 import { createFormStore } from '@/stores/form-store';
-import { Me } from '@/types/me';
+import { hasPermission, Me } from '@/types/me';
 import { API_PATH } from '@/lib/api/common';
 
 export function TeacherList({ me }: { me: Me | null }) {
@@ -35,15 +35,11 @@ export function TeacherList({ me }: { me: Me | null }) {
   const permissionConfig = {
     canEdit: (teacher: Teacher) => {
       if (!me) return false;
-      if (me.role === 'super_admin') return true;
-      return me.role === 'admin' && me.id === teacher.id;
+      return hasPermission(me, 'teachers.manage') || me.id === teacher.id;
     },
     canDelete: (teacher: Teacher) => {
       if (!me) return false;
-      return (
-        (me.role === 'admin' && me.id === teacher.id) ||
-        (me.role === 'super_admin' && me.id !== teacher.id)
-      );
+      return hasPermission(me, 'teachers.manage') && me.id !== teacher.id;
     }
   };
 
@@ -9,7 +9,7 @@ import { Announcement, announcementSchema, AnnouncementSchemaShape, announcement
 import { createFormStore } from "@/stores/form-store";
 import { useCrud } from "@/hooks/use-crud";
 import { API_PATH } from "@/lib/api/common";
-import { Me } from "@/types/me";
+import { hasPermission, Me } from "@/types/me";
 
 const AnnouncementSection = ({ me }: { me: Me }) => {
   const [editingAnnouncement, setEditingAnnouncement] = useState<Announcement | null>(null);
@@ -40,7 +40,7 @@ const AnnouncementSection = ({ me }: { me: Me }) => {
   // 檢查是否有權限編輯公告
   const canEditAnnouncement = useCallback((announcement: Announcement) => {
     if (!me) return false;
-    return me.id === announcement.teacher_id || me.role === "super_admin";
+    return me.id === announcement.teacher_id || hasPermission(me, "announcements.manage");
   }, [me]);
 
   // 處理公告提交
@@ -6,6 +6,8 @@ import { toast } from "sonner";
 import { DateFieldsMap } from '@/types/common';
 import { convertDates } from '@/lib/data-convert';
 import { useAuthStore } from '@/stores/auth-store';
+import { authService } from '@/lib/api/client-auth';
+import { CSRF_HEADER, getCsrfToken } from '@/lib/api/common';
 
 const BASE_API_CONFIG = {
 	headers: {
@@ -39,6 +41,29 @@ interface SuccessMessage {
 	description: string;
 }
 
+// 每次送出前重新讀取 CSRF token，更新憑證後重送的請求才會帶到新的值
+const buildRequestInit = (options: RequestInit): RequestInit => ({
+	...BASE_API_CONFIG,
+	...options,
+	headers: {
+		...BASE_API_CONFIG.headers,
+		...options.headers,
+		[CSRF_HEADER]: getCsrfToken(),
+	},
+});
+
+// 同時有多個請求收到 401 時，只發送一次更新憑證的請求
+let refreshPromise: Promise<boolean> | null = null;
+
+const refreshSession = () => {
+	if (!refreshPromise) {
+		refreshPromise = authService.refresh().finally(() => {
+			refreshPromise = null;
+		});
+	}
+	return refreshPromise;
+};
+
 class AuthenticationError extends Error {
 	constructor(message: string) {
 		super(message);
@@ -57,10 +82,11 @@ export const useApiRequest = () => {
 	}: ApiRequestParams<T>): Promise<AppResponse<T>> => {
 		let response: Response;
 		try {
-			response = await fetch(url, {
-				...BASE_API_CONFIG,
-				...options,
-			});
+			response = await fetch(url, buildRequestInit(options));
+
+			if (response.status === 401 && await refreshSession()) {
+				response = await fetch(url, buildRequestInit(options));
+			}
 		} catch (networkError) {
 			console.error("Network Error:", networkError);
 			toast.error("網路錯誤", {
@@ -1,3 +1,9 @@
+import { CSRF_HEADER, getCsrfToken } from '@/lib/api/common';
+
+export interface LoginResult {
+  two_factor_required: boolean,
+}
+
 export const authService = {
   login: async (username: string, password: string) => {
     try {
@@ -10,23 +16,63 @@ export const authService = {
         body: JSON.stringify({ username, password }),
       });
 
+      const data = await response.json();
       if (!response.ok) {
-        const data = await response.json();
         throw new Error(data.message || '登入失敗');
       }
 
-      return response;
+      return data.data as LoginResult;
     } catch (error) {
       throw error instanceof Error ? error : new Error('無法連接到伺服器');
     }
   },
 
+  verifyTwoFactor: async (code: string) => {
+    try {
+      const response = await fetch(`${process.env.NEXT_PUBLIC_API_URL}/api/login/two-factor`, {
+        method: 'POST',
+        headers: {
+          'Content-Type': 'application/json',
+        },
+        credentials: 'include',
+        body: JSON.stringify({ code }),
+      });
+
+      const data = await response.json();
+      if (!response.ok) {
+        throw new Error(data.message || '驗證失敗');
+      }
+
+      return data.data as LoginResult;
+    } catch (error) {
+      throw error instanceof Error ? error : new Error('無法連接到伺服器');
+    }
+  },
+
+  refresh: async () => {
+    try {
+      const response = await fetch(`${process.env.NEXT_PUBLIC_API_URL}/api/refresh`, {
+        method: 'POST',
+        headers: {
+          [CSRF_HEADER]: getCsrfToken(),
+        },
+        credentials: 'include',
+      });
+
+      // 409 代表其他請求剛更新過憑證，新的 cookie 已經生效
+      return response.ok || response.status === 409;
+    } catch {
+      return false;
+    }
+  },
+
   logout: async () => {
     try {
       const response = await fetch(`${process.env.NEXT_PUBLIC_API_URL}/api/logout`, {
         method: 'POST',
         headers: {
           'Content-Type': 'application/json',
+          [CSRF_HEADER]: getCsrfToken(),
         },
         credentials: 'include',
       });
@@ -5,4 +5,13 @@ export const API_PATH = {
   studentGrades: `${process.env.NEXT_PUBLIC_API_URL}/api/grades`,
   announcements: `${process.env.NEXT_PUBLIC_API_URL}/api/announcements`,
   attendances: `${process.env.NEXT_PUBLIC_API_URL}/api/attendance-records`,  
-};
\ No newline at end of file
+};
+
+export const CSRF_HEADER = 'X-CSRF-Token';
+
+// 後端每次發出登入憑證時都會更換 csrf_token cookie，修改資料的請求需將它放進標頭
+export function getCsrfToken(): string {
+  if (typeof document === 'undefined') return '';
+  const cookie = document.cookie.split('; ').find((c) => c.startsWith('csrf_token='));
+  return cookie ? decodeURIComponent(cookie.slice('csrf_token='.length)) : '';
+}
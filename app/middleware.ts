@@ -1,27 +1,73 @@
 import { NextResponse } from 'next/server';
 import type { NextRequest } from 'next/server';
 
+// access token 到期前這段時間內就先更新，避免頁面渲染途中失效
+const REFRESH_LEEWAY_SECONDS = 30;
+
+function isTokenExpiring(token: string): boolean {
+  try {
+    const payload = JSON.parse(atob(token.split('.')[1].replace(/-/g, '+').replace(/_/g, '/')));
+    return payload.exp - REFRESH_LEEWAY_SECONDS < Date.now() / 1000;
+  } catch {
+    return true;
+  }
+}
+
+async function refreshTokens(request: NextRequest): Promise<string[] | null> {
+  try {
+    const res = await fetch(`${process.env.NEXT_PUBLIC_API_URL}/api/refresh`, {
+      method: 'POST',
+      headers: {
+        cookie: request.headers.get('cookie') ?? '',
+        'X-CSRF-Token': request.cookies.get('csrf_token')?.value ?? '',
+      },
+      cache: 'no-store',
+    });
+
+    if (!res.ok) return null;
+    return res.headers.getSetCookie();
+  } catch {
+    return null;
+  }
+}
+
 export async function middleware(request: NextRequest) {
   const path = request.nextUrl.pathname;
   const isPublicPath = path === '/login';
-  const token = request.cookies.get('auth_token')?.value;
+  let token = request.cookies.get('auth_token')?.value;
+  const refreshToken = request.cookies.get('refresh_token')?.value;
 
-  if (isPublicPath && token) {
-    return NextResponse.redirect(new URL('/', request.url));
+  let setCookies: string[] | null = null;
+  if (refreshToken && (!token || isTokenExpiring(token))) {
+    setCookies = await refreshTokens(request);
+    if (setCookies) {
+      // 將新的 cookie 同時帶給這次的頁面渲染與瀏覽器
+      for (const setCookie of setCookies) {
+        const [pair] = setCookie.split(';');
+        const index = pair.indexOf('=');
+        request.cookies.set(pair.slice(0, index), pair.slice(index + 1));
+      }
+      token = request.cookies.get('auth_token')?.value;
+    }
   }
 
-  if (!isPublicPath && !token) {
+  let response: NextResponse;
+  if (isPublicPath && token) {
+    response = NextResponse.redirect(new URL('/', request.url));
+  } else if (path === '/' && !token) {
     const from = new URL('/login', request.url);
     from.searchParams.set('from', path);
-    return NextResponse.redirect(from);
+    response = NextResponse.redirect(from);
+  } else {
+    response = NextResponse.next({ request });
   }
 
-  return NextResponse.next();
+  setCookies?.forEach((setCookie) => response.headers.append('set-cookie', setCookie));
+  return response;
 }
 
 export const config = {
   matcher: [
-    '/',
-    '/login',
+    '/((?!_next/static|_next/image|favicon.ico).*)',
   ]
-};
\ No newline at end of file
+};
@@ -2,6 +2,14 @@ export interface Me {
   id: string,
   username: string,
   name: string,
-  role: 'super_admin' | 'admin' | 'user',
+  role: string,
+  permissions: string[],
+  must_change_password: boolean,
+  two_factor_enabled: boolean,
+  must_enroll_two_factor: boolean,
   exp: number,
-}
\ No newline at end of file
+}
+
+export function hasPermission(me: Me | null | undefined, permission: string): boolean {
+  return !!me && me.permissions.includes(permission);
+}